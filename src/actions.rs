@@ -8,9 +8,27 @@ pub enum Action {
     PlayPause,
     RateDown,
     RateUp,
+    ExportWav,
+    NextSentence,
+    PreviousSentence,
+    NextParagraph,
+    PreviousParagraph,
+    SeekForward,
+    SeekBack,
+    Enqueue,
+    NextInQueue,
+    ClearQueue,
+    AnnounceQueue,
+    HistoryBack,
+    HistoryForward,
+    ShowHistory,
+    ResumeBookmark,
+    ToggleTicker,
+    ToggleSpeakOnCopy,
+    ReadClipboard,
 }
 
-pub const ACTION_LIST: [Action; 8] = [
+pub const ACTION_LIST: [Action; 26] = [
     Action::Read,
     Action::Close,
     Action::ReloadSettings,
@@ -19,6 +37,24 @@ pub const ACTION_LIST: [Action; 8] = [
     Action::PlayPause,
     Action::RateDown,
     Action::RateUp,
+    Action::ExportWav,
+    Action::NextSentence,
+    Action::PreviousSentence,
+    Action::NextParagraph,
+    Action::PreviousParagraph,
+    Action::SeekForward,
+    Action::SeekBack,
+    Action::Enqueue,
+    Action::NextInQueue,
+    Action::ClearQueue,
+    Action::AnnounceQueue,
+    Action::HistoryBack,
+    Action::HistoryForward,
+    Action::ShowHistory,
+    Action::ResumeBookmark,
+    Action::ToggleTicker,
+    Action::ToggleSpeakOnCopy,
+    Action::ReadClipboard,
 ];
 
 #[test]
@@ -45,6 +81,24 @@ impl ::std::fmt::Display for Action {
             PlayPause => write!(f, "play_pause"),
             RateDown => write!(f, "rate_down"),
             RateUp => write!(f, "rate_up"),
+            ExportWav => write!(f, "export_wav"),
+            NextSentence => write!(f, "next_sentence"),
+            PreviousSentence => write!(f, "previous_sentence"),
+            NextParagraph => write!(f, "next_paragraph"),
+            PreviousParagraph => write!(f, "previous_paragraph"),
+            SeekForward => write!(f, "seek_forward"),
+            SeekBack => write!(f, "seek_back"),
+            Enqueue => write!(f, "enqueue"),
+            NextInQueue => write!(f, "next_in_queue"),
+            ClearQueue => write!(f, "clear_queue"),
+            AnnounceQueue => write!(f, "announce_queue"),
+            HistoryBack => write!(f, "history_back"),
+            HistoryForward => write!(f, "history_forward"),
+            ShowHistory => write!(f, "show_history"),
+            ResumeBookmark => write!(f, "resume_bookmark"),
+            ToggleTicker => write!(f, "toggle_ticker"),
+            ToggleSpeakOnCopy => write!(f, "toggle_speak_on_copy"),
+            ReadClipboard => write!(f, "read_clipboard"),
         }
     }
 }
@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local};
+use preferences::Preferences;
+use rust_reader::wide_string::WideString;
+use serde::{Deserialize, Serialize};
+
+use crate::settings::APP_INFO;
+
+/// Shorter texts are quick to read again, so they get no bookmark.
+pub const MIN_BOOKMARK_LEN: usize = 1000;
+
+/// A stable hash of the cleaned text, so the same text finds its bookmark after a restart.
+pub fn bookmark_key(text: &WideString) -> Option<String> {
+    if text.len() - 1 < MIN_BOOKMARK_LEN {
+        return None;
+    }
+    // FNV-1a, `DefaultHasher` may change between rust versions.
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for c in text.as_string().encode_utf16() {
+        for b in c.to_le_bytes() {
+            hash ^= u64::from(b);
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    Some(format!("{:016x}", hash))
+}
+
+/// Where reading stopped.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Bookmark {
+    /// In utf16 units of the cleaned text.
+    pub position: usize,
+    pub len: usize,
+    pub saved: DateTime<Local>,
+}
+
+impl Bookmark {
+    pub fn percent(&self) -> f64 {
+        100.0 * (self.position as f64) / (self.len.max(1) as f64)
+    }
+}
+
+/// The bookmarks of all the long texts, by `bookmark_key`.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Bookmarks {
+    marks: HashMap<String, Bookmark>,
+    /// Only bookmarks loaded with `from_file` are saved.
+    #[serde(skip)]
+    persistent: bool,
+    #[serde(skip)]
+    last_saved: Option<Instant>,
+}
+
+impl Bookmarks {
+    /// Stored next to the settings, see `Settings::get_dir`.
+    pub fn from_file() -> Bookmarks {
+        let mut out = Bookmarks::load(&APP_INFO, "bookmarks").unwrap_or_else(|_| {
+            println!("failed to lode bookmarks.");
+            Bookmarks::default()
+        });
+        out.persistent = true;
+        out
+    }
+
+    pub fn store(&mut self) {
+        if !self.persistent {
+            return;
+        }
+        self.last_saved = Some(Instant::now());
+        if self.save(&APP_INFO, "bookmarks").is_err() {
+            println!("failed to save bookmarks.");
+        }
+    }
+
+    /// Save if it has been `every` since the last save, so not much is lost in a crash.
+    pub fn store_every(&mut self, every: Duration) {
+        if !matches!(self.last_saved, Some(t) if t.elapsed() < every) {
+            self.store();
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Bookmark> {
+        self.marks.get(key)
+    }
+
+    pub fn set(&mut self, key: &str, position: usize, len: usize) {
+        self.marks.insert(
+            key.to_string(),
+            Bookmark {
+                position,
+                len,
+                saved: Local::now(),
+            },
+        );
+    }
+
+    /// The text was read to the end, so there is nothing to resume.
+    pub fn remove(&mut self, key: &str) {
+        self.marks.remove(key);
+    }
+
+    /// Forget bookmarks older than `days`.
+    pub fn prune(&mut self, days: i64) {
+        let cutoff = Local::now() - chrono::Duration::days(days);
+        self.marks.retain(|_, b| b.saved >= cutoff);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys() {
+        assert_eq!(bookmark_key(&"short".into()), None);
+        let long: WideString = "word ".repeat(MIN_BOOKMARK_LEN).into();
+        let key = bookmark_key(&long).unwrap();
+        assert_eq!(key.len(), 16);
+        assert_eq!(bookmark_key(&long), Some(key.clone()));
+        let other: WideString = "Word ".repeat(MIN_BOOKMARK_LEN).into();
+        assert_ne!(bookmark_key(&other), Some(key));
+    }
+
+    #[test]
+    fn set_remove_and_prune() {
+        let mut marks = Bookmarks::default();
+        marks.set("a", 50, 200);
+        marks.set("b", 10, 200);
+        assert_eq!(marks.get("a").unwrap().percent(), 25.0);
+        marks.remove("b");
+        assert!(marks.get("b").is_none());
+        marks.prune(1);
+        assert!(marks.get("a").is_some());
+        marks.marks.get_mut("a").unwrap().saved = Local::now() - chrono::Duration::days(2);
+        marks.prune(1);
+        assert!(marks.get("a").is_none());
+        // not loaded from a file, so not saved to one
+        marks.store_every(Duration::ZERO);
+        assert!(marks.last_saved.is_none());
+    }
+}
@@ -0,0 +1,156 @@
+use aho_corasick::{AhoCorasick, MatchKind};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// How to say one word, or a few words.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Pronunciation {
+    pub word: String,
+    /// A respelling the voice says right.
+    pub say: String,
+    /// Otherwise case is ignored, for ASCII letters.
+    #[serde(default)]
+    pub case_sensitive: bool,
+    /// Otherwise `word` also matches inside longer words.
+    #[serde(default = "default_whole_word")]
+    pub whole_word: bool,
+}
+
+fn default_whole_word() -> bool {
+    true
+}
+
+/// Add `entry` to `entries`, replacing the one for the same word if there is one.
+pub fn insert_pronunciation(entries: &mut Vec<Pronunciation>, entry: Pronunciation) {
+    match entries
+        .iter()
+        .position(|e| e.word == entry.word && e.case_sensitive == entry.case_sensitive)
+    {
+        Some(i) => entries[i] = entry,
+        None => entries.push(entry),
+    }
+}
+
+impl Pronunciation {
+    pub fn new<T: Into<String>, S: Into<String>>(word: T, say: S) -> Pronunciation {
+        Pronunciation {
+            word: word.into(),
+            say: say.into(),
+            case_sensitive: false,
+            whole_word: true,
+        }
+    }
+}
+
+/// A list of words to say differently, matched all at once so it stays fast when it is long.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(from = "Vec<Pronunciation>", into = "Vec<Pronunciation>")]
+pub struct Dictionary {
+    entries: Vec<Pronunciation>,
+    automaton: Option<AhoCorasick>,
+    /// For each pattern of `automaton`, the `entries` it is the word of.
+    patterns: Vec<Vec<usize>>,
+}
+
+impl Dictionary {
+    pub fn new(entries: Vec<Pronunciation>) -> Dictionary {
+        let mut words: Vec<String> = Vec::new();
+        let mut patterns: Vec<Vec<usize>> = Vec::new();
+        for (i, e) in entries.iter().enumerate() {
+            if e.word.is_empty() {
+                continue;
+            }
+            // matched without case, the entries check it if they care
+            let word = e.word.to_ascii_lowercase();
+            match words.iter().position(|w| *w == word) {
+                Some(p) => patterns[p].push(i),
+                None => {
+                    words.push(word);
+                    patterns.push(vec![i]);
+                }
+            }
+        }
+        let automaton = if words.is_empty() {
+            None
+        } else {
+            Some(
+                AhoCorasick::builder()
+                    .ascii_case_insensitive(true)
+                    .match_kind(MatchKind::Standard)
+                    .build(&words)
+                    .expect("failed to build pronunciation dictionary"),
+            )
+        };
+        Dictionary {
+            entries,
+            automaton,
+            patterns,
+        }
+    }
+
+    pub fn entries(&self) -> &[Pronunciation] {
+        &self.entries
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.automaton.is_none()
+    }
+
+    /// The u8 ranges of `text` to replace, and what to say instead, in order and not overlapping.
+    ///
+    /// Where matches overlap the one that starts first wins, then the longest.
+    pub fn find<'d>(&'d self, text: &str) -> Vec<(Range<usize>, &'d str)> {
+        let automaton = match &self.automaton {
+            Some(a) => a,
+            None => return Vec::new(),
+        };
+        let mut found: Vec<(Range<usize>, &str)> = automaton
+            .find_overlapping_iter(text)
+            .filter_map(|m| {
+                let range = m.start()..m.end();
+                self.patterns[m.pattern().as_usize()]
+                    .iter()
+                    .map(|&i| &self.entries[i])
+                    .find(|e| {
+                        (!e.case_sensitive || text[range.clone()] == e.word)
+                            && (!e.whole_word || is_whole_word(text, &range))
+                    })
+                    .map(|e| (range, e.say.as_str()))
+            })
+            .collect();
+        found.sort_by_key(|(r, _)| (r.start, usize::MAX - r.end));
+        let mut end = 0;
+        found.retain(|(r, _)| {
+            let keep = r.start >= end;
+            if keep {
+                end = r.end;
+            }
+            keep
+        });
+        found
+    }
+}
+
+fn is_whole_word(text: &str, range: &Range<usize>) -> bool {
+    let before = text[..range.start].chars().next_back();
+    let after = text[range.end..].chars().next();
+    !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+}
+
+impl From<Vec<Pronunciation>> for Dictionary {
+    fn from(entries: Vec<Pronunciation>) -> Dictionary {
+        Dictionary::new(entries)
+    }
+}
+
+impl From<Dictionary> for Vec<Pronunciation> {
+    fn from(dictionary: Dictionary) -> Vec<Pronunciation> {
+        dictionary.entries
+    }
+}
+
+impl PartialEq for Dictionary {
+    fn eq(&self, other: &Dictionary) -> bool {
+        self.entries == other.entries
+    }
+}
@@ -1,11 +1,18 @@
 use regex::*;
 use std::borrow::Cow;
+use std::ops::Range;
 use unicode_segmentation::*;
 use crate::wide_string::*;
 
 mod regex_cleaner_pair;
 pub use self::regex_cleaner_pair::*;
 
+mod dictionary;
+pub use self::dictionary::*;
+
+mod pls;
+pub use self::pls::*;
+
 // // un comment and add #![feature(test)] to main to benchmark
 // #[cfg(test)]
 // mod bench;
@@ -162,6 +169,101 @@ where
     }
 }
 
+/// Say the words of `dictionary` instead, in the `pairs` of `raw` that the cleaners left alone.
+///
+/// A cleaner that only put whitespace for whitespace does not hide the words around it,
+/// so entries of more than one word still match.
+fn dictionary_replace<'a, I>(raw: &'a str, pairs: I, dictionary: &'a Dictionary) -> Vec<Pair<'a>>
+where
+    I: 'a + Iterator<Item = Pair<'a>>,
+{
+    let mut out = Vec::new();
+    let mut run = Vec::new();
+    for pair in pairs {
+        let spacing = match &pair.1 {
+            None => true,
+            Some(r) => pair.0.trim().is_empty() && r.trim().is_empty(),
+        };
+        if spacing {
+            run.push(pair);
+        } else {
+            out.extend(dictionary_run(raw, std::mem::take(&mut run), dictionary));
+            out.push(pair);
+        }
+    }
+    out.extend(dictionary_run(raw, run, dictionary));
+    out
+}
+
+/// `dictionary_replace` on pairs next to each other in `raw`, matching the text as it was written.
+fn dictionary_run<'a>(
+    raw: &'a str,
+    run: Vec<Pair<'a>>,
+    dictionary: &'a Dictionary,
+) -> Vec<Pair<'a>> {
+    let (Some(first), Some(last)) = (run.first(), run.last()) else {
+        return run;
+    };
+    // the pairs are slices of `raw`, one after the other
+    let start = first.0.as_ptr() as usize - raw.as_ptr() as usize;
+    let end = last.0.as_ptr() as usize - raw.as_ptr() as usize + last.0.len();
+    let text = &raw[start..end];
+    let mut ranges = Vec::with_capacity(run.len());
+    let mut at = 0;
+    for (orig, _) in &run {
+        ranges.push(at..at + orig.len());
+        at += orig.len();
+    }
+    // a match can not start or end in the middle of what a cleaner replaced
+    let inside_replaced = |i: usize| {
+        let p = ranges.partition_point(|r| r.end <= i);
+        p < run.len() && run[p].1.is_some() && ranges[p].start < i
+    };
+    let mut out = Vec::new();
+    let mut next = 0;
+    let mut at = 0;
+    for (range, say) in dictionary.find(text) {
+        if inside_replaced(range.start) || inside_replaced(range.end) {
+            continue;
+        }
+        keep_pairs(text, &run, &ranges, &mut next, at..range.start, &mut out);
+        out.push((&text[range.clone()], Some(say.into())));
+        while next < run.len() && ranges[next].end <= range.end {
+            next += 1;
+        }
+        at = range.end;
+    }
+    keep_pairs(text, &run, &ranges, &mut next, at..text.len(), &mut out);
+    out
+}
+
+/// The parts of the pairs from `next` on that are in `span`, which only cuts ones that were not replaced.
+fn keep_pairs<'a>(
+    text: &'a str,
+    run: &[Pair<'a>],
+    ranges: &[Range<usize>],
+    next: &mut usize,
+    span: Range<usize>,
+    out: &mut Vec<Pair<'a>>,
+) {
+    while *next < run.len() && (ranges[*next].start < span.end || span.end == text.len()) {
+        let range = &ranges[*next];
+        match &run[*next].1 {
+            Some(_) => out.push(run[*next].clone()),
+            None => {
+                let part = range.start.max(span.start)..range.end.min(span.end);
+                if !part.is_empty() {
+                    out.push((&text[part], None));
+                }
+            }
+        }
+        if range.end > span.end {
+            break;
+        }
+        *next += 1;
+    }
+}
+
 fn running_count<'a>(st: &mut (&'a str, usize), ch: &'a str) -> Option<Pair<'a>> {
     if st.0 != ch {
         st.1 = 0;
@@ -193,19 +295,60 @@ fn trivial_pair<'a>(text: &'a str) -> Box<dyn Iterator<Item = Pair<'a>> + 'a> {
 fn clean_iter<'r: 'a, 'a>(
     raw: &'a str,
     list: &'r [RegexCleanerPair],
+    dictionary: Option<&'r Dictionary>,
 ) -> Box<dyn Iterator<Item = Pair<'a>> + 'a> {
     let mut out = trivial_pair(raw);
     for reg in list.iter() {
         out = regex_replace(out, reg);
     }
+    // last, so what the cleaners replaced, like a link, is left alone
+    if let Some(dictionary) = dictionary.filter(|d| !d.is_empty()) {
+        out = Box::new(dictionary_replace(raw, out, dictionary).into_iter());
+    }
     Box::new(graphemes_pair(out))
 }
 
+/// The default list of cleaners, as used by a new install of the reader.
+pub fn default_cleaners() -> Vec<RegexCleanerPair> {
+    RegexCleanerPair::prep_list(&[
+        (r"\s+", " "),
+        (
+            concat!(
+                r"(https?://)?(?P<a>[-a-zA-Z0-9@:%._\+~#=]{2,256}",
+                r"\.[a-z]{2,6})\b[-a-zA-Z0-9@:%_\+.~#?&//=]{10,}"
+            ),
+            "link to $a",
+        ),
+        (
+            r"(?P<s>[0-9a-f]{6})([0-9]+[a-f]|[a-f]+[0-9])[0-9a-f]*",
+            "hash $s",
+        ),
+    ])
+    .unwrap()
+}
+
+/// Apply each of the cleaners in order, then shorten runs of repeated characters.
 pub fn clean_text<'r: 'a, 'a, O>(raw: &'a str, list: &'r [RegexCleanerPair]) -> O
 where
     O: ::std::iter::FromIterator<Cow<'a, str>>,
 {
-    clean_iter(raw, list)
+    clean_iter(raw, list, None)
+        .map(|(o, r)| r.unwrap_or_else(|| o.into()))
+        .collect()
+}
+
+/// Clean like `clean_text`, and say the words in the dictionary as it says to.
+///
+/// This is exactly the text the reader speaks.
+pub fn clean_text_with_dictionary<'r: 'a, 'a, O>(
+    raw: &'a str,
+    list: &'r [RegexCleanerPair],
+    dictionary: &'r Dictionary,
+) -> O
+where
+    O: ::std::iter::FromIterator<Cow<'a, str>>,
+{
+    clean_iter(raw, list, Some(dictionary))
         .map(|(o, r)| r.unwrap_or_else(|| o.into()))
         .collect()
 }
@@ -214,14 +357,15 @@ fn clean_text_idx<'r: 'a, 'a, F>(
     raw: &'a str,
     len: F,
     list: &'r [RegexCleanerPair],
+    dictionary: Option<&'r Dictionary>,
 ) -> Box<dyn Iterator<Item = (usize, usize)> + 'a>
 where
     F: 'a + Fn(&str) -> usize,
 {
     Box::new(
         (0..1).map(|x| (x, x)).chain(
-            clean_iter(raw, list)
-                .map(move |(o, r)| (len(o), len(&*r.unwrap_or_else(|| o.into()))))
+            clean_iter(raw, list, dictionary)
+                .map(move |(o, r)| (len(o), len(&r.unwrap_or_else(|| o.into()))))
                 .scan((0, 0), move |st, x| {
                     st.0 += x.0;
                     st.1 += x.1;
@@ -231,30 +375,86 @@ where
     )
 }
 
-#[allow(dead_code)]
+/// For each segment boundary in the cleaned text, the matching u8 index in `raw`.
 pub fn clean_text_u8idx_in<T: AsRef<str>>(raw: T, list: &[RegexCleanerPair]) -> Vec<usize> {
-    clean_text_idx(raw.as_ref(), LenUtf::len_utf8, list)
+    clean_text_idx(raw.as_ref(), LenUtf::len_utf8, list, None)
         .map(|(s, _)| s)
         .collect()
 }
 
-#[allow(dead_code)]
+/// For each segment boundary in the cleaned text, the matching u16 index in `raw`.
 pub fn clean_text_u16idx_in<T: AsRef<str>>(raw: T, list: &[RegexCleanerPair]) -> Vec<usize> {
-    clean_text_idx(raw.as_ref(), LenUtf::len_utf16, list)
+    clean_text_idx(raw.as_ref(), LenUtf::len_utf16, list, None)
         .map(|(s, _)| s)
         .collect()
 }
 
-#[allow(dead_code)]
+/// For each segment boundary in the cleaned text, its u8 index in the cleaned text.
+///
+/// Use with `clean_text_u8idx_in` and `invert_idx` to map a range in the
+/// cleaned text back to the raw text.
 pub fn clean_text_u8idx_out<T: AsRef<str>>(raw: T, list: &[RegexCleanerPair]) -> Vec<usize> {
-    clean_text_idx(raw.as_ref(), LenUtf::len_utf8, list)
+    clean_text_idx(raw.as_ref(), LenUtf::len_utf8, list, None)
         .map(|(_, s)| s)
         .collect()
 }
 
-#[allow(dead_code)]
+/// For each segment boundary in the cleaned text, its u16 index in the cleaned text.
+///
+/// Use with `clean_text_u16idx_in` and `invert_idx` to map a range in the
+/// cleaned text back to the raw text.
 pub fn clean_text_u16idx_out<T: AsRef<str>>(raw: T, list: &[RegexCleanerPair]) -> Vec<usize> {
-    clean_text_idx(raw.as_ref(), LenUtf::len_utf16, list)
+    clean_text_idx(raw.as_ref(), LenUtf::len_utf16, list, None)
+        .map(|(_, s)| s)
+        .collect()
+}
+
+/// `clean_text_u8idx_in` for `clean_text_with_dictionary`.
+pub fn clean_text_u8idx_in_with_dictionary<T: AsRef<str>>(
+    raw: T,
+    list: &[RegexCleanerPair],
+    dictionary: &Dictionary,
+) -> Vec<usize> {
+    clean_text_idx(raw.as_ref(), LenUtf::len_utf8, list, Some(dictionary))
+        .map(|(s, _)| s)
+        .collect()
+}
+
+/// `clean_text_u16idx_in` for `clean_text_with_dictionary`.
+pub fn clean_text_u16idx_in_with_dictionary<T: AsRef<str>>(
+    raw: T,
+    list: &[RegexCleanerPair],
+    dictionary: &Dictionary,
+) -> Vec<usize> {
+    clean_text_idx(raw.as_ref(), LenUtf::len_utf16, list, Some(dictionary))
+        .map(|(s, _)| s)
+        .collect()
+}
+
+/// `clean_text_u8idx_out` for `clean_text_with_dictionary`.
+///
+/// A replacement is one segment, use `invert_idx_covering` so that part of it
+/// maps back to all of what it replaced.
+pub fn clean_text_u8idx_out_with_dictionary<T: AsRef<str>>(
+    raw: T,
+    list: &[RegexCleanerPair],
+    dictionary: &Dictionary,
+) -> Vec<usize> {
+    clean_text_idx(raw.as_ref(), LenUtf::len_utf8, list, Some(dictionary))
+        .map(|(_, s)| s)
+        .collect()
+}
+
+/// `clean_text_u16idx_out` for `clean_text_with_dictionary`.
+///
+/// A replacement is one segment, use `invert_idx_covering` so that part of it
+/// maps back to all of what it replaced.
+pub fn clean_text_u16idx_out_with_dictionary<T: AsRef<str>>(
+    raw: T,
+    list: &[RegexCleanerPair],
+    dictionary: &Dictionary,
+) -> Vec<usize> {
+    clean_text_idx(raw.as_ref(), LenUtf::len_utf16, list, Some(dictionary))
         .map(|(_, s)| s)
         .collect()
 }
@@ -0,0 +1,328 @@
+//! Read and write W3C Pronunciation Lexicon Specification files.
+//!
+//! Only `<alias>` can be used, the text is spoken as plain text so the voice is never given a `<phoneme>`.
+
+use super::Pronunciation;
+
+const PLS_NAMESPACE: &str = "http://www.w3.org/2005/01/pronunciation-lexicon";
+
+/// What was found in a PLS file.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Lexicon {
+    pub entries: Vec<Pronunciation>,
+    /// The `xml:lang` of the lexicon.
+    pub lang: Option<String>,
+    /// How many lexemes had no `<alias>`, only phonemes.
+    pub skipped: usize,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Token<'a> {
+    Open {
+        name: &'a str,
+        attrs: Vec<(&'a str, String)>,
+        empty: bool,
+    },
+    Close(&'a str),
+    Text(String),
+}
+
+/// Parse a PLS file, each grapheme of a lexeme becomes an entry saying its first alias.
+pub fn read_pls(xml: &str) -> Result<Lexicon, String> {
+    let mut out = Lexicon::default();
+    let mut stack: Vec<&str> = Vec::new();
+    let mut graphemes: Vec<String> = Vec::new();
+    let mut aliases: Vec<String> = Vec::new();
+    for token in tokens(xml)? {
+        match token {
+            Token::Open { name, attrs, empty } => {
+                let name = local_name(name);
+                if stack.is_empty() {
+                    if name != "lexicon" {
+                        return Err(format!("expected <lexicon>, found <{}>", name));
+                    }
+                    out.lang = attrs
+                        .into_iter()
+                        .find(|(n, _)| *n == "xml:lang")
+                        .map(|(_, v)| v);
+                }
+                match name {
+                    "lexeme" => {
+                        graphemes.clear();
+                        aliases.clear();
+                    }
+                    "grapheme" => graphemes.push(String::new()),
+                    "alias" => aliases.push(String::new()),
+                    _ => {}
+                }
+                if !empty {
+                    stack.push(name);
+                }
+            }
+            Token::Close(name) => {
+                let name = local_name(name);
+                if stack.pop() != Some(name) {
+                    return Err(format!("unexpected </{}>", name));
+                }
+                if name == "lexeme" {
+                    match aliases.iter().map(|a| a.trim()).find(|a| !a.is_empty()) {
+                        Some(alias) => out.entries.extend(
+                            graphemes
+                                .iter()
+                                .map(|g| g.trim())
+                                .filter(|g| !g.is_empty())
+                                .map(|g| Pronunciation::new(g, alias)),
+                        ),
+                        None => out.skipped += 1,
+                    }
+                }
+            }
+            Token::Text(text) => match stack.last() {
+                Some(&"grapheme") => graphemes.last_mut().unwrap().push_str(&text),
+                Some(&"alias") => aliases.last_mut().unwrap().push_str(&text),
+                _ => {}
+            },
+        }
+    }
+    if let Some(name) = stack.last() {
+        return Err(format!("<{}> is not closed", name));
+    }
+    Ok(out)
+}
+
+/// A PLS file with a lexeme for each entry.
+///
+/// PLS has no way to say an entry is only part of a word, so those are left out.
+/// Case sensitivity is not kept either.
+/// Only the first entry for each word is written, so list the ones that should win first.
+pub fn write_pls<'a, I: IntoIterator<Item = &'a Pronunciation>>(entries: I, lang: &str) -> String {
+    let mut out = String::new();
+    out += "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n";
+    out += &format!(
+        "<lexicon version=\"1.0\" xmlns=\"{}\" alphabet=\"ipa\" xml:lang=\"{}\">\n",
+        PLS_NAMESPACE,
+        escape(lang)
+    );
+    let mut written = std::collections::HashSet::new();
+    for e in entries.into_iter().filter(|e| e.whole_word) {
+        if !written.insert(e.word.as_str()) {
+            continue;
+        }
+        out += &format!(
+            "  <lexeme>\n    <grapheme>{}</grapheme>\n    <alias>{}</alias>\n  </lexeme>\n",
+            escape(&e.word),
+            escape(&e.say)
+        );
+    }
+    out += "</lexicon>\n";
+    out
+}
+
+fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn unescape(text: &str) -> Result<String, String> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(i) = rest.find('&') {
+        out += &rest[..i];
+        let end = rest[i..]
+            .find(';')
+            .ok_or_else(|| format!("unfinished entity in {:?}", text))?;
+        let entity = &rest[i + 1..i + end];
+        let c = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => match entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+            {
+                Some(hex) => u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+                None => entity
+                    .strip_prefix('#')
+                    .and_then(|dec| dec.parse().ok())
+                    .and_then(char::from_u32),
+            },
+        };
+        out.push(c.ok_or_else(|| format!("unknown entity &{};", entity))?);
+        rest = &rest[i + end + 1..];
+    }
+    out += rest;
+    Ok(out)
+}
+
+/// Just enough XML for PLS, no DTDs.
+fn tokens(xml: &str) -> Result<Vec<Token<'_>>, String> {
+    fn skip_past<'a>(rest: &'a str, end: &str) -> Result<(&'a str, &'a str), String> {
+        let i = rest.find(end).ok_or_else(|| format!("missing {:?}", end))?;
+        Ok((&rest[..i], &rest[i + end.len()..]))
+    }
+
+    /// Like `skip_past(rest, ">")`, but a `>` in a quoted attribute does not end the tag.
+    fn skip_tag(rest: &str) -> Result<(&str, &str), String> {
+        let mut quote = None;
+        for (i, c) in rest.char_indices() {
+            match quote {
+                None if c == '>' => return Ok((&rest[..i], &rest[i + 1..])),
+                None if c == '"' || c == '\'' => quote = Some(c),
+                Some(q) if q == c => quote = None,
+                _ => {}
+            }
+        }
+        Err(format!("missing {:?}", ">"))
+    }
+
+    let mut out = Vec::new();
+    let mut rest = xml.trim_start_matches('\u{feff}');
+    while !rest.is_empty() {
+        if let Some(r) = rest.strip_prefix("<!--") {
+            rest = skip_past(r, "-->")?.1;
+        } else if let Some(r) = rest.strip_prefix("<![CDATA[") {
+            let (text, r) = skip_past(r, "]]>")?;
+            out.push(Token::Text(text.to_string()));
+            rest = r;
+        } else if let Some(r) = rest.strip_prefix("<?") {
+            rest = skip_past(r, "?>")?.1;
+        } else if let Some(r) = rest.strip_prefix("<!") {
+            rest = skip_tag(r)?.1;
+        } else if let Some(r) = rest.strip_prefix("</") {
+            let (name, r) = skip_past(r, ">")?;
+            out.push(Token::Close(name.trim()));
+            rest = r;
+        } else if let Some(r) = rest.strip_prefix('<') {
+            let (tag, r) = skip_tag(r)?;
+            let (tag, empty) = match tag.strip_suffix('/') {
+                Some(t) => (t, true),
+                None => (tag, false),
+            };
+            let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+            let (name, mut attrs_text) = tag.split_at(name_end);
+            let mut attrs = Vec::new();
+            loop {
+                attrs_text = attrs_text.trim_start();
+                if attrs_text.is_empty() {
+                    break;
+                }
+                let (attr, value) = attrs_text
+                    .split_once('=')
+                    .ok_or_else(|| format!("bad attribute in <{}>", name))?;
+                let value = value.trim_start();
+                let quote = value
+                    .chars()
+                    .next()
+                    .filter(|q| *q == '"' || *q == '\'')
+                    .ok_or_else(|| format!("unquoted attribute in <{}>", name))?;
+                let (value, r) = skip_past(&value[1..], &quote.to_string())?;
+                attrs.push((attr.trim(), unescape(value)?));
+                attrs_text = r;
+            }
+            out.push(Token::Open { name, attrs, empty });
+            rest = r;
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            out.push(Token::Text(unescape(&rest[..end])?));
+            rest = &rest[end..];
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE lexicon>
+<lexicon version="1.0"
+      xmlns="http://www.w3.org/2005/01/pronunciation-lexicon"
+      alphabet="ipa" xml:lang="en-GB">
+  <!-- the first one -->
+  <lexeme>
+    <grapheme>W3C</grapheme>
+    <grapheme>w3c</grapheme>
+    <alias>World Wide Web Consortium</alias>
+  </lexeme>
+  <lexeme>
+    <grapheme>tomato</grapheme>
+    <phoneme>t&#601;&#712;m&#593;&#720;t&#601;&#650;</phoneme>
+  </lexeme>
+  <lexeme>
+    <grapheme>AT&amp;T</grapheme>
+    <alias><![CDATA[A T & T]]></alias>
+    <alias>ignored</alias>
+  </lexeme>
+  <lexeme><grapheme>empty alias</grapheme><alias/></lexeme>
+</lexicon>
+"#;
+
+    #[test]
+    fn read() {
+        let lexicon = read_pls(SAMPLE).unwrap();
+        assert_eq!(lexicon.lang.as_deref(), Some("en-GB"));
+        assert_eq!(
+            lexicon.entries,
+            vec![
+                Pronunciation::new("W3C", "World Wide Web Consortium"),
+                Pronunciation::new("w3c", "World Wide Web Consortium"),
+                Pronunciation::new("AT&T", "A T & T"),
+            ]
+        );
+        assert_eq!(lexicon.skipped, 2);
+    }
+
+    #[test]
+    fn not_pls() {
+        assert!(read_pls("<html><body/></html>").is_err());
+        assert!(read_pls("<lexicon><lexeme></lexicon>").is_err());
+        assert!(read_pls("<lexicon><lexeme><grapheme>a &nope; b").is_err());
+        assert!(read_pls("<lexicon xml:lang=\"en>").is_err());
+    }
+
+    #[test]
+    fn quoted_angle_bracket() {
+        let xml = r#"<lexicon xml:lang="en-GB" note='a > b' other="it's">
+  <lexeme note="<>"><grapheme>x</grapheme><alias>ex</alias></lexeme>
+</lexicon>"#;
+        let lexicon = read_pls(xml).unwrap();
+        assert_eq!(lexicon.lang.as_deref(), Some("en-GB"));
+        assert_eq!(lexicon.entries, vec![Pronunciation::new("x", "ex")]);
+    }
+
+    #[test]
+    fn round_trip() {
+        let entries = vec![
+            Pronunciation::new("AT&T", "A T and T"),
+            Pronunciation::new("<tag>", "\"quoted\""),
+            Pronunciation {
+                whole_word: false,
+                ..Pronunciation::new("ough", "oh")
+            },
+        ];
+        let xml = write_pls(&entries, "en-US");
+        let lexicon = read_pls(&xml).unwrap();
+        assert_eq!(lexicon.lang.as_deref(), Some("en-US"));
+        assert_eq!(lexicon.entries, entries[..2]);
+        assert_eq!(lexicon.skipped, 0);
+    }
+
+    #[test]
+    fn one_lexeme_per_word() {
+        let entries = vec![
+            Pronunciation::new("gif", "jif"),
+            Pronunciation::new("gif", "graphics interchange format"),
+        ];
+        let lexicon = read_pls(&write_pls(&entries, "en-US")).unwrap();
+        assert_eq!(lexicon.entries, entries[..1]);
+    }
+}
@@ -1,3 +1,4 @@
+use super::Pronunciation;
 use regex::*;
 use serde::de::{self, Deserialize, SeqAccess, Visitor};
 use serde::{Deserializer, Serialize, Serializer};
@@ -21,6 +22,7 @@ impl RegexCleanerPair {
             .map(|&(reg, rep)| RegexCleanerPair::new(reg, rep.to_string()))
             .collect()
     }
+    #[allow(clippy::needless_borrowed_reference)]
     pub fn to_parts(&self) -> (&Regex, &str) {
         let &RegexCleanerPair {
             regex: ref reg,
@@ -28,6 +30,56 @@ impl RegexCleanerPair {
         } = self;
         (reg, r)
     }
+    /// If this only replaces a word, like `(?i)\bword\b` with `respelling`, the same as a `Pronunciation`.
+    pub fn as_pronunciation(&self) -> Option<Pronunciation> {
+        let mut pattern = self.regex.as_str();
+        let case_sensitive = match pattern.strip_prefix("(?i)") {
+            Some(p) => {
+                pattern = p;
+                false
+            }
+            None => true,
+        };
+        let whole_word = match pattern
+            .strip_prefix(r"\b")
+            .and_then(|p| p.strip_suffix(r"\b"))
+        {
+            Some(p) => {
+                pattern = p;
+                true
+            }
+            None => false,
+        };
+        let word = unescape_literal(pattern)?;
+        if word.is_empty() || self.rep.contains('$') {
+            return None;
+        }
+        Some(Pronunciation {
+            word,
+            say: self.rep.clone(),
+            case_sensitive,
+            whole_word,
+        })
+    }
+}
+
+/// The text `pattern` matches, if it only matches one text.
+fn unescape_literal(pattern: &str) -> Option<String> {
+    let mut out = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(e) if e.is_ascii_punctuation() => out.push(e),
+                _ => return None,
+            },
+            '.' | '^' | '$' | '*' | '+' | '?' | '(' | ')' | '[' | ']' | '{' | '}' | '|' => {
+                return None
+            }
+            _ => out.push(c),
+        }
+    }
+    Some(out)
 }
 
 impl Serialize for RegexCleanerPair {
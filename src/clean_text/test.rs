@@ -1,9 +1,23 @@
-use once_cell::sync::Lazy;
 use super::*;
+use once_cell::sync::Lazy;
 use quickcheck::quickcheck;
 
-pub static RE_LIST: Lazy<Vec<RegexCleanerPair>> = Lazy::new(|| {
-    crate::settings::Settings::new().cleaners
+pub static RE_LIST: Lazy<Vec<RegexCleanerPair>> = Lazy::new(default_cleaners);
+
+pub static WORDS: Lazy<Dictionary> = Lazy::new(|| {
+    Dictionary::new(vec![
+        Pronunciation::new("Hello", "heh low"),
+        Pronunciation {
+            case_sensitive: true,
+            ..Pronunciation::new("US", "you ess")
+        },
+        Pronunciation {
+            whole_word: false,
+            ..Pronunciation::new("ough", "oh")
+        },
+        Pronunciation::new("New York", "noo york"),
+        Pronunciation::new("New", "gnu"),
+    ])
 });
 
 pub fn clean_text_string<T: AsRef<str>>(raw: T, list: &[RegexCleanerPair]) -> String {
@@ -30,6 +44,7 @@ fn one_word_u8idx() {
 }
 
 #[test]
+#[allow(clippy::unnecessary_to_owned)] // checks that an owned String is accepted
 fn in_string() {
     assert_eq!(clean_text_string("Hello".to_string(), &RE_LIST), "Hello");
 }
@@ -297,7 +312,9 @@ fn quickcheck_clean_text_u8idx() {
 
 fn test_does_not_lose_segments<T: AsRef<str>>(text: T) -> bool {
     let text = text.as_ref();
-    let left_out: String = clean_iter(text, &RE_LIST).map(|(o, _)| o).collect();
+    let left_out: String = clean_iter(text, &RE_LIST, Some(&WORDS))
+        .map(|(o, _)| o)
+        .collect();
     text == left_out
 }
 
@@ -305,3 +322,100 @@ fn test_does_not_lose_segments<T: AsRef<str>>(text: T) -> bool {
 fn quickcheck_does_not_lose_segments() {
     quickcheck(test_does_not_lose_segments as fn(String) -> bool);
 }
+
+#[test]
+fn dictionary() {
+    let clean = |t: &str| -> String { clean_text_with_dictionary(t, &RE_LIST, &WORDS) };
+    assert_eq!(clean("hello   world"), "heh low world");
+    assert_eq!(clean("Hellos"), "Hellos");
+    assert_eq!(clean("the US and us"), "the you ess and us");
+    assert_eq!(clean("though"), "thoh");
+    assert_eq!(clean("New York is new"), "noo york is gnu");
+    // the replacement is not cleaned again
+    let underline = Dictionary::new(vec![Pronunciation::new("x", "a  ______ b")]);
+    assert_eq!(
+        clean_text_with_dictionary::<String>("x  y", &RE_LIST, &underline),
+        "a  ______ b y"
+    );
+}
+
+#[test]
+fn dictionary_leaves_links() {
+    let words = Dictionary::new(vec![
+        Pronunciation::new("com", "calm"),
+        Pronunciation::new("www", "dub dub dub"),
+    ]);
+    assert_eq!(
+        clean_text_with_dictionary::<String>(
+            "see https://www.example.com/some/long/path and com",
+            &RE_LIST,
+            &words
+        ),
+        "see link to www.example.com and calm"
+    );
+}
+
+#[test]
+fn dictionary_u8idx() {
+    let text = "Say  hello\tUS";
+    let vec_u8idx_in = clean_text_u8idx_in_with_dictionary(text, &RE_LIST, &WORDS);
+    let vec_u8idx_out = clean_text_u8idx_out_with_dictionary(text, &RE_LIST, &WORDS);
+    assert_eq!(
+        clean_text_with_dictionary::<String>(text, &RE_LIST, &WORDS),
+        "Say heh low you ess"
+    );
+    assert_eq!(clean_text::<String>(text, &RE_LIST), "Say hello US");
+    assert_eq!(
+        invert_idx_covering(&vec_u8idx_in, &vec_u8idx_out, &(4..11)),
+        5..10
+    );
+    // each word of the replacement is all of the word it replaced
+    assert_eq!(
+        invert_idx_covering(&vec_u8idx_in, &vec_u8idx_out, &(4..7)),
+        5..10
+    );
+    assert_eq!(
+        invert_idx_covering(&vec_u8idx_in, &vec_u8idx_out, &(8..11)),
+        5..10
+    );
+    assert_eq!(
+        invert_idx_covering(&vec_u8idx_in, &vec_u8idx_out, &(12..19)),
+        11..13
+    );
+}
+
+#[test]
+fn cleaner_as_pronunciation() {
+    let as_pronunciation = |re: &str, rep: &str| {
+        RegexCleanerPair::new(re, rep.to_string())
+            .unwrap()
+            .as_pronunciation()
+    };
+    assert_eq!(
+        as_pronunciation(r"(?i)\bW3C\b", "W three C"),
+        Some(Pronunciation::new("W3C", "W three C"))
+    );
+    assert_eq!(
+        as_pronunciation(r"e\.g\.", "for example"),
+        Some(Pronunciation {
+            case_sensitive: true,
+            whole_word: false,
+            ..Pronunciation::new("e.g.", "for example")
+        })
+    );
+    assert_eq!(as_pronunciation(r"\s+", " "), None);
+    assert_eq!(as_pronunciation(r"colou?r", "color"), None);
+    assert_eq!(as_pronunciation(r"(?P<a>x)", "$a"), None);
+    // the default cleaners are all more than a word
+    assert!(RE_LIST.iter().all(|c| c.as_pronunciation().is_none()));
+}
+
+#[test]
+fn dictionary_round_trip() {
+    let json = serde_json::to_string(&*WORDS).unwrap();
+    let back: Dictionary = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, *WORDS);
+    assert_eq!(back.find("new york"), WORDS.find("new york"));
+    let old: Dictionary = serde_json::from_str(r#"[{"word": "a", "say": "b"}]"#).unwrap();
+    assert!(old.entries()[0].whole_word);
+}
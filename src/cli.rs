@@ -0,0 +1,98 @@
+use crate::text_source::{read_file, StdinSource, TextSource};
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+/// The command line, `rust_reader [--wav OUT.wav] [--import-pls IN.pls]... [--export-pls OUT.pls] [FILE]...`
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Args {
+    /// Render the text to this wav file instead of reading it out loud.
+    pub wav: Option<PathBuf>,
+    /// Add the pronunciations in these lexicons to the settings.
+    pub import_pls: Vec<PathBuf>,
+    /// Save the pronunciations in the settings as a lexicon.
+    pub export_pls: Option<PathBuf>,
+    /// Files to read, if empty read stdin.
+    pub files: Vec<PathBuf>,
+}
+
+impl Args {
+    pub fn parse<I: IntoIterator<Item = OsString>>(args: I) -> Result<Args, String> {
+        let mut out = Args::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if arg == "--wav" {
+                let path = args.next().ok_or("--wav needs a file name")?;
+                out.wav = Some(path.into());
+            } else if arg == "--import-pls" {
+                let path = args.next().ok_or("--import-pls needs a file name")?;
+                out.import_pls.push(path.into());
+            } else if arg == "--export-pls" {
+                let path = args.next().ok_or("--export-pls needs a file name")?;
+                out.export_pls = Some(path.into());
+            } else {
+                out.files.push(arg.into());
+            }
+        }
+        Ok(out)
+    }
+
+    /// Only work on lexicons, do not read anything.
+    pub fn is_lexicon_only(&self) -> bool {
+        !self.import_pls.is_empty() || self.export_pls.is_some()
+    }
+
+    /// The text of all the files, or of stdin if there are none.
+    pub fn read_input(&self) -> String {
+        let mut text = String::new();
+        if self.files.is_empty() {
+            match StdinSource.get_text() {
+                Ok(s) => text = s,
+                Err(e) => eprintln!("{}", e),
+            }
+        }
+        for path in &self.files {
+            match read_file(path) {
+                Ok(s) => text += &s,
+                Err(e) => eprintln!("{}", e),
+            }
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        Args::parse(args.iter().map(OsString::from))
+    }
+
+    #[test]
+    fn no_args() {
+        assert_eq!(parse(&[]), Ok(Args::default()));
+    }
+
+    #[test]
+    fn wav_and_files() {
+        assert_eq!(
+            parse(&["a.txt", "--wav", "out.wav", "b.txt"]),
+            Ok(Args {
+                wav: Some("out.wav".into()),
+                files: vec!["a.txt".into(), "b.txt".into()],
+                ..Args::default()
+            })
+        );
+        assert!(parse(&["a.txt", "--wav"]).is_err());
+    }
+
+    #[test]
+    fn lexicons() {
+        let args = parse(&["--import-pls", "a.pls", "--export-pls", "out.pls"]).unwrap();
+        assert_eq!(args.import_pls, vec![PathBuf::from("a.pls")]);
+        assert_eq!(args.export_pls, Some("out.pls".into()));
+        assert!(args.is_lexicon_only());
+        assert!(!parse(&["a.txt"]).unwrap().is_lexicon_only());
+        assert!(parse(&["--import-pls"]).is_err());
+    }
+}
@@ -1,20 +1,216 @@
-use clipboard_win::{get_clipboard_string, set_clipboard_string};
-use error_code::ErrorCode;
+use crate::copy_profile::{modifiers_to_release, release_needs_mask, CopyProfile, MODIFIER_KEYS};
+use crate::html::{cf_html_fragment, html_to_speech};
+use crate::rtf::{plain_text_is_degraded, rtf_to_speech, RtfText};
+use clipboard_win::{formats, get_clipboard, get_clipboard_string};
 use std::mem;
 use std::num::NonZeroU32;
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::thread::sleep;
 use std::time::Duration;
-use windows::Win32::UI::Input::KeyboardAndMouse;
+use windows::w;
+use windows::Win32::{
+    Foundation::{CloseHandle, FALSE, HANDLE},
+    Graphics::Gdi,
+    System::DataExchange::{
+        GetClipboardData, GetClipboardOwner, IsClipboardFormatAvailable, RegisterClipboardFormatW,
+        SetClipboardData,
+    },
+    System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32,
+        PROCESS_QUERY_LIMITED_INFORMATION,
+    },
+    UI::Input::KeyboardAndMouse,
+    UI::WindowsAndMessaging as wm,
+};
 
-fn get_clipboard_seq_num() -> Option<NonZeroU32> {
+/// The clipboard sequence number after the last time `get_text` was done with the clipboard.
+static OWN_SEQ_NUM: AtomicU32 = AtomicU32::new(0);
+
+pub fn get_clipboard_seq_num() -> Option<NonZeroU32> {
     clipboard_win::seq_num()
 }
 
+/// The clipboard sequence number after the reader last changed the clipboard itself.
+pub fn own_seq_num() -> Option<u32> {
+    Some(OWN_SEQ_NUM.load(Ordering::Relaxed)).filter(|&n| n != 0)
+}
+
+/// The clipboard holds something its owner asked clipboard monitors to leave alone, like a password.
+pub fn clipboard_is_private() -> bool {
+    [
+        w!("ExcludeClipboardContentFromMonitorProcessing"),
+        w!("Clipboard Viewer Ignore"),
+    ]
+    .iter()
+    .any(|&name| unsafe {
+        let format = RegisterClipboardFormatW(name);
+        format != 0 && IsClipboardFormatAvailable(format).as_bool()
+    })
+}
+
+/// The path of the program that put what is on the clipboard there,
+/// or of the program in front if the clipboard has no owner.
+pub fn clipboard_owner_exe() -> Option<String> {
+    let mut owner = unsafe { GetClipboardOwner() };
+    if owner.0 == 0 {
+        owner = unsafe { wm::GetForegroundWindow() };
+    }
+    window_exe(owner)
+}
+
+/// The path of the program in front, and the class name of its window.
+pub fn foreground_program() -> (Option<String>, Option<String>) {
+    let window = unsafe { wm::GetForegroundWindow() };
+    if window.0 == 0 {
+        return (None, None);
+    }
+    let mut buf = [0u16; 256];
+    let len = unsafe { wm::GetClassNameW(window, &mut buf) };
+    let class = (len > 0).then(|| String::from_utf16_lossy(&buf[..len as usize]));
+    (window_exe(window), class)
+}
+
+/// The path of the program that made `window`.
+fn window_exe(window: windows::Win32::Foundation::HWND) -> Option<String> {
+    unsafe {
+        let mut pid = 0;
+        wm::GetWindowThreadProcessId(window, Some(&mut pid));
+        if pid == 0 {
+            return None;
+        }
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, pid).ok()?;
+        let mut buf = [0u16; 1024];
+        let mut len = buf.len() as u32;
+        let ok = QueryFullProcessImageNameW(
+            process,
+            PROCESS_NAME_WIN32,
+            windows::core::PWSTR(buf.as_mut_ptr()),
+            &mut len,
+        );
+        CloseHandle(process);
+        ok.as_bool()
+            .then(|| String::from_utf16_lossy(&buf[..len as usize]))
+    }
+}
+
+/// Ask Windows to keep what the reader puts on the clipboard out of the clipboard history
+/// and the cloud clipboard. The clipboard must be open.
+fn keep_out_of_history() {
+    for name in ["CanIncludeInClipboardHistory", "CanUploadToCloudClipboard"] {
+        if let Some(format) = clipboard_win::register_format(name) {
+            let _ = clipboard_win::raw::set_without_clear(format.get(), &0u32.to_ne_bytes());
+        }
+    }
+}
+
+/// Formats that Windows makes again from the others when they are asked for,
+/// `CF_BITMAP` and `CF_PALETTE` from `CF_DIB`, and `CF_METAFILEPICT` from `CF_ENHMETAFILE`.
+fn is_synthesized(format: u32) -> bool {
+    matches!(
+        format,
+        formats::CF_BITMAP | formats::CF_PALETTE | formats::CF_METAFILEPICT
+    )
+}
+
+/// Formats that hold a GDI handle, or that only the program that copied can draw,
+/// so there is nothing that can be saved.
+fn is_handle_format(format: u32) -> bool {
+    matches!(
+        format,
+        formats::CF_OWNERDISPLAY
+            | formats::CF_DSPBITMAP
+            | formats::CF_DSPMETAFILEPICT
+            | formats::CF_DSPENHMETAFILE
+    ) || (formats::CF_GDIOBJFIRST..=formats::CF_GDIOBJLAST).contains(&format)
+}
+
+/// The bytes of the enhanced metafile on the clipboard. The clipboard must be open.
+fn get_enh_metafile() -> Option<Vec<u8>> {
+    unsafe {
+        let handle = GetClipboardData(formats::CF_ENHMETAFILE).ok()?;
+        let emf = Gdi::HENHMETAFILE(handle.0);
+        let len = Gdi::GetEnhMetaFileBits(emf, None);
+        if len == 0 {
+            return None;
+        }
+        let mut bits = vec![0; len as usize];
+        (Gdi::GetEnhMetaFileBits(emf, Some(&mut bits)) == len).then_some(bits)
+    }
+}
+
+/// Put an enhanced metafile made from `bits` on the clipboard. The clipboard must be open.
+fn set_enh_metafile(bits: &[u8]) {
+    unsafe {
+        let emf = Gdi::SetEnhMetaFileBits(bits);
+        // once it is set the clipboard owns it
+        if emf.0 != 0 && SetClipboardData(formats::CF_ENHMETAFILE, HANDLE(emf.0)).is_err() {
+            Gdi::DeleteEnhMetaFile(emf);
+        }
+    }
+}
+
+/// Everything that was on the clipboard, in every format, to put back after copying.
+pub struct ClipboardSnapshot {
+    formats: Vec<(u32, Vec<u8>)>,
+    /// The formats that could not be saved, and Windows will not make again from one that was.
+    lost: Vec<u32>,
+}
+
+impl ClipboardSnapshot {
+    pub fn take() -> clipboard_win::SysResult<ClipboardSnapshot> {
+        let _clip = clipboard_win::Clipboard::new_attempts(10)?;
+        let mut formats = Vec::new();
+        let mut lost = Vec::new();
+        for format in clipboard_win::EnumFormats::new() {
+            if is_synthesized(format) {
+                continue;
+            }
+            let data = if format == formats::CF_ENHMETAFILE {
+                get_enh_metafile()
+            } else if is_handle_format(format) {
+                None
+            } else {
+                let mut data = Vec::new();
+                clipboard_win::raw::get_vec(format, &mut data)
+                    .is_ok()
+                    .then_some(data)
+            };
+            match data {
+                Some(data) => formats.push((format, data)),
+                None => lost.push(format),
+            }
+        }
+        Ok(ClipboardSnapshot { formats, lost })
+    }
+
+    /// Put back what was saved, and say what could not be.
+    pub fn restore(&self) -> clipboard_win::SysResult<()> {
+        for format in &self.lost {
+            eprintln!("clipboard format {} was lost", format);
+        }
+        let _clip = clipboard_win::Clipboard::new_attempts(10)?;
+        clipboard_win::empty()?;
+        for (format, data) in &self.formats {
+            if *format == formats::CF_ENHMETAFILE {
+                set_enh_metafile(data);
+            } else {
+                let _ = clipboard_win::raw::set_without_clear(*format, data);
+            }
+        }
+        keep_out_of_history();
+        Ok(())
+    }
+}
+
 pub fn clipboard_setup() {
     if get_clipboard_seq_num().is_none() {
         // this will crash on our reading so lets get it over with.
         // this may fix the problem
-        set_clipboard_string("").unwrap();
+        {
+            let _clip = clipboard_win::Clipboard::new_attempts(10).unwrap();
+            clipboard_win::raw::set_string("").unwrap();
+            keep_out_of_history();
+        }
         // let us see if it did
         get_clipboard_seq_num()
             .expect("Lacks sufficient rights to access clipboard(WINSTA_ACCESSCLIPBOARD)");
@@ -55,21 +251,95 @@ pub fn press_key(vk: &[u16]) {
     }
 }
 
-pub fn press_ctrl_c() {
-    println!("sending ctrl-c");
-    press_key(&[KeyboardAndMouse::VK_CONTROL.0, 67]); //ascii for "c"
+/// An unassigned key, pressed so that letting go of alt or win does not open a menu.
+const MASK_KEY: u16 = 0xe8;
+
+/// The modifier keys that are down on the keyboard.
+fn held_modifiers() -> Vec<u16> {
+    MODIFIER_KEYS
+        .iter()
+        .copied()
+        .filter(|&vk| unsafe { KeyboardAndMouse::GetAsyncKeyState(i32::from(vk)) } < 0)
+        .collect()
 }
 
-pub fn what_on_clipboard_seq_num(clip_num: NonZeroU32, n: u8) -> bool {
-    for i in 0..u32::from(n) {
+/// Wait up to `wait_ms` for the modifiers to be let go, returning those that are still down.
+fn wait_for_modifiers(wait_ms: u64) -> Vec<u16> {
+    let start = std::time::Instant::now();
+    loop {
+        let held = held_modifiers();
+        if held.is_empty() || start.elapsed() >= Duration::from_millis(wait_ms) {
+            return held;
+        }
+        sleep(Duration::from_millis(10));
+    }
+}
+
+/// Press the copy chord of `profile`, and nothing else.
+///
+/// The hotkey that got here is usually still down, and its modifiers would make the chord
+/// into some other shortcut, so first wait for them and then let go of them if they are still down.
+pub fn press_copy(profile: &CopyProfile) -> Result<(), String> {
+    let keys = profile.keys()?;
+    let held = wait_for_modifiers(profile.modifier_wait_ms);
+    let release = modifiers_to_release(&held, &keys);
+    if release_needs_mask(&release) {
+        press_key(&[MASK_KEY]);
+    }
+    for &vk in &release {
+        send_key_event(
+            KeyboardAndMouse::VIRTUAL_KEY(vk),
+            KeyboardAndMouse::KEYEVENTF_KEYUP,
+        );
+    }
+    // they are not pressed again, so one still held is up until it is pressed again,
+    // but pressing it for the user could leave it stuck down if they had already let go
+    press_key(&keys);
+    Ok(())
+}
+
+/// Wait for the clipboard to change from `clip_num`, as long as `profile` says to.
+pub fn what_on_clipboard_seq_num(clip_num: NonZeroU32, profile: &CopyProfile) -> bool {
+    for wait in profile.waits() {
         if get_clipboard_seq_num().unwrap_or(clip_num) != clip_num {
             return true;
         }
-        sleep(Duration::from_millis(2u64.pow(i)));
+        sleep(Duration::from_millis(wait));
     }
     get_clipboard_seq_num().unwrap_or(clip_num) != clip_num
 }
 
+/// Why `get_text` has nothing, in words to say.
+#[derive(Debug)]
+pub enum CopyError {
+    /// The chord of the profile did not change the clipboard.
+    NothingCopied {
+        profile: String,
+        chord: String,
+    },
+    BadChord {
+        profile: String,
+        error: String,
+    },
+    Clipboard,
+}
+
+impl std::fmt::Display for CopyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CopyError::NothingCopied { profile, chord } => write!(
+                f,
+                "nothing was copied by {}, with the {} copy profile.",
+                chord, profile
+            ),
+            CopyError::BadChord { profile, error } => {
+                write!(f, "the {} copy profile has an {}.", profile, error)
+            }
+            CopyError::Clipboard => write!(f, "could not read the clipboard."),
+        }
+    }
+}
+
 pub fn what_on_get_clipboard_string(n: u8) -> clipboard_win::SysResult<String> {
     for i in 0..u32::from(n) {
         match get_clipboard_string() {
@@ -80,18 +350,70 @@ pub fn what_on_get_clipboard_string(n: u8) -> clipboard_win::SysResult<String> {
     get_clipboard_string()
 }
 
-pub fn get_text() -> clipboard_win::SysResult<String> {
-    println!("getting text");
-    let old_clip = what_on_get_clipboard_string(6);
+/// The HTML fragment on the clipboard, if a browser put one there.
+fn get_clipboard_html() -> Option<String> {
+    let format = clipboard_win::register_format("HTML Format")?.get();
+    if !clipboard_win::is_format_avail(format) {
+        return None;
+    }
+    let data: Vec<u8> = get_clipboard(formats::RawData(format)).ok()?;
+    cf_html_fragment(&data)
+}
+
+/// The Rich Text on the clipboard, if a word processor put some there.
+fn get_clipboard_rtf() -> Option<RtfText> {
+    let format = clipboard_win::register_format("Rich Text Format")?.get();
+    if !clipboard_win::is_format_avail(format) {
+        return None;
+    }
+    let data: Vec<u8> = get_clipboard(formats::RawData(format)).ok()?;
+    rtf_to_speech(&data).ok()
+}
+
+/// The text on the clipboard, from the Rich Text if the plain text lost something it has,
+/// or from the HTML if there is some so that its structure is said.
+pub fn what_on_get_clipboard_text(n: u8) -> clipboard_win::SysResult<String> {
+    let plain = what_on_get_clipboard_string(n);
+    if let Some(rtf) = get_clipboard_rtf() {
+        let degraded = plain
+            .as_ref()
+            .map_or(true, |p| plain_text_is_degraded(p, &rtf));
+        if degraded && !rtf.text.trim().is_empty() {
+            return Ok(rtf.text);
+        }
+    }
+    if let Some(html) = get_clipboard_html() {
+        let text = html_to_speech(&html);
+        if !text.trim().is_empty() {
+            return Ok(text);
+        }
+    }
+    plain
+}
+
+/// Copy the selection the way `profile` says, and put back what was on the clipboard.
+pub fn get_text(profile: &CopyProfile) -> Result<String, CopyError> {
+    let old_clip = ClipboardSnapshot::take();
     let old_clip_num = get_clipboard_seq_num()
         .expect("Lacks sufficient rights to access clipboard(WINSTA_ACCESSCLIPBOARD)");
-    press_ctrl_c();
-    if !what_on_clipboard_seq_num(old_clip_num, 6) {
-        return Err(ErrorCode::new(0));
+    if let Err(error) = press_copy(profile) {
+        return Err(CopyError::BadChord {
+            profile: profile.name.clone(),
+            error,
+        });
     }
-    let new_clip = what_on_get_clipboard_string(6);
+    if !what_on_clipboard_seq_num(old_clip_num, profile) {
+        return Err(CopyError::NothingCopied {
+            profile: profile.name.clone(),
+            chord: profile.say_chord(),
+        });
+    }
+    let new_clip = what_on_get_clipboard_text(6).map_err(|_| CopyError::Clipboard);
     if let Ok(clip) = old_clip {
-        let _ = set_clipboard_string(&clip);
+        let _ = clip.restore();
+    }
+    if let Some(n) = get_clipboard_seq_num() {
+        OWN_SEQ_NUM.store(n.get(), Ordering::Relaxed);
     }
     new_clip
 }
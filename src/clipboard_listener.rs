@@ -0,0 +1,112 @@
+use crate::window::*;
+use windows::core::PCWSTR;
+use windows::w;
+use windows::Win32::{
+    Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, WPARAM},
+    System::DataExchange::{AddClipboardFormatListener, RemoveClipboardFormatListener},
+    System::Threading::GetCurrentThreadId,
+    UI::WindowsAndMessaging as wm,
+};
+
+/// Posted to the main thread when the clipboard has been still for the debounce time after a change.
+pub const WM_APP_CLIPBOARD_CHANGED: u32 = wm::WM_APP + 21;
+
+const DEBOUNCE_TIMER: usize = 1;
+
+/// A hidden window that is told each time the clipboard changes.
+pub struct ClipboardListener {
+    window: HWND,
+    listening: bool,
+    debounce_ms: u32,
+}
+
+impl ClipboardListener {
+    pub fn new() -> Box<ClipboardListener> {
+        let mut out = Box::new(ClipboardListener {
+            window: HWND(0),
+            listening: false,
+            debounce_ms: 0,
+        });
+
+        let window_class_name = w!("clipboard_listener_class_name");
+        unsafe {
+            wm::RegisterClassW(&wm::WNDCLASSW {
+                style: wm::WNDCLASS_STYLES(0),
+                lpfnWndProc: Some(window_proc_generic::<ClipboardListener>),
+                cbClsExtra: 0,
+                cbWndExtra: 0,
+                hInstance: HINSTANCE(0),
+                hIcon: wm::HICON(0),
+                hCursor: wm::HCURSOR(0),
+                hbrBackground: windows::Win32::Graphics::Gdi::HBRUSH(0),
+                lpszMenuName: PCWSTR::null(),
+                lpszClassName: window_class_name,
+            });
+            out.window = wm::CreateWindowExW(
+                wm::WINDOW_EX_STYLE(0),
+                window_class_name,
+                PCWSTR(&mut 0u16),
+                wm::WINDOW_STYLE(0),
+                0,
+                0,
+                0,
+                0,
+                wm::HWND_MESSAGE,
+                wm::HMENU(0),
+                HINSTANCE(0),
+                Some(&mut *out as *mut _ as *mut _),
+            );
+        }
+        out
+    }
+
+    /// Start or stop listening.
+    pub fn listen(&mut self, enabled: bool, debounce_ms: u32) {
+        self.debounce_ms = debounce_ms;
+        if enabled == self.listening {
+            return;
+        }
+        self.listening = enabled;
+        unsafe {
+            if enabled {
+                AddClipboardFormatListener(self.window);
+            } else {
+                wm::KillTimer(self.window, DEBOUNCE_TIMER);
+                RemoveClipboardFormatListener(self.window);
+            }
+        }
+    }
+}
+
+impl Windowed for ClipboardListener {
+    fn window_proc(&mut self, msg: u32, w_param: WPARAM, _l_param: LPARAM) -> Option<LRESULT> {
+        match msg {
+            wm::WM_CLIPBOARDUPDATE => {
+                // start the wait again, only the last of a quick run of copies is read
+                unsafe { wm::SetTimer(self.window, DEBOUNCE_TIMER, self.debounce_ms.max(1), None) };
+                return Some(LRESULT(0));
+            }
+            wm::WM_TIMER if w_param.0 == DEBOUNCE_TIMER => {
+                unsafe {
+                    wm::KillTimer(self.window, DEBOUNCE_TIMER);
+                    wm::PostThreadMessageW(
+                        GetCurrentThreadId(),
+                        WM_APP_CLIPBOARD_CHANGED,
+                        WPARAM(0),
+                        LPARAM(0),
+                    );
+                }
+                return Some(LRESULT(0));
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+impl Drop for ClipboardListener {
+    fn drop(&mut self) {
+        self.listen(false, 0);
+        destroy_window(self.window);
+    }
+}
@@ -0,0 +1,221 @@
+use crate::speak_on_copy::same_program;
+use serde::{Deserialize, Serialize};
+
+/// How to copy the selection from some programs.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct CopyProfile {
+    /// Said when copying fails.
+    pub name: String,
+    /// The file names of the programs, with or without `.exe`.
+    pub processes: Vec<String>,
+    /// The class names of their windows, for programs that share one process like consoles.
+    pub window_classes: Vec<String>,
+    /// The keys that copy, like `ctrl+shift+c`.
+    pub chord: String,
+    /// How many times to look if the clipboard has changed.
+    pub retries: u8,
+    /// How long to wait before looking again, doubled each time.
+    pub backoff_ms: u64,
+    /// How long to wait for the keys of the hotkey to be let go, before letting go of them for the user.
+    pub modifier_wait_ms: u64,
+}
+
+impl Default for CopyProfile {
+    fn default() -> CopyProfile {
+        CopyProfile {
+            name: "default".to_string(),
+            processes: Vec::new(),
+            window_classes: Vec::new(),
+            chord: "ctrl+c".to_string(),
+            retries: 6,
+            backoff_ms: 1,
+            modifier_wait_ms: 500,
+        }
+    }
+}
+
+fn strings(list: &[&str]) -> Vec<String> {
+    list.iter().map(|s| s.to_string()).collect()
+}
+
+pub fn default_copy_profiles() -> Vec<CopyProfile> {
+    vec![
+        CopyProfile {
+            name: "terminal".to_string(),
+            processes: strings(&["WindowsTerminal", "alacritty", "mintty", "wezterm-gui"]),
+            window_classes: strings(&["CASCADIA_HOSTING_WINDOW_CLASS"]),
+            chord: "ctrl+shift+c".to_string(),
+            ..CopyProfile::default()
+        },
+        CopyProfile {
+            name: "console".to_string(),
+            window_classes: strings(&["ConsoleWindowClass"]),
+            chord: "ctrl+insert".to_string(),
+            ..CopyProfile::default()
+        },
+        CopyProfile {
+            name: "electron".to_string(),
+            processes: strings(&["Code", "Discord", "Obsidian", "Slack", "Teams"]),
+            retries: 8,
+            backoff_ms: 4,
+            ..CopyProfile::default()
+        },
+        CopyProfile::default(),
+    ]
+}
+
+impl CopyProfile {
+    /// A profile with no processes and no window classes is for every program.
+    pub fn matches(&self, exe_path: Option<&str>, window_class: Option<&str>) -> bool {
+        if self.processes.is_empty() && self.window_classes.is_empty() {
+            return true;
+        }
+        exe_path.is_some_and(|exe| self.processes.iter().any(|p| same_program(exe, p)))
+            || window_class.is_some_and(|class| {
+                self.window_classes
+                    .iter()
+                    .any(|c| c.trim().eq_ignore_ascii_case(class))
+            })
+    }
+
+    /// The first of `profiles` for the program in front.
+    pub fn find(
+        profiles: &[CopyProfile],
+        exe_path: Option<&str>,
+        window_class: Option<&str>,
+    ) -> CopyProfile {
+        profiles
+            .iter()
+            .find(|p| p.matches(exe_path, window_class))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// The milliseconds to wait after each look at the clipboard.
+    pub fn waits(&self) -> impl Iterator<Item = u64> + '_ {
+        (0..u32::from(self.retries)).map(|i| self.backoff_ms.saturating_mul(2u64.saturating_pow(i)))
+    }
+
+    /// The virtual keys of `chord`, to press in order.
+    pub fn keys(&self) -> Result<Vec<u16>, String> {
+        self.chord.split('+').map(key_code).collect()
+    }
+
+    /// `chord` the way it is said.
+    pub fn say_chord(&self) -> String {
+        self.chord
+            .split('+')
+            .map(|k| match k.trim().to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => "control".to_string(),
+                "ins" => "insert".to_string(),
+                k => k.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// The left and right modifier keys, as the keyboard has them.
+pub const MODIFIER_KEYS: [u16; 8] = [0xa0, 0xa1, 0xa2, 0xa3, 0xa4, 0xa5, 0x5b, 0x5c];
+
+/// Shift, ctrl and alt without left or right.
+fn either_side(vk: u16) -> u16 {
+    match vk {
+        0xa0 | 0xa1 => 0x10,
+        0xa2 | 0xa3 => 0x11,
+        0xa4 | 0xa5 => 0x12,
+        0x5c => 0x5b,
+        vk => vk,
+    }
+}
+
+/// The `held` modifiers that are not in `chord`, to let go of so that the program sees only the chord.
+pub fn modifiers_to_release(held: &[u16], chord: &[u16]) -> Vec<u16> {
+    held.iter()
+        .copied()
+        .filter(|&vk| !chord.iter().any(|&c| either_side(c) == either_side(vk)))
+        .collect()
+}
+
+/// Letting go of alt or win opens a menu, unless some other key was pressed while it was down.
+pub fn release_needs_mask(release: &[u16]) -> bool {
+    release
+        .iter()
+        .any(|&vk| matches!(either_side(vk), 0x12 | 0x5b))
+}
+
+fn key_code(name: &str) -> Result<u16, String> {
+    let lower = name.trim().to_ascii_lowercase();
+    let code = match lower.as_str() {
+        "ctrl" | "control" => 0x11,
+        "shift" => 0x10,
+        "alt" => 0x12,
+        "win" => 0x5b,
+        "insert" | "ins" => 0x2d,
+        k if k.len() == 1 && k.as_bytes()[0].is_ascii_alphanumeric() => {
+            u16::from(k.as_bytes()[0].to_ascii_uppercase())
+        }
+        k => match k.strip_prefix('f').and_then(|n| n.parse::<u16>().ok()) {
+            Some(n @ 1..=24) => 0x70 + n - 1,
+            _ => return Err(format!("unknown key {:?}", name)),
+        },
+    };
+    Ok(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find() {
+        let profiles = default_copy_profiles();
+        let exe = r"C:\Program Files\WindowsApps\Microsoft.WindowsTerminal\WindowsTerminal.exe";
+        assert_eq!(
+            CopyProfile::find(&profiles, Some(exe), None).name,
+            "terminal"
+        );
+        let found = CopyProfile::find(&profiles, Some("conhost.exe"), Some("ConsoleWindowClass"));
+        assert_eq!(found.name, "console");
+        let found = CopyProfile::find(&profiles, Some(r"C:\x\notepad.exe"), Some("Notepad"));
+        assert_eq!(found, CopyProfile::default());
+        assert_eq!(CopyProfile::find(&[], None, None).name, "default");
+    }
+
+    #[test]
+    fn keys() {
+        let profiles = default_copy_profiles();
+        assert_eq!(profiles[0].keys(), Ok(vec![0x11, 0x10, 0x43]));
+        assert_eq!(profiles[0].say_chord(), "control shift c");
+        assert_eq!(profiles[1].keys(), Ok(vec![0x11, 0x2d]));
+        let f5 = CopyProfile {
+            chord: "F5".to_string(),
+            ..CopyProfile::default()
+        };
+        assert_eq!(f5.keys(), Ok(vec![0x74]));
+        let bad = CopyProfile {
+            chord: "ctrl+nope".to_string(),
+            ..CopyProfile::default()
+        };
+        assert!(bad.keys().is_err());
+        assert_eq!(
+            CopyProfile::default().waits().collect::<Vec<_>>(),
+            [1, 2, 4, 8, 16, 32]
+        );
+    }
+
+    #[test]
+    fn held_modifiers() {
+        let ctrl_c = CopyProfile::default().keys().unwrap();
+        // ctrl-alt-/ is still down
+        let release = modifiers_to_release(&[0xa2, 0xa5], &ctrl_c);
+        assert_eq!(release, [0xa5]);
+        assert!(release_needs_mask(&release));
+        let release = modifiers_to_release(&[0xa3, 0xa0], &ctrl_c);
+        assert_eq!(release, [0xa0]);
+        assert!(!release_needs_mask(&release));
+        let ctrl_shift_c = default_copy_profiles()[0].keys().unwrap();
+        assert!(modifiers_to_release(&[0xa3, 0xa0], &ctrl_shift_c).is_empty());
+    }
+}
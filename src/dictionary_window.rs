@@ -0,0 +1,265 @@
+use crate::window::*;
+use rust_reader::clean_text::{insert_pronunciation, Pronunciation};
+use windows::core::PCWSTR;
+use windows::w;
+use windows::Win32::{
+    Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, RECT, WPARAM},
+    Graphics::Gdi,
+    System::LibraryLoader,
+    System::Threading::GetCurrentThreadId,
+    UI::WindowsAndMessaging as wm,
+};
+
+/// Posted to the main thread when the pronunciations are to be saved in the settings.
+pub const WM_APP_SAVE_DICTIONARY: u32 = wm::WM_APP + 20;
+
+/// The settings page for the pronunciation dictionary.
+/// Select a word to edit it, "add" replaces an entry for the same word.
+pub struct DictionaryWindow {
+    /// As edited, not yet saved.
+    entries: Vec<Pronunciation>,
+    window: HWND,
+    list: HWND,
+    word: (HWND, HWND),
+    say: (HWND, HWND),
+    case_sensitive: HWND,
+    whole_word: HWND,
+    add: HWND,
+    remove: HWND,
+    save: HWND,
+}
+
+impl DictionaryWindow {
+    pub fn new(entries: &[Pronunciation]) -> Box<DictionaryWindow> {
+        let mut out = Box::new(DictionaryWindow {
+            entries: Vec::new(),
+            window: HWND(0),
+            list: HWND(0),
+            word: (HWND(0), HWND(0)),
+            say: (HWND(0), HWND(0)),
+            case_sensitive: HWND(0),
+            whole_word: HWND(0),
+            add: HWND(0),
+            remove: HWND(0),
+            save: HWND(0),
+        });
+
+        let window_class_name = w!("dictionary_window_class_name");
+        unsafe {
+            wm::RegisterClassW(&wm::WNDCLASSW {
+                style: wm::WNDCLASS_STYLES(0),
+                lpfnWndProc: Some(window_proc_generic::<DictionaryWindow>),
+                cbClsExtra: 0,
+                cbWndExtra: 0,
+                hInstance: HINSTANCE(0),
+                hIcon: wm::LoadIconW(
+                    LibraryLoader::GetModuleHandleW(PCWSTR::null()).unwrap(),
+                    PCWSTR::from_raw(1 as *const u16),
+                )
+                .expect("failed to load icon"),
+                hCursor: wm::LoadCursorW(HINSTANCE(0), wm::IDI_APPLICATION)
+                    .expect("failed to load icon"),
+                hbrBackground: Gdi::HBRUSH(16),
+                lpszMenuName: PCWSTR::null(),
+                lpszClassName: window_class_name,
+            });
+            out.window = wm::CreateWindowExW(
+                wm::WINDOW_EX_STYLE(0),
+                window_class_name,
+                PCWSTR(&mut 0u16),
+                wm::WS_OVERLAPPEDWINDOW | wm::WS_CLIPSIBLINGS | wm::WS_CLIPCHILDREN,
+                0,
+                0,
+                0,
+                0,
+                wm::GetDesktopWindow(),
+                wm::HMENU(0),
+                HINSTANCE(0),
+                Some(&mut *out as *mut _ as *mut _),
+            );
+        }
+        out.word = (
+            create_static_window(out.window, Some(&"word".into())),
+            create_edit_window(out.window, wm::WINDOW_STYLE(0)),
+        );
+        out.say = (
+            create_static_window(out.window, Some(&"say as".into())),
+            create_edit_window(out.window, wm::WINDOW_STYLE(0)),
+        );
+        out.case_sensitive = create_check_box_window(out.window, w!("match case"));
+        out.whole_word = create_check_box_window(out.window, w!("whole word"));
+        set_checked(out.whole_word, true);
+        out.add = create_button_window(out.window, w!("add"));
+        out.remove = create_button_window(out.window, w!("remove"));
+        out.save = create_button_window(out.window, w!("save"));
+        out.list = create_list_window(
+            out.window,
+            wm::WS_VISIBLE | wm::WINDOW_STYLE(wm::LBS_NOTIFY as u32),
+        );
+        set_window_text(out.window, &"reader pronunciations".into());
+        out.set_entries(entries);
+        move_window(
+            out.window,
+            &RECT {
+                left: 0,
+                top: 0,
+                right: 400,
+                bottom: 400,
+            },
+        );
+        out
+    }
+
+    pub fn entries(&self) -> &[Pronunciation] {
+        &self.entries
+    }
+
+    /// Show `entries`, dropping any unsaved edits.
+    pub fn set_entries(&mut self, entries: &[Pronunciation]) {
+        self.entries = entries.to_vec();
+        self.refresh();
+        enable_window(self.save, false);
+    }
+
+    pub fn show_window(&self) -> bool {
+        show_window(self.window, wm::SW_SHOW)
+    }
+
+    fn refresh(&mut self) {
+        let lines: Vec<String> = self
+            .entries
+            .iter()
+            .map(|e| {
+                let mut line = format!("{} \u{2192} {}", e.word, e.say);
+                if e.case_sensitive {
+                    line += "  (match case)";
+                }
+                if !e.whole_word {
+                    line += "  (in words)";
+                }
+                line
+            })
+            .collect();
+        set_list_items(self.list, &lines);
+    }
+
+    fn selected(&self) -> Option<usize> {
+        let index =
+            unsafe { wm::SendMessageW(self.list, wm::LB_GETCURSEL, WPARAM(0), LPARAM(0)) }.0;
+        usize::try_from(index)
+            .ok()
+            .filter(|&i| i < self.entries.len())
+    }
+
+    fn edit_selected(&mut self) {
+        if let Some(e) = self.selected().map(|i| self.entries[i].clone()) {
+            set_window_text(self.word.1, &e.word.as_str().into());
+            set_window_text(self.say.1, &e.say.as_str().into());
+            set_checked(self.case_sensitive, e.case_sensitive);
+            set_checked(self.whole_word, e.whole_word);
+        }
+    }
+
+    fn add(&mut self) {
+        let entry = Pronunciation {
+            word: get_window_text(self.word.1).as_string(),
+            say: get_window_text(self.say.1).as_string(),
+            case_sensitive: is_checked(self.case_sensitive),
+            whole_word: is_checked(self.whole_word),
+        };
+        if entry.word.trim().is_empty() {
+            return;
+        }
+        insert_pronunciation(&mut self.entries, entry);
+        self.refresh();
+        enable_window(self.save, true);
+    }
+
+    fn remove(&mut self) {
+        if let Some(i) = self.selected() {
+            self.entries.remove(i);
+            self.refresh();
+            enable_window(self.save, true);
+        }
+    }
+}
+
+impl Windowed for DictionaryWindow {
+    fn window_proc(&mut self, msg: u32, w_param: WPARAM, l_param: LPARAM) -> Option<LRESULT> {
+        match msg {
+            wm::WM_CLOSE => {
+                show_window(self.window, wm::SW_HIDE);
+                return Some(LRESULT(0));
+            }
+            wm::WM_SIZE => {
+                let rect = get_client_rect(self.window);
+                if (w_param.0 <= 2) && rect.right > 0 && rect.bottom > 0 {
+                    let rect = rect.inset(3);
+                    let (row, rest) = rect.split_rows(25);
+                    let (l, r) = row.split_columns(60);
+                    move_window(self.word.0, &l.inset(3));
+                    move_window(self.word.1, &r.inset(3));
+                    let (row, rest) = rest.split_rows(25);
+                    let (l, r) = row.split_columns(60);
+                    move_window(self.say.0, &l.inset(3));
+                    move_window(self.say.1, &r.inset(3));
+                    let (row, rest) = rest.split_rows(25);
+                    let (l, r) = row.split_columns(row.right / 2);
+                    move_window(self.case_sensitive, &l.inset(3));
+                    move_window(self.whole_word, &r.inset(3));
+                    let (row, rest) = rest.split_rows(25);
+                    let (l, r) = row.split_columns(row.right / 3);
+                    let (m, r) = r.split_columns(r.right / 2);
+                    move_window(self.add, &l.inset(3));
+                    move_window(self.remove, &m.inset(3));
+                    move_window(self.save, &r.inset(3));
+                    move_window(self.list, &rest.inset(3));
+                    return Some(LRESULT(0));
+                }
+            }
+            wm::WM_GETMINMAXINFO => {
+                let data = unsafe { &mut *(l_param.0 as *mut wm::MINMAXINFO) };
+                data.ptMinTrackSize.x = 300;
+                data.ptMinTrackSize.y = 220;
+                return Some(LRESULT(0));
+            }
+            wm::WM_COMMAND => {
+                let hiword = ((w_param.0 >> 16) & 0xffff) as u32;
+                if self.list.0 == l_param.0 && hiword == wm::LBN_SELCHANGE {
+                    self.edit_selected();
+                    return Some(LRESULT(0));
+                }
+                if hiword == wm::BN_CLICKED {
+                    if self.add.0 == l_param.0 {
+                        self.add();
+                        return Some(LRESULT(0));
+                    }
+                    if self.remove.0 == l_param.0 {
+                        self.remove();
+                        return Some(LRESULT(0));
+                    }
+                    if self.save.0 == l_param.0 {
+                        enable_window(self.save, false);
+                        unsafe {
+                            wm::PostThreadMessageW(
+                                GetCurrentThreadId(),
+                                WM_APP_SAVE_DICTIONARY,
+                                WPARAM(0),
+                                LPARAM(0),
+                            )
+                        };
+                        return Some(LRESULT(0));
+                    }
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+impl Drop for DictionaryWindow {
+    fn drop(&mut self) {
+        destroy_window(self.window);
+    }
+}
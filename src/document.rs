@@ -0,0 +1,177 @@
+use rust_reader::clean_text::*;
+use rust_reader::wide_string::*;
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Something to read, both as it was selected and as it is spoken.
+pub struct Document {
+    raw: String,
+    /// The cleaned text, exactly what is passed to the speech engine.
+    text: WideString,
+    /// The u16 index in `raw` of each segment boundary in `text`.
+    idx_in: Vec<usize>,
+    /// The u16 index in `text` of each segment boundary in `text`.
+    idx_out: Vec<usize>,
+}
+
+impl Document {
+    pub fn new(raw: String, cleaners: &[RegexCleanerPair]) -> Document {
+        Document::with_dictionary(raw, cleaners, &Dictionary::default())
+    }
+
+    /// Also say the words in `dictionary` the way it says to.
+    pub fn with_dictionary(
+        raw: String,
+        cleaners: &[RegexCleanerPair],
+        dictionary: &Dictionary,
+    ) -> Document {
+        Document {
+            text: clean_text_with_dictionary(&raw, cleaners, dictionary),
+            idx_in: clean_text_u16idx_in_with_dictionary(&raw, cleaners, dictionary),
+            idx_out: clean_text_u16idx_out_with_dictionary(&raw, cleaners, dictionary),
+            raw,
+        }
+    }
+
+    pub fn raw(&self) -> &str {
+        &self.raw
+    }
+
+    pub fn text(&self) -> &WideString {
+        &self.text
+    }
+
+    /// `position` in `text`, or the end of `text` if it is past it.
+    ///
+    /// A saved position is past the end if the cleaners changed how long the text is.
+    pub fn clamp(&self, position: usize) -> usize {
+        position.min(self.text.len().saturating_sub(1))
+    }
+
+    /// What part of `raw` the utf16 range `cleaned` of `text` came from, in utf16 units.
+    pub fn original_range(&self, cleaned: &Range<usize>) -> Range<usize> {
+        invert_idx_covering(&self.idx_in, &self.idx_out, cleaned)
+    }
+
+    /// Where each word of `text` starts, in utf16 units.
+    pub fn word_starts(&self) -> Vec<usize> {
+        let text = self.text.as_string();
+        text.split_word_bound_indices()
+            .filter(|(_, w)| w.chars().any(char::is_alphanumeric))
+            .map(|(i, _)| u16idx_from_u8idx(&text, 0..i).end)
+            .collect()
+    }
+
+    /// Where each sentence of `text` starts, in utf16 units.
+    pub fn sentence_starts(&self) -> Vec<usize> {
+        let text = self.text.as_string();
+        text.split_sentence_bound_indices()
+            .filter(|(_, s)| !s.trim().is_empty())
+            .map(|(i, s)| i + (s.len() - s.trim_start().len()))
+            .map(|i| u16idx_from_u8idx(&text, 0..i).end)
+            .collect()
+    }
+
+    /// Where each paragraph of `text` starts, in utf16 units.
+    ///
+    /// The cleaners may have joined the lines, so the paragraphs are found in the raw text.
+    /// If there are blank lines they separate paragraphs, otherwise each line is one.
+    pub fn paragraph_starts(&self) -> Vec<usize> {
+        let lines: Vec<(usize, &str)> = self
+            .raw
+            .split_inclusive('\n')
+            .scan(0, |pos, line| {
+                let start = *pos;
+                *pos += line.len();
+                Some((start, line))
+            })
+            .collect();
+        let blank_lines = lines.iter().any(|(_, l)| l.trim().is_empty());
+        let mut out: Vec<usize> = lines
+            .iter()
+            .enumerate()
+            .filter(|&(i, (_, l))| {
+                !l.trim().is_empty() && (!blank_lines || i == 0 || lines[i - 1].1.trim().is_empty())
+            })
+            .map(|(_, &(start, l))| start + (l.len() - l.trim_start().len()))
+            .map(|i| u16idx_from_u8idx(&self.raw, 0..i).end)
+            .map(|i| invert_idx_covering(&self.idx_out, &self.idx_in, &(i..i)).start)
+            .collect();
+        out.dedup();
+        out
+    }
+}
+
+/// The first of the sorted `starts` after `pos`.
+pub fn next_start(starts: &[usize], pos: usize) -> Option<usize> {
+    starts.iter().copied().find(|&s| s > pos)
+}
+
+/// The start before the one `pos` is in, or the first if `pos` is in the first.
+pub fn previous_start(starts: &[usize], pos: usize) -> Option<usize> {
+    let current = starts.partition_point(|&s| s <= pos);
+    starts.get(current.saturating_sub(2)).copied()
+}
+
+/// The one of the sorted `starts` closest to `pos`.
+pub fn nearest_start(starts: &[usize], pos: usize) -> Option<usize> {
+    let i = starts.partition_point(|&s| s < pos);
+    let after = starts.get(i).copied();
+    let before = i.checked_sub(1).map(|i| starts[i]);
+    match (before, after) {
+        (Some(b), Some(a)) if pos - b <= a - pos => Some(b),
+        (_, Some(a)) => Some(a),
+        (b, None) => b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sentences() {
+        let doc = Document::new("Hello world. How are \u{1d565}you?  Fine.".to_string(), &[]);
+        assert_eq!(doc.sentence_starts(), vec![0, 13, 29]);
+        assert_eq!(doc.text().get_slice(29..34), "Fine.");
+    }
+
+    #[test]
+    fn paragraphs_are_lines() {
+        let doc = Document::new("One.\r\nTwo\n\n  Three".to_string(), &default_cleaners());
+        assert_eq!(doc.text().as_string(), "One. Two Three");
+        // blank lines, so "Two" is part of the first paragraph
+        assert_eq!(doc.paragraph_starts(), vec![0, 9]);
+        let doc = Document::new("One.\r\nTwo\n  Three".to_string(), &default_cleaners());
+        assert_eq!(doc.paragraph_starts(), vec![0, 5, 9]);
+    }
+
+    #[test]
+    fn words() {
+        let doc = Document::new("Hello, big \u{1d565}world!".to_string(), &[]);
+        assert_eq!(doc.word_starts(), vec![0, 7, 11]);
+    }
+
+    #[test]
+    fn nearest() {
+        let starts = [0, 10, 20];
+        assert_eq!(nearest_start(&starts, 4), Some(0));
+        assert_eq!(nearest_start(&starts, 5), Some(0));
+        assert_eq!(nearest_start(&starts, 6), Some(10));
+        assert_eq!(nearest_start(&starts, 10), Some(10));
+        assert_eq!(nearest_start(&starts, 99), Some(20));
+        assert_eq!(nearest_start(&[], 5), None);
+    }
+
+    #[test]
+    fn next_and_previous() {
+        let starts = [0, 10, 20];
+        assert_eq!(next_start(&starts, 0), Some(10));
+        assert_eq!(next_start(&starts, 15), Some(20));
+        assert_eq!(next_start(&starts, 20), None);
+        assert_eq!(previous_start(&starts, 15), Some(0));
+        assert_eq!(previous_start(&starts, 20), Some(10));
+        assert_eq!(previous_start(&starts, 5), Some(0));
+        assert_eq!(previous_start(&[], 5), None);
+    }
+}
@@ -0,0 +1,515 @@
+use std::ffi::{CStr, CString};
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::ops::Range;
+use std::os::raw::{c_char, c_int, c_short, c_uint, c_void};
+use std::path::Path;
+use std::ptr::{null, null_mut};
+use std::sync::mpsc::Sender;
+use std::sync::Mutex;
+
+use libloading::Library;
+
+use crate::speech_engine::*;
+use crate::wav::*;
+use rust_reader::wide_string::*;
+
+// From speak_lib.h
+const AUDIO_OUTPUT_PLAYBACK: c_int = 0;
+const ESPEAK_INITIALIZE_DONT_EXIT: c_int = 0x8000;
+const ENOUTPUT_MODE_SYNCHRONOUS: c_int = 1;
+const ENOUTPUT_MODE_SPEAK_AUDIO: c_int = 2;
+const POS_CHARACTER: c_int = 1;
+const ESPEAK_CHARS_UTF8: c_uint = 1;
+const ESPEAK_RATE: c_int = 1;
+const ESPEAK_VOLUME: c_int = 2;
+const EVENT_LIST_TERMINATED: c_int = 0;
+const EVENT_WORD: c_int = 1;
+const EVENT_SENTENCE: c_int = 2;
+const EVENT_MSG_TERMINATED: c_int = 6;
+
+/// The rate espeak-ng uses by default, in words per minute.
+const DEFAULT_WPM: f64 = 175.0;
+const MIN_WPM: i32 = 80;
+const MAX_WPM: i32 = 450;
+
+#[repr(C)]
+struct EspeakEvent {
+    event_type: c_int,
+    unique_identifier: c_uint,
+    /// Counted in chars from 1.
+    text_position: c_int,
+    length: c_int,
+    audio_position: c_int,
+    sample: c_int,
+    user_data: *mut c_void,
+    id: *const c_void,
+}
+
+#[repr(C)]
+struct VoiceSpec {
+    name: *const c_char,
+    languages: *const c_char,
+    identifier: *const c_char,
+    gender: u8,
+    age: u8,
+    variant: u8,
+    xx1: u8,
+    score: c_int,
+    spare: *mut c_void,
+}
+
+type SynthCallback = extern "C" fn(*mut c_short, c_int, *mut EspeakEvent) -> c_int;
+
+/// The entry points we use from `libespeak-ng`, looked up when the reader starts
+/// so that the reader still builds where espeak-ng is not installed.
+struct Lib {
+    initialize: unsafe extern "C" fn(c_int, c_int, *const c_char, c_int) -> c_int,
+    initialize_output: unsafe extern "C" fn(c_int, c_int, *const c_char) -> c_int,
+    set_synth_callback: unsafe extern "C" fn(SynthCallback),
+    synth: unsafe extern "C" fn(
+        *const c_void,
+        usize,
+        c_uint,
+        c_int,
+        c_uint,
+        c_uint,
+        *mut c_uint,
+        *mut c_void,
+    ) -> c_int,
+    cancel: unsafe extern "C" fn() -> c_int,
+    synchronize: unsafe extern "C" fn() -> c_int,
+    set_parameter: unsafe extern "C" fn(c_int, c_int, c_int) -> c_int,
+    list_voices: unsafe extern "C" fn(*mut VoiceSpec) -> *const *const VoiceSpec,
+    set_voice_by_name: unsafe extern "C" fn(*const c_char) -> c_int,
+    get_current_voice: unsafe extern "C" fn() -> *mut VoiceSpec,
+    terminate: unsafe extern "C" fn() -> c_int,
+    _lib: Library,
+}
+
+impl Lib {
+    fn load() -> Result<Lib, libloading::Error> {
+        unsafe {
+            let lib =
+                Library::new("libespeak-ng.so.1").or_else(|_| Library::new("libespeak-ng.so"))?;
+            Ok(Lib {
+                initialize: *lib.get(b"espeak_Initialize\0")?,
+                initialize_output: *lib.get(b"espeak_ng_InitializeOutput\0")?,
+                set_synth_callback: *lib.get(b"espeak_SetSynthCallback\0")?,
+                synth: *lib.get(b"espeak_Synth\0")?,
+                cancel: *lib.get(b"espeak_Cancel\0")?,
+                synchronize: *lib.get(b"espeak_Synchronize\0")?,
+                set_parameter: *lib.get(b"espeak_SetParameter\0")?,
+                list_voices: *lib.get(b"espeak_ListVoices\0")?,
+                set_voice_by_name: *lib.get(b"espeak_SetVoiceByName\0")?,
+                get_current_voice: *lib.get(b"espeak_GetCurrentVoice\0")?,
+                terminate: *lib.get(b"espeak_Terminate\0")?,
+                _lib: lib,
+            })
+        }
+    }
+}
+
+/// What the espeak-ng callback thread knows about the text being read.
+struct Shared {
+    /// Counts calls to `espeak_Synth`, so that events from canceled text can be ignored.
+    generation: usize,
+    /// Where in `last_read` the text being synthesized starts.
+    offset: usize,
+    /// The utf16 index of each char of the text being synthesized, and of its end.
+    utf16_idx: Vec<usize>,
+    status: Status,
+    window: Option<Sender<()>>,
+    /// The samples and word boundaries of a wav file being rendered.
+    recording: Option<(Vec<i16>, Vec<WordTiming>)>,
+}
+
+static SHARED: Mutex<Shared> = Mutex::new(Shared {
+    generation: 0,
+    offset: 0,
+    utf16_idx: Vec::new(),
+    status: Status {
+        running_state: RunningState::Idle,
+        word_range: 0..0,
+        sent_range: 0..0,
+    },
+    window: None,
+    recording: None,
+});
+
+impl Shared {
+    fn utf16_range(&self, text_position: c_int, length: c_int) -> Range<usize> {
+        let last = self.utf16_idx.len().saturating_sub(1);
+        let start = ((text_position.max(1) - 1) as usize).min(last);
+        let end = (start + length.max(0) as usize).min(last);
+        self.offset + self.utf16_idx[start]..self.offset + self.utf16_idx[end]
+    }
+}
+
+extern "C" fn synth_callback(wav: *mut c_short, samples: c_int, events: *mut EspeakEvent) -> c_int {
+    let mut shared = SHARED.lock().unwrap();
+    if let Some((recorded, _)) = &mut shared.recording {
+        if !wav.is_null() && samples > 0 {
+            recorded
+                .extend_from_slice(unsafe { std::slice::from_raw_parts(wav, samples as usize) });
+        }
+    }
+    let mut event = events;
+    while !event.is_null() {
+        let e = unsafe { &*event };
+        if e.event_type == EVENT_LIST_TERMINATED {
+            break;
+        }
+        if e.user_data as usize == shared.generation && !shared.utf16_idx.is_empty() {
+            match e.event_type {
+                EVENT_WORD => {
+                    let word_range = shared.utf16_range(e.text_position, e.length);
+                    if let Some((_, timings)) = &mut shared.recording {
+                        timings.push(WordTiming {
+                            ms: e.audio_position.max(0) as u64,
+                            word_range: word_range.clone(),
+                        });
+                    }
+                    shared.status.word_range = word_range;
+                }
+                EVENT_SENTENCE => {
+                    // espeak-ng does not say where a sentence ends.
+                    let end = shared.offset + shared.utf16_idx[shared.utf16_idx.len() - 1];
+                    shared.status.sent_range = shared.utf16_range(e.text_position, 0).start..end;
+                }
+                EVENT_MSG_TERMINATED => shared.status.running_state = RunningState::Done,
+                _ => {}
+            }
+            if matches!(e.event_type, EVENT_WORD | EVENT_MSG_TERMINATED) {
+                if let Some(window) = &shared.window {
+                    window.send(()).ok();
+                }
+            }
+        }
+        event = unsafe { event.add(1) };
+    }
+    0
+}
+
+/// Convert a rate on the SAPI scale to words per minute.
+///
+/// Like SAPI, 10 is three times the normal speed and -10 is a third of it.
+fn rate_to_wpm(rate: i32) -> i32 {
+    let wpm = DEFAULT_WPM * 3f64.powf(rate as f64 / 10.0);
+    (wpm.round() as i32).clamp(MIN_WPM, MAX_WPM)
+}
+
+/// A `SpeechEngine` using a locally installed espeak-ng.
+///
+/// espeak-ng has no pause, so pausing stops reading and resuming starts again
+/// from the word that was being read.
+pub struct EspeakVoice {
+    lib: Lib,
+    sample_rate: u32,
+    rate: i32,
+    volume: u16,
+    paused: bool,
+    last_read: WideString,
+}
+
+impl EspeakVoice {
+    /// `window` will be sent a message at each word boundary.
+    pub fn new(window: Sender<()>) -> Result<Box<EspeakVoice>, String> {
+        println!("new for EspeakVoice");
+        let lib = Lib::load().map_err(|e| format!("could not load espeak-ng: {}", e))?;
+        // returns the sample rate
+        let sample_rate = unsafe {
+            (lib.initialize)(
+                AUDIO_OUTPUT_PLAYBACK,
+                0,
+                null(),
+                ESPEAK_INITIALIZE_DONT_EXIT,
+            )
+        };
+        if sample_rate < 0 {
+            return Err("could not initialize espeak-ng".to_string());
+        }
+        unsafe { (lib.set_synth_callback)(synth_callback) };
+        SHARED.lock().unwrap().window = Some(window);
+        let mut out = Box::new(EspeakVoice {
+            lib,
+            sample_rate: sample_rate as u32,
+            rate: 0,
+            volume: 100,
+            paused: false,
+            last_read: WideString::new(),
+        });
+        out.set_volume(100);
+        out.set_rate(0);
+        Ok(out)
+    }
+
+    fn voice_name(voice: *const VoiceSpec) -> String {
+        if voice.is_null() || unsafe { (*voice).name.is_null() } {
+            return "unknown".to_string();
+        }
+        unsafe { CStr::from_ptr((*voice).name) }
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+impl SpeechEngine for EspeakVoice {
+    fn speak(&mut self, text: WideString) {
+        self.last_read = text;
+        {
+            let mut shared = SHARED.lock().unwrap();
+            shared.status.word_range = 0..0;
+            shared.status.sent_range = 0..0;
+        }
+        self.speak_from(0);
+    }
+
+    fn speak_from(&mut self, offset: usize) {
+        let text = self.last_read.get_slice(offset..self.last_read.len() - 1);
+        let mut utf16_idx: Vec<usize> = text
+            .chars()
+            .scan(0, |pos, c| {
+                let out = *pos;
+                *pos += c.len_utf16();
+                Some(out)
+            })
+            .collect();
+        utf16_idx.push(text.encode_utf16().count());
+        let generation = {
+            let mut shared = SHARED.lock().unwrap();
+            shared.generation += 1;
+            shared.offset = offset;
+            shared.utf16_idx = utf16_idx;
+            shared.status.running_state = RunningState::Speaking;
+            shared.generation
+        };
+        unsafe { (self.lib.cancel)() };
+        self.paused = false;
+        let text = CString::new(text.replace('\0', " ")).unwrap();
+        unsafe {
+            (self.lib.synth)(
+                text.as_ptr() as *const c_void,
+                text.as_bytes_with_nul().len(),
+                0,
+                POS_CHARACTER,
+                0,
+                ESPEAK_CHARS_UTF8,
+                null_mut(),
+                generation as *mut c_void,
+            )
+        };
+    }
+
+    fn speak_to_wav(&mut self, text: WideString, path: &Path) -> io::Result<Vec<WordTiming>> {
+        // in synchronous mode espeak_Synth hands us the samples before it returns
+        unsafe { (self.lib.initialize_output)(ENOUTPUT_MODE_SYNCHRONOUS, 0, null()) };
+        SHARED.lock().unwrap().recording = Some((vec![], vec![]));
+        self.speak(text);
+        let (samples, timings) = SHARED.lock().unwrap().recording.take().unwrap_or_default();
+        unsafe { (self.lib.initialize_output)(ENOUTPUT_MODE_SPEAK_AUDIO, 0, null()) };
+        write_wav(
+            BufWriter::new(File::create(path)?),
+            self.sample_rate,
+            &samples,
+        )?;
+        Ok(timings)
+    }
+
+    fn wait(&mut self) {
+        unsafe { (self.lib.synchronize)() };
+    }
+
+    fn pause(&mut self) {
+        if self.get_status().running_state != RunningState::Speaking {
+            return;
+        }
+        self.paused = true;
+        {
+            let mut shared = SHARED.lock().unwrap();
+            shared.generation += 1;
+            shared.status.running_state = RunningState::Idle;
+        }
+        unsafe { (self.lib.cancel)() };
+    }
+
+    fn resume(&mut self) {
+        if self.paused {
+            let word_start = SHARED.lock().unwrap().status.word_range.start;
+            self.speak_from(word_start);
+        }
+    }
+
+    fn set_rate(&mut self, rate: i32) -> i32 {
+        self.rate = rate.clamp(-10, 10);
+        unsafe { (self.lib.set_parameter)(ESPEAK_RATE, rate_to_wpm(self.rate), 0) };
+        // espeak-ng only uses the new rate for new text
+        if self.get_status().running_state == RunningState::Speaking {
+            let word_start = SHARED.lock().unwrap().status.word_range.start;
+            self.speak_from(word_start);
+        }
+        self.rate
+    }
+
+    fn get_rate(&mut self) -> i32 {
+        self.rate
+    }
+
+    fn available_voice_names(&mut self) -> Vec<String> {
+        let mut out = vec![];
+        let mut voices = unsafe { (self.lib.list_voices)(null_mut()) };
+        while !voices.is_null() && unsafe { !(*voices).is_null() } {
+            out.push(EspeakVoice::voice_name(unsafe { *voices }));
+            voices = unsafe { voices.add(1) };
+        }
+        out
+    }
+
+    fn set_voice_by_name(&mut self, voice_name: String) -> String {
+        if let Ok(name) = CString::new(voice_name) {
+            unsafe { (self.lib.set_voice_by_name)(name.as_ptr()) };
+        }
+        // Return name of voice now in use
+        EspeakVoice::voice_name(unsafe { (self.lib.get_current_voice)() })
+    }
+
+    fn set_volume(&mut self, volume: u16) {
+        self.volume = volume.min(100);
+        unsafe { (self.lib.set_parameter)(ESPEAK_VOLUME, self.volume as c_int, 0) };
+    }
+
+    fn get_volume(&mut self) -> u16 {
+        self.volume
+    }
+
+    fn get_status(&mut self) -> Status {
+        SHARED.lock().unwrap().status.clone()
+    }
+
+    fn last_read(&self) -> &WideString {
+        &self.last_read
+    }
+}
+
+impl Drop for EspeakVoice {
+    fn drop(&mut self) {
+        unsafe {
+            (self.lib.cancel)();
+            (self.lib.terminate)();
+        }
+        SHARED.lock().unwrap().window = None;
+        println!("drop for EspeakVoice");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_matches_sapi_scale() {
+        assert_eq!(rate_to_wpm(0), 175);
+        assert_eq!(rate_to_wpm(5), 303);
+        assert_eq!(rate_to_wpm(-5), 101);
+        assert_eq!(rate_to_wpm(10), MAX_WPM);
+        assert_eq!(rate_to_wpm(-10), MIN_WPM);
+    }
+
+    #[test]
+    fn positions_are_utf16() {
+        let shared = Shared {
+            generation: 0,
+            offset: 3,
+            // "a\u{1d565}b"
+            utf16_idx: vec![0, 1, 3, 4],
+            status: Status {
+                running_state: RunningState::Speaking,
+                word_range: 0..0,
+                sent_range: 0..0,
+            },
+            window: None,
+            recording: None,
+        };
+        assert_eq!(shared.utf16_range(1, 3), 3..7);
+        assert_eq!(shared.utf16_range(3, 1), 6..7);
+        assert_eq!(shared.utf16_range(3, 10), 6..7);
+    }
+
+    /// Both tests use `SHARED`.
+    static SHARED_TEST: Mutex<()> = Mutex::new(());
+
+    /// The data chunk of a wav file, and how long it plays for.
+    fn wav_data(wav: &[u8]) -> (&[u8], u64) {
+        let rate = u32::from_le_bytes(wav[24..28].try_into().unwrap());
+        let len = u32::from_le_bytes(wav[40..44].try_into().unwrap()) as usize;
+        let data = &wav[44..44 + len];
+        (data, (data.len() / 2) as u64 * 1000 / u64::from(rate))
+    }
+
+    #[test]
+    fn recording_keeps_the_samples() {
+        let _lock = SHARED_TEST.lock().unwrap();
+        let generation = {
+            let mut shared = SHARED.lock().unwrap();
+            shared.generation += 1;
+            shared.offset = 0;
+            // "Hello world"
+            shared.utf16_idx = (0..=11).collect();
+            shared.recording = Some((vec![], vec![]));
+            shared.generation
+        };
+        let mut samples = vec![1i16; 22050];
+        let mut events = [
+            EspeakEvent {
+                event_type: EVENT_WORD,
+                unique_identifier: 0,
+                text_position: 7,
+                length: 5,
+                audio_position: 600,
+                sample: 0,
+                user_data: generation as *mut c_void,
+                id: null(),
+            },
+            EspeakEvent {
+                event_type: EVENT_LIST_TERMINATED,
+                unique_identifier: 0,
+                text_position: 0,
+                length: 0,
+                audio_position: 0,
+                sample: 0,
+                user_data: null_mut(),
+                id: null(),
+            },
+        ];
+        synth_callback(samples.as_mut_ptr(), 22050, events.as_mut_ptr());
+        synth_callback(null_mut(), 0, events[1..].as_mut_ptr());
+        let (recorded, timings) = SHARED.lock().unwrap().recording.take().unwrap();
+        let mut wav = Vec::new();
+        write_wav(&mut wav, 22050, &recorded).unwrap();
+        let (data, ms) = wav_data(&wav);
+        assert_eq!(data.len(), 2 * 22050);
+        assert_eq!(timings[0].word_range, 6..11);
+        assert!(timings.last().unwrap().ms <= ms);
+    }
+
+    /// Only where espeak-ng is installed.
+    #[test]
+    fn export_has_sound() {
+        let _lock = SHARED_TEST.lock().unwrap();
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let mut voice = match EspeakVoice::new(tx) {
+            Ok(voice) => voice,
+            Err(e) => {
+                println!("skipped: {}", e);
+                return;
+            }
+        };
+        let path = std::env::temp_dir().join("rust_reader_espeak_test.wav");
+        let timings = voice.speak_to_wav("Hello big world".into(), &path).unwrap();
+        let wav = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let (data, ms) = wav_data(&wav);
+        assert!(!data.is_empty());
+        assert!(timings.last().unwrap().ms <= ms);
+    }
+}
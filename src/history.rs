@@ -0,0 +1,177 @@
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Local};
+use preferences::Preferences;
+use serde::{Deserialize, Serialize};
+
+use crate::settings::APP_INFO;
+
+/// How much of each entry to show in the history window.
+const SUMMARY_LEN: usize = 80;
+
+/// Something that was read.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HistoryEntry {
+    pub id: u64,
+    pub time: DateTime<Local>,
+    pub raw: String,
+    /// The text as it was spoken.
+    pub cleaned: String,
+    /// How far it was read, in utf16 units of `cleaned`.
+    pub position: usize,
+    pub voice: String,
+    pub rate: i32,
+}
+
+impl HistoryEntry {
+    /// One line for the history window.
+    pub fn summary(&self) -> String {
+        let len = self.cleaned.encode_utf16().count().max(1);
+        let mut text: String = self.cleaned.chars().take(SUMMARY_LEN).collect();
+        if text.len() < self.cleaned.len() {
+            text += "...";
+        }
+        format!(
+            "{} {:.0}% {}",
+            self.time.format("%Y-%m-%d %H:%M"),
+            100.0 * (self.position as f64) / (len as f64),
+            text
+        )
+    }
+}
+
+/// Everything that was read, oldest first.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct History {
+    next_id: u64,
+    entries: VecDeque<HistoryEntry>,
+}
+
+impl History {
+    pub fn from_file() -> History {
+        History::load(&APP_INFO, "history").unwrap_or_else(|_| {
+            println!("failed to lode history.");
+            History::default()
+        })
+    }
+
+    pub fn to_file(&self) {
+        if self.save(&APP_INFO, "history").is_err() {
+            println!("failed to save history.");
+        }
+    }
+
+    /// Remember something new, forgetting the oldest entries past `limit`. Returns its id.
+    pub fn push(&mut self, raw: &str, cleaned: &str, voice: &str, rate: i32, limit: usize) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.entries.push_back(HistoryEntry {
+            id,
+            time: Local::now(),
+            raw: raw.to_string(),
+            cleaned: cleaned.to_string(),
+            position: 0,
+            voice: voice.to_string(),
+            rate,
+        });
+        self.truncate(limit);
+        id
+    }
+
+    /// Forget all but the newest `limit` entries.
+    pub fn truncate(&mut self, limit: usize) {
+        while self.entries.len() > limit {
+            self.entries.pop_front();
+        }
+    }
+
+    pub fn set_position(&mut self, id: u64, position: usize) {
+        if let Some(entry) = self.entries.iter_mut().find(|e| e.id == id) {
+            entry.position = position;
+        }
+    }
+
+    pub fn get(&self, id: u64) -> Option<&HistoryEntry> {
+        self.entries.iter().find(|e| e.id == id)
+    }
+
+    /// The entry older than `id`, or the newest if there is no `id`.
+    pub fn before(&self, id: Option<u64>) -> Option<&HistoryEntry> {
+        match id {
+            None => self.entries.back(),
+            Some(id) => self.entries.iter().rev().find(|e| e.id < id),
+        }
+    }
+
+    /// The entry newer than `id`.
+    pub fn after(&self, id: u64) -> Option<&HistoryEntry> {
+        self.entries.iter().find(|e| e.id > id)
+    }
+
+    /// The entries containing every word of `query`, ignoring case, newest first.
+    pub fn search(&self, query: &str) -> Vec<&HistoryEntry> {
+        let words: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+        self.entries
+            .iter()
+            .rev()
+            .filter(|e| {
+                let raw = e.raw.to_lowercase();
+                let cleaned = e.cleaned.to_lowercase();
+                words
+                    .iter()
+                    .all(|w| raw.contains(w.as_str()) || cleaned.contains(w.as_str()))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(texts: &[&str], limit: usize) -> History {
+        let mut out = History::default();
+        for t in texts {
+            out.push(t, t, "voice", 0, limit);
+        }
+        out
+    }
+
+    #[test]
+    fn step_back_and_forward() {
+        let history = history(&["one", "two", "three"], 10);
+        let newest = history.before(None).unwrap();
+        assert_eq!(newest.raw, "three");
+        let older = history.before(Some(newest.id)).unwrap();
+        assert_eq!(older.raw, "two");
+        assert_eq!(history.after(older.id).unwrap().raw, "three");
+        assert!(history.after(newest.id).is_none());
+        let oldest = history.before(Some(older.id)).unwrap();
+        assert!(history.before(Some(oldest.id)).is_none());
+    }
+
+    #[test]
+    fn retention_limit() {
+        let mut history = history(&["one", "two", "three"], 2);
+        assert_eq!(history.search("").len(), 2);
+        assert_eq!(history.search("")[1].raw, "two");
+        history.truncate(0);
+        assert!(history.before(None).is_none());
+    }
+
+    #[test]
+    fn search_all_words() {
+        let mut history = history(&["The Quick fox", "a quick dog", "The end"], 10);
+        let found: Vec<&str> = history
+            .search("quick")
+            .iter()
+            .map(|e| e.raw.as_str())
+            .collect();
+        assert_eq!(found, vec!["a quick dog", "The Quick fox"]);
+        assert_eq!(history.search("the FOX").len(), 1);
+        assert_eq!(history.search("").len(), 3);
+        let id = history.search("end")[0].id;
+        history.set_position(id, 4);
+        assert!(history.get(id).unwrap().summary().ends_with(" 57% The end"));
+    }
+}
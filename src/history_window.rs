@@ -0,0 +1,162 @@
+use crate::history::*;
+use crate::window::*;
+use windows::core::PCWSTR;
+use windows::w;
+use windows::Win32::{
+    Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, RECT, WPARAM},
+    Graphics::Gdi,
+    System::LibraryLoader,
+    System::Threading::GetCurrentThreadId,
+    UI::WindowsAndMessaging as wm,
+};
+
+/// Posted to the main thread with the id of the history entry to read in the `WPARAM`.
+pub const WM_APP_READ_HISTORY: u32 = wm::WM_APP + 17;
+
+/// Lists what was read, filtered by a search box. Double click an entry to read it again.
+pub struct HistoryWindow {
+    history: History,
+    window: HWND,
+    search: HWND,
+    list: HWND,
+    /// The id of each line in `list`.
+    shown: Vec<u64>,
+}
+
+impl HistoryWindow {
+    pub fn new(history: History) -> Box<HistoryWindow> {
+        let mut out = Box::new(HistoryWindow {
+            history,
+            window: HWND(0),
+            search: HWND(0),
+            list: HWND(0),
+            shown: Vec::new(),
+        });
+
+        let window_class_name = w!("history_window_class_name");
+        unsafe {
+            wm::RegisterClassW(&wm::WNDCLASSW {
+                style: wm::WNDCLASS_STYLES(0),
+                lpfnWndProc: Some(window_proc_generic::<HistoryWindow>),
+                cbClsExtra: 0,
+                cbWndExtra: 0,
+                hInstance: HINSTANCE(0),
+                hIcon: wm::LoadIconW(
+                    LibraryLoader::GetModuleHandleW(PCWSTR::null()).unwrap(),
+                    PCWSTR::from_raw(1 as *const u16),
+                )
+                .expect("failed to load icon"),
+                hCursor: wm::LoadCursorW(HINSTANCE(0), wm::IDI_APPLICATION)
+                    .expect("failed to load icon"),
+                hbrBackground: Gdi::HBRUSH(16),
+                lpszMenuName: PCWSTR::null(),
+                lpszClassName: window_class_name,
+            });
+            out.window = wm::CreateWindowExW(
+                wm::WINDOW_EX_STYLE(0),
+                window_class_name,
+                PCWSTR(&mut 0u16),
+                wm::WS_OVERLAPPEDWINDOW | wm::WS_CLIPSIBLINGS | wm::WS_CLIPCHILDREN,
+                0,
+                0,
+                0,
+                0,
+                wm::GetDesktopWindow(),
+                wm::HMENU(0),
+                HINSTANCE(0),
+                Some(&mut *out as *mut _ as *mut _),
+            );
+        }
+        out.search = create_edit_window(out.window, wm::WINDOW_STYLE(0));
+        out.list = create_list_window(
+            out.window,
+            wm::WS_VISIBLE | wm::WINDOW_STYLE(wm::LBS_NOTIFY as u32),
+        );
+        set_window_text(out.window, &"reader history".into());
+        move_window(
+            out.window,
+            &RECT {
+                left: 0,
+                top: 0,
+                right: 500,
+                bottom: 400,
+            },
+        );
+        out
+    }
+
+    pub fn get_inner_history(&self) -> &History {
+        &self.history
+    }
+
+    /// Remember to call `refresh` if the list needs to change.
+    pub fn get_mut_inner_history(&mut self) -> &mut History {
+        &mut self.history
+    }
+
+    pub fn show_window(&mut self) -> bool {
+        self.refresh();
+        show_window(self.window, wm::SW_SHOW)
+    }
+
+    /// List the entries that match the search box.
+    pub fn refresh(&mut self) {
+        let query = get_window_text(self.search).as_string();
+        let found = self.history.search(&query);
+        self.shown = found.iter().map(|e| e.id).collect();
+        let lines: Vec<String> = found.iter().map(|e| e.summary()).collect();
+        set_list_items(self.list, &lines);
+    }
+}
+
+impl Windowed for HistoryWindow {
+    fn window_proc(&mut self, msg: u32, w_param: WPARAM, l_param: LPARAM) -> Option<LRESULT> {
+        match msg {
+            wm::WM_CLOSE => {
+                show_window(self.window, wm::SW_HIDE);
+                return Some(LRESULT(0));
+            }
+            wm::WM_SIZE => {
+                let rect = get_client_rect(self.window);
+                if (w_param.0 <= 2) && rect.right > 0 && rect.bottom > 0 {
+                    let (up, down) = rect.inset(3).split_rows(25);
+                    move_window(self.search, &up.inset(3));
+                    move_window(self.list, &down.inset(3));
+                    return Some(LRESULT(0));
+                }
+            }
+            wm::WM_GETMINMAXINFO => {
+                let data = unsafe { &mut *(l_param.0 as *mut wm::MINMAXINFO) };
+                data.ptMinTrackSize.x = 300;
+                data.ptMinTrackSize.y = 150;
+                return Some(LRESULT(0));
+            }
+            wm::WM_COMMAND => {
+                let hiword = ((w_param.0 >> 16) & 0xffff) as u32;
+                if self.search.0 == l_param.0 && hiword == wm::EN_CHANGE {
+                    self.refresh();
+                    return Some(LRESULT(0));
+                }
+                if self.list.0 == l_param.0 && hiword == wm::LBN_DBLCLK {
+                    let index = unsafe {
+                        wm::SendMessageW(self.list, wm::LB_GETCURSEL, WPARAM(0), LPARAM(0))
+                    }
+                    .0;
+                    if let Some(&id) = usize::try_from(index).ok().and_then(|i| self.shown.get(i)) {
+                        unsafe {
+                            wm::PostThreadMessageW(
+                                GetCurrentThreadId(),
+                                WM_APP_READ_HISTORY,
+                                WPARAM(id as usize),
+                                LPARAM(0),
+                            )
+                        };
+                    }
+                    return Some(LRESULT(0));
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+}
@@ -1,7 +1,16 @@
 use itertools::Itertools;
+#[cfg(windows)]
 use windows::Win32::Foundation::HWND;
+#[cfg(windows)]
 use windows::Win32::UI::Input::KeyboardAndMouse;
 
+// Virtual-key codes used by the default settings, these match `KeyboardAndMouse::VK_*`.
+pub const VK_ESCAPE: u32 = 0x1B;
+pub const VK_OEM_PLUS: u32 = 0xBB;
+pub const VK_OEM_MINUS: u32 = 0xBD;
+pub const VK_OEM_PERIOD: u32 = 0xBE;
+pub const VK_OEM_2: u32 = 0xBF;
+
 fn convert_modifiers(modifiers: u32) -> String {
     ["Alt", "Ctr", "Sht", "Win"]
         .iter()
@@ -48,6 +57,7 @@ pub fn convert_mod(modifiers: u8) -> u8 {
     to_modifiers
 }
 
+#[cfg(windows)]
 #[derive(Debug)]
 pub struct HotKey {
     vk: u32,
@@ -55,6 +65,7 @@ pub struct HotKey {
     id: i32,
 }
 
+#[cfg(windows)]
 impl HotKey {
     pub fn new(modifiers: u32, vk: u32, id: i32) -> Option<HotKey> {
         let new_hot = HotKey { modifiers, vk, id };
@@ -80,12 +91,13 @@ impl HotKey {
     }
 }
 
+#[cfg(windows)]
 impl ::std::fmt::Display for HotKey {
     fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
         use std::char;
         if self.modifiers > 0 && self.vk > 0 {
             write!(f, "{}+", convert_modifiers(self.modifiers))?;
-            if self.vk as u16 == KeyboardAndMouse::VK_ESCAPE.0 {
+            if self.vk == VK_ESCAPE {
                 write!(f, "Esc")
             } else {
                 write!(
@@ -106,6 +118,7 @@ impl ::std::fmt::Display for HotKey {
     }
 }
 
+#[cfg(windows)]
 impl Drop for HotKey {
     fn drop(&mut self) {
         if self.modifiers > 0 && self.vk > 0 {
@@ -0,0 +1,589 @@
+//! Turn the HTML a browser puts on the clipboard into text that says what the structure was.
+
+/// Elements whose contents are never read.
+const HIDDEN: &[&str] = &[
+    "head", "script", "style", "template", "noscript", "title", "svg", "math",
+];
+/// Elements that go on lines of their own.
+const BLOCKS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "dd",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "header",
+    "hr",
+    "main",
+    "nav",
+    "p",
+    "section",
+    "summary",
+];
+/// Elements that have no closing tag.
+const VOID: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// The HTML fragment out of the `CF_HTML` clipboard format,
+/// that is a header of byte offsets followed by the whole page.
+pub fn cf_html_fragment(data: &[u8]) -> Option<String> {
+    let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+    let data = &data[..end];
+    let mut start_fragment = None;
+    let mut end_fragment = None;
+    for line in data.split(|&b| b == b'\n') {
+        let line = String::from_utf8_lossy(line);
+        let line = line.trim();
+        if line.starts_with('<') {
+            break;
+        }
+        let offset = |value: &str| value.trim().parse::<usize>().ok();
+        match line.split_once(':') {
+            Some(("StartFragment", v)) => start_fragment = offset(v),
+            Some(("EndFragment", v)) => end_fragment = offset(v),
+            _ => {}
+        }
+    }
+    let fragment = match (start_fragment, end_fragment) {
+        (Some(s), Some(e)) if s <= e && e <= data.len() => &data[s..e],
+        _ => {
+            // the offsets are often wrong, the comments are more reliable
+            let text = std::str::from_utf8(data).ok()?;
+            let s = text.find("<!--StartFragment-->")? + "<!--StartFragment-->".len();
+            let e = text[s..].find("<!--EndFragment-->")? + s;
+            &text.as_bytes()[s..e]
+        }
+    };
+    Some(String::from_utf8_lossy(fragment).into_owned())
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Token<'a> {
+    Open {
+        name: String,
+        attrs: Vec<(String, String)>,
+    },
+    Close(String),
+    Text(&'a str),
+}
+
+/// Browsers are forgiving, so is this. Anything that is not a tag is text.
+fn tokens(html: &str) -> Vec<Token<'_>> {
+    let mut out = Vec::new();
+    let mut rest = html;
+    let mut text_start = 0;
+    let mut pos = 0;
+    fn flush<'a>(out: &mut Vec<Token<'a>>, html: &'a str, from: usize, to: usize) {
+        if from < to {
+            out.push(Token::Text(&html[from..to]));
+        }
+    }
+
+    while let Some(i) = rest.find('<') {
+        pos += i;
+        rest = &html[pos..];
+        let skip_past = |end: &str| rest.find(end).map_or(rest.len(), |e| e + end.len());
+        let is_name = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == ':';
+        let len = if rest.starts_with("<!--") {
+            flush(&mut out, html, text_start, pos);
+            skip_past("-->")
+        } else if rest.starts_with("<!") || rest.starts_with("<?") {
+            flush(&mut out, html, text_start, pos);
+            skip_past(">")
+        } else if let Some(close) = rest
+            .strip_prefix("</")
+            .filter(|r| r.starts_with(|c: char| c.is_ascii_alphabetic()))
+        {
+            flush(&mut out, html, text_start, pos);
+            let name_len = close.find(|c| !is_name(c)).unwrap_or(close.len());
+            out.push(Token::Close(close[..name_len].to_ascii_lowercase()));
+            skip_past(">")
+        } else if rest[1..].starts_with(|c: char| c.is_ascii_alphabetic()) {
+            flush(&mut out, html, text_start, pos);
+            let (token, len) = open_tag(rest);
+            let raw_text = match &token {
+                Token::Open { name, .. } if name == "script" || name == "style" => {
+                    Some(format!("</{}", name))
+                }
+                _ => None,
+            };
+            out.push(token);
+            match raw_text {
+                // their contents could have anything in them, even "<"
+                Some(end) => {
+                    let lower = rest[len..].to_ascii_lowercase();
+                    len + lower.find(&end).unwrap_or(lower.len())
+                }
+                None => len,
+            }
+        } else {
+            // a "<" that is not a tag
+            pos += 1;
+            rest = &html[pos..];
+            continue;
+        };
+        pos += len;
+        rest = &html[pos..];
+        text_start = pos;
+    }
+    flush(&mut out, html, text_start, html.len());
+    out
+}
+
+/// Parse the tag at the start of `tag`, returns it and its length.
+fn open_tag(tag: &str) -> (Token<'_>, usize) {
+    let is_name = |c: char| !(c.is_whitespace() || c == '/' || c == '>' || c == '=');
+    let mut rest = &tag[1..];
+    let name_len = rest.find(|c| !is_name(c)).unwrap_or(rest.len());
+    let name = rest[..name_len].to_ascii_lowercase();
+    rest = &rest[name_len..];
+    let mut attrs = Vec::new();
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+        if rest.is_empty() || rest.starts_with('>') {
+            break;
+        }
+        let attr_len = rest.find(|c| !is_name(c)).unwrap_or(rest.len()).max(1);
+        let attr = rest[..attr_len].to_ascii_lowercase();
+        rest = rest[attr_len..].trim_start();
+        let mut value = String::new();
+        if let Some(r) = rest.strip_prefix('=') {
+            let r = r.trim_start();
+            let (v, r) = match r.chars().next() {
+                Some(q @ ('"' | '\'')) => {
+                    let r = &r[1..];
+                    let end = r.find(q).unwrap_or(r.len());
+                    (&r[..end], r.get(end + 1..).unwrap_or(""))
+                }
+                _ => {
+                    let end = r
+                        .find(|c: char| c.is_whitespace() || c == '>')
+                        .unwrap_or(r.len());
+                    (&r[..end], &r[end..])
+                }
+            };
+            value = decode_entities(v);
+            rest = r;
+        }
+        attrs.push((attr, value));
+    }
+    let len = tag.len() - rest.len() + rest.starts_with('>') as usize;
+    (Token::Open { name, attrs }, len)
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(i) = rest.find('&') {
+        out += &rest[..i];
+        rest = &rest[i..];
+        let end = rest[1..]
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '#')
+            .map_or(rest.len(), |e| e + 1);
+        let entity = &rest[1..end];
+        let c = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some('\u{a0}'),
+            "ndash" => Some('\u{2013}'),
+            "mdash" => Some('\u{2014}'),
+            "lsquo" => Some('\u{2018}'),
+            "rsquo" => Some('\u{2019}'),
+            "ldquo" => Some('\u{201c}'),
+            "rdquo" => Some('\u{201d}'),
+            "hellip" => Some('\u{2026}'),
+            "bull" => Some('\u{2022}'),
+            "middot" => Some('\u{b7}'),
+            "copy" => Some('\u{a9}'),
+            "reg" => Some('\u{ae}'),
+            "trade" => Some('\u{2122}'),
+            "deg" => Some('\u{b0}'),
+            _ => match entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+            {
+                Some(hex) => u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+                None => entity
+                    .strip_prefix('#')
+                    .and_then(|dec| dec.parse().ok())
+                    .and_then(char::from_u32),
+            },
+        };
+        match c {
+            Some(c) => {
+                out.push(c);
+                rest = rest[end..].strip_prefix(';').unwrap_or(&rest[end..]);
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out + rest
+}
+
+struct List {
+    ordered: bool,
+    next: i64,
+}
+
+#[derive(Default)]
+struct Table {
+    headers: Vec<String>,
+    rows: usize,
+    /// The text of each cell of the row so far, and if it is a header cell.
+    cells: Vec<(String, bool)>,
+    in_row: bool,
+    /// Where the cell being read starts in the line, and if it is a header cell.
+    cell: Option<(usize, bool)>,
+}
+
+/// Builds the text a line at a time.
+#[derive(Default)]
+struct Speech {
+    lines: Vec<String>,
+    line: String,
+    space: bool,
+    hidden: usize,
+    pre: usize,
+    lists: Vec<List>,
+    tables: Vec<Table>,
+}
+
+impl Speech {
+    fn in_cell(&self) -> bool {
+        self.tables.last().is_some_and(|t| t.cell.is_some())
+    }
+
+    fn write(&mut self, text: &str) {
+        if self.space && !self.line.is_empty() && !self.line.ends_with(' ') {
+            self.line.push(' ');
+        }
+        self.space = false;
+        self.line += text;
+    }
+
+    fn text(&mut self, text: &str) {
+        if self.hidden > 0 {
+            return;
+        }
+        let text = decode_entities(text);
+        if self.pre > 0 && !self.in_cell() {
+            let mut lines = text.split('\n');
+            if let Some(first) = lines.next() {
+                self.write(first.trim_end());
+            }
+            for line in lines {
+                self.break_line();
+                self.write(line.trim_end());
+            }
+            return;
+        }
+        for (i, word) in text.split(char::is_whitespace).enumerate() {
+            if i > 0 {
+                self.space = true;
+            }
+            if !word.is_empty() {
+                self.write(word);
+            }
+        }
+    }
+
+    /// End the line, with a full stop so the voice pauses there.
+    fn break_line(&mut self) {
+        if self.in_cell() {
+            self.space = true;
+            return;
+        }
+        let line = self.line.trim();
+        if !line.is_empty() {
+            let mut line = line.to_string();
+            if !line.ends_with(|c: char| ".!?:;".contains(c)) {
+                line.push('.');
+            }
+            self.lines.push(line);
+        }
+        self.line.clear();
+        self.space = false;
+    }
+
+    fn open(&mut self, name: &str, attrs: &[(String, String)]) {
+        let attr = |a: &str| attrs.iter().find(|(n, _)| n == a).map(|(_, v)| v.as_str());
+        if HIDDEN.contains(&name) {
+            self.hidden += 1;
+            return;
+        }
+        if self.hidden > 0 {
+            return;
+        }
+        match name {
+            "br" => self.break_line(),
+            "pre" => {
+                self.break_line();
+                self.pre += 1;
+            }
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.break_line();
+                self.write(&format!("Heading {}:", &name[1..]));
+                self.space = true;
+            }
+            "ul" | "ol" | "menu" => {
+                self.break_line();
+                let next = attr("start")
+                    .and_then(|s| s.trim().parse().ok())
+                    .unwrap_or(1);
+                self.lists.push(List {
+                    ordered: name == "ol",
+                    next,
+                });
+            }
+            "li" => {
+                self.break_line();
+                let prefix = match self.lists.last_mut() {
+                    Some(list) if list.ordered => {
+                        if let Some(value) = attr("value").and_then(|s| s.trim().parse().ok()) {
+                            list.next = value;
+                        }
+                        list.next += 1;
+                        format!("{}.", list.next - 1)
+                    }
+                    _ => "Bullet:".to_string(),
+                };
+                self.write(&prefix);
+                self.space = true;
+            }
+            "table" => {
+                self.break_line();
+                self.lines.push("Table.".to_string());
+                self.tables.push(Table::default());
+            }
+            "tr" => {
+                self.end_row();
+                if let Some(t) = self.tables.last_mut() {
+                    t.in_row = true;
+                }
+            }
+            "td" | "th" => {
+                self.end_cell();
+                self.break_line();
+                if let Some(t) = self.tables.last_mut() {
+                    t.in_row = true;
+                    t.cell = Some((self.line.len(), name == "th"));
+                }
+            }
+            "a" if attr("href").is_some() => {
+                self.write("link:");
+                self.space = true;
+            }
+            "img" => {
+                if let Some(alt) = attr("alt").map(str::trim).filter(|a| !a.is_empty()) {
+                    self.write(&format!("image: {}", alt));
+                    self.space = true;
+                }
+            }
+            _ if BLOCKS.contains(&name) => self.break_line(),
+            _ => {}
+        }
+    }
+
+    fn close(&mut self, name: &str) {
+        if HIDDEN.contains(&name) {
+            self.hidden = self.hidden.saturating_sub(1);
+            return;
+        }
+        if self.hidden > 0 {
+            return;
+        }
+        match name {
+            "pre" => {
+                self.pre = self.pre.saturating_sub(1);
+                self.break_line();
+            }
+            "ul" | "ol" | "menu" => {
+                self.break_line();
+                self.lists.pop();
+            }
+            "table" => {
+                self.end_row();
+                if self.tables.pop().is_some() {
+                    self.break_line();
+                    self.lines.push("End of table.".to_string());
+                }
+            }
+            "tr" => self.end_row(),
+            "td" | "th" => self.end_cell(),
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "li" => self.break_line(),
+            _ if BLOCKS.contains(&name) => self.break_line(),
+            _ => {}
+        }
+    }
+
+    fn end_cell(&mut self) {
+        if let Some(t) = self.tables.last_mut() {
+            if let Some((start, header)) = t.cell.take() {
+                let text = self.line[start..].trim().to_string();
+                self.line.truncate(start);
+                t.cells.push((text, header));
+            }
+        }
+    }
+
+    /// Say the row, with the name of the column before each cell if the table has a header row.
+    fn end_row(&mut self) {
+        self.end_cell();
+        let t = match self.tables.last_mut() {
+            Some(t) if t.in_row => t,
+            _ => return,
+        };
+        t.in_row = false;
+        let cells = std::mem::take(&mut t.cells);
+        if cells.iter().all(|(text, _)| text.is_empty()) {
+            return;
+        }
+        let line = if t.rows == 0 && t.headers.is_empty() && cells.iter().all(|&(_, h)| h) {
+            t.headers = cells.into_iter().map(|(text, _)| text).collect();
+            format!("Columns: {}", t.headers.join("; "))
+        } else {
+            t.rows += 1;
+            let cells: Vec<String> = cells
+                .into_iter()
+                .enumerate()
+                .map(|(i, (text, _))| match t.headers.get(i) {
+                    Some(h) if !h.is_empty() => format!("{}: {}", h, text),
+                    _ => text,
+                })
+                .collect();
+            format!("Row {}: {}", t.rows, cells.join("; "))
+        };
+        self.break_line();
+        self.write(&line);
+        self.break_line();
+    }
+}
+
+/// The text of `html`, one line for each block, with headings, list items,
+/// table rows and links said to be what they are.
+pub fn html_to_speech(html: &str) -> String {
+    let mut speech = Speech::default();
+    for token in tokens(html) {
+        match token {
+            Token::Open { name, attrs } => {
+                speech.open(&name, &attrs);
+                if VOID.contains(&name.as_str()) {
+                    speech.close(&name);
+                }
+            }
+            Token::Close(name) => speech.close(&name),
+            Token::Text(text) => speech.text(text),
+        }
+    }
+    while !speech.tables.is_empty() {
+        speech.close("table");
+    }
+    speech.break_line();
+    speech.lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn structure() {
+        let html = r#"<h2 id=top>Getting   started</h2>
+<p>Read the <a href="https://example.com/docs">docs</a> &amp; then:</p>
+<ul><li>install<li>run <b>it</b></li></ul>
+<ol start="3"><li>three</li><li>four</li></ol>
+<script>if (a < b) { document.write("<p>no</p>") }</script>
+<p>A&nbsp;line<br>break 2 &lt; 3 &#x263A;</p>"#;
+        assert_eq!(
+            html_to_speech(html),
+            [
+                "Heading 2: Getting started.",
+                "Read the link: docs & then:",
+                "Bullet: install.",
+                "Bullet: run it.",
+                "3. three.",
+                "4. four.",
+                "A line.",
+                "break 2 < 3 \u{263a}.",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn tables() {
+        let html = "<table><tr><th>Name<th>Age</tr>\
+            <tr><td>Ann</td><td><p>30</p></td></tr>\
+            <tr><td>Bob<td>4</table>\
+            <table><tr><td>no</td><td>headers</td></tr></table>";
+        assert_eq!(
+            html_to_speech(html),
+            [
+                "Table.",
+                "Columns: Name; Age.",
+                "Row 1: Name: Ann; Age: 30.",
+                "Row 2: Name: Bob; Age: 4.",
+                "End of table.",
+                "Table.",
+                "Row 1: no; headers.",
+                "End of table.",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn preformatted_and_hidden() {
+        let html = "<head><title>t</title><style>p { color: red }</style></head>\
+            <pre>fn main() {\n    go();\n}</pre><img alt='a cat'><img src=x.png>";
+        assert_eq!(
+            html_to_speech(html),
+            "fn main() {.\ngo();\n}.\nimage: a cat."
+        );
+    }
+
+    #[test]
+    fn clipboard_fragment() {
+        let page =
+            "<html><body><!--StartFragment--><b>caf\u{e9}</b><!--EndFragment--></body></html>";
+        let header_len = 105;
+        let start = header_len + page.find("<b>").unwrap();
+        let end = header_len + page.find("<!--End").unwrap();
+        let header = format!(
+            "Version:0.9\r\nStartHTML:{:010}\r\nEndHTML:{:010}\r\nStartFragment:{:010}\r\nEndFragment:{:010}\r\n",
+            header_len,
+            header_len + page.len(),
+            start,
+            end
+        );
+        assert_eq!(header.len(), header_len);
+        let data = format!("{}{}\0", header, page);
+        assert_eq!(
+            cf_html_fragment(data.as_bytes()).as_deref(),
+            Some("<b>caf\u{e9}</b>")
+        );
+        // bad offsets, so use the comments
+        let data = data.replace("EndFragment:0", "EndFragment:9");
+        assert_eq!(
+            cf_html_fragment(data.as_bytes()).as_deref(),
+            Some("<b>caf\u{e9}</b>")
+        );
+        assert_eq!(cf_html_fragment(b"no html"), None);
+    }
+}
@@ -0,0 +1,16 @@
+//! The text cleaning pipeline of rust_reader.
+//!
+//! Use the same list of cleaners as the reader, to get exactly the text it would speak.
+//!
+//! ```
+//! use rust_reader::clean_text::*;
+//!
+//! let raw = "see https://www.youtube.com/watch?v=JFpanWNgfQY\r\n  now";
+//! let cleaners = default_cleaners();
+//! let dictionary = Dictionary::new(vec![Pronunciation::new("now", "naow")]);
+//! let spoken: String = clean_text_with_dictionary(raw, &cleaners, &dictionary);
+//! assert_eq!(spoken, "see link to www.youtube.com naow");
+//! ```
+
+pub mod clean_text;
+pub mod wide_string;
@@ -1,52 +1,637 @@
 // Comment out the following line in order to see console output
 #![cfg_attr(not(test), windows_subsystem = "windows")]
+// Off Windows only the portable core is built, so that it can be tested.
+#![cfg_attr(not(windows), allow(dead_code, unused_imports))]
 
+#[cfg(windows)]
 use windows::Win32::{
     Foundation::{LPARAM, WPARAM},
     System::Threading::GetCurrentThreadId,
     UI::WindowsAndMessaging as wm,
 };
 
-mod wide_string;
+use rust_reader::wide_string::*;
+use std::collections::BTreeMap;
+
+#[cfg(windows)]
 mod window;
+#[cfg(windows)]
 use crate::window::*;
 
+mod speech_engine;
+use crate::speech_engine::*;
+
+#[cfg(test)]
+mod memory_engine;
+
+mod ui;
+use crate::ui::*;
+
+#[cfg(test)]
+mod memory_ui;
+
+#[cfg(not(windows))]
+mod espeak;
+#[cfg(not(windows))]
+use crate::espeak::*;
+
+#[cfg(windows)]
 mod sapi;
+#[cfg(windows)]
 use crate::sapi::*;
 
+#[cfg(windows)]
+mod reader_window;
+#[cfg(windows)]
+use crate::reader_window::*;
+
+mod progress;
+use crate::progress::*;
+
+mod speech_events;
+use crate::speech_events::*;
+
+mod time_estimator;
+
+mod wav;
+
+mod document;
+use crate::document::*;
+
+mod queue;
+use crate::queue::*;
+
+mod history;
+use crate::history::*;
+
+mod bookmarks;
+use crate::bookmarks::*;
+
+mod session;
+use crate::session::*;
+
+mod ticker;
+
+#[cfg(windows)]
+mod ticker_window;
+
+#[cfg(windows)]
+mod history_window;
+#[cfg(windows)]
+use crate::history_window::*;
+
+mod cli;
+use crate::cli::*;
+
+mod copy_profile;
+
+mod text_source;
+use crate::text_source::*;
+
+#[cfg(windows)]
 mod clipboard;
+#[cfg(windows)]
 use crate::clipboard::*;
 
+mod html;
+
+mod rtf;
+
+mod speak_on_copy;
+use crate::speak_on_copy::*;
+
+#[cfg(windows)]
+mod notification_icon;
+
+#[cfg(windows)]
+mod clipboard_listener;
+#[cfg(windows)]
+use crate::clipboard_listener::*;
+
 mod actions;
 use crate::actions::*;
 
 mod hot_key;
-use crate::hot_key::*;
 
 mod settings;
 use crate::settings::*;
 
-mod clean_text;
-use crate::clean_text::*;
+#[cfg(windows)]
+mod dictionary_window;
+#[cfg(windows)]
+use crate::dictionary_window::*;
+
+#[cfg(windows)]
+mod settings_window;
 
+#[cfg(windows)]
+mod windows_ui;
+#[cfg(windows)]
+use crate::windows_ui::*;
+
+use rust_reader::clean_text::*;
+
+#[cfg(windows)]
 mod on_screen_control;
 
-struct State {
-    voice: Box<SpVoice>,
-    settings: Box<SettingsWindow>,
-    hk: Vec<HotKey>,
+struct State<V: SpeechEngine, U: Ui> {
+    voice: Box<V>,
+    ui: U,
+    /// The window of `document`.
+    window: Box<U::View>,
+    /// What `voice` is reading.
+    document: Document,
+    /// `document` is a message from the reader, not worth keeping in the session.
+    message: bool,
+    /// Where `voice` was last told to start reading `document`, until it reads a word.
+    /// Until then its status still says where it was before.
+    started_at: Option<usize>,
+    /// The other open documents, each in its own window.
+    background: Vec<OpenDocument<U::View>>,
+    /// The id of `document` in `history`.
+    history_id: Option<u64>,
+    /// The `bookmark_key` of `document`, if it is long enough to have one.
+    bookmark_key: Option<String>,
+    bookmarks: Bookmarks,
+    /// Where `ResumeBookmark` jumps to, and the `bookmark_key` of the document it is in.
+    resume_offer: Option<(String, usize)>,
+    /// What to read after `document`.
+    queue: ReadingQueue,
+    /// What the voice passed at each boundary, for everything that follows along.
+    events: SpeechEvents<Self>,
+    progress: Progress,
+    copy_filter: CopyFilter,
+    /// Where each action that reads something gets its text.
+    sources: BTreeMap<Action, Box<dyn TextSource>>,
 }
 
-impl State {
-    fn read(&mut self) {
-        self.voice.resume();
-        match get_text() {
-            Ok(x) => self.voice.speak(clean_text::<WideString>(
-                &x,
-                &self.settings.get_inner_settings().cleaners,
-            )),
+/// The name of the `SpeechEvents` mark at the bookmark `resume_offer` would jump to.
+const RESUME_MARK: &str = "resume";
+
+/// A document in its own window, that the voice is not reading.
+struct OpenDocument<W> {
+    window: Box<W>,
+    document: Document,
+    history_id: Option<u64>,
+    /// Where to continue reading from, in utf16 units.
+    position: usize,
+}
+
+impl<V: SpeechEngine, U: Ui> State<V, U> {
+    fn speak<T: Into<String>>(&mut self, string: T) {
+        self.read_document(Document::new(string.into(), &[]), None);
+        self.message = true;
+    }
+
+    fn read_document(&mut self, doc: Document, history_id: Option<u64>) {
+        if self.bookmark_key.is_some() {
+            // interrupted, so save where it stopped
+            self.bookmarks.store();
+        }
+        self.bookmark_key = bookmark_key(doc.text());
+        if self.resume_offer.as_ref().map(|o| &o.0) != self.bookmark_key.as_ref() {
+            self.resume_offer = None;
+        }
+        self.window.set_text(doc.text());
+        self.voice.speak(doc.text().clone());
+        self.document = doc;
+        self.message = false;
+        self.started_at = Some(0);
+        self.history_id = history_id;
+        self.events.start();
+        if let Some((key, position)) = &self.resume_offer {
+            if self.bookmark_key.as_ref() == Some(key) {
+                self.events.set_mark(RESUME_MARK, *position);
+            }
+        }
+        self.progress.start();
+        self.window.set_progress(0.0, "");
+    }
+
+    /// Clean `raw` the way the settings say.
+    fn new_document(&self, raw: String) -> Document {
+        let settings = self.ui.settings();
+        Document::with_dictionary(raw, &settings.cleaners, &settings.dictionary)
+    }
+
+    /// The text for `act`, from the source the settings bind it to.
+    fn text_for(&mut self, act: Action) -> Result<String, String> {
+        match self.sources.get_mut(&act) {
+            Some(source) => source.get_text(),
+            None => Err(format!("{} has no text source.", act)),
+        }
+    }
+
+    fn read(&mut self, act: Action) {
+        self.resume();
+        match self.text_for(act) {
+            Ok(x) => {
+                let doc = self.new_document(x);
+                let id = self.remember(&doc);
+                self.open_document(doc, id);
+            }
+            Err(x) => {
+                println!("{}", x);
+                self.speak(x);
+            }
+        }
+    }
+
+    /// Read `doc` in a new window, or in place of the focused one, depending on the settings.
+    fn open_document(&mut self, doc: Document, history_id: Option<u64>) {
+        if self.ui.settings().read_opens_window && !self.document.text().is_empty() {
+            let window = self.ui.new_view();
+            let old = self.swap_active(window);
+            self.background.push(old);
+            self.window.set_rate(self.ui.settings().rate);
+        } else if let Some(i) = self.focused_background() {
+            // its document is replaced, so only keep the window
+            let focused = self.background.remove(i);
+            let old = self.swap_active(focused.window);
+            self.background.push(old);
+        }
+        self.read_offering_bookmark(doc, history_id);
+    }
+
+    /// Put `window` in front for the voice to read in, returning what was there.
+    fn swap_active(&mut self, window: Box<U::View>) -> OpenDocument<U::View> {
+        let position = self.position();
+        OpenDocument {
+            window: std::mem::replace(&mut self.window, window),
+            document: std::mem::replace(&mut self.document, Document::new(String::new(), &[])),
+            history_id: self.history_id.take(),
+            position,
+        }
+    }
+
+    /// Read the background document `i`, from where it was left.
+    fn activate(&mut self, i: usize) {
+        let doc = self.background.remove(i);
+        let old = self.swap_active(doc.window);
+        self.background.push(old);
+        self.resume();
+        self.read_document(doc.document, doc.history_id);
+        if doc.position > 0 {
+            self.jump_to(doc.position);
+        }
+    }
+
+    /// The background document whose window the user switched to most recently,
+    /// if that was after switching to the window of `document`.
+    fn focused_background(&self) -> Option<usize> {
+        let (i, activated) = self
+            .background
+            .iter()
+            .enumerate()
+            .filter_map(|(i, d)| Some((i, d.window.last_activated()?)))
+            .max_by_key(|&(_, activated)| activated)?;
+        match self.window.last_activated() {
+            Some(active) if active >= activated => None,
+            _ => Some(i),
+        }
+    }
+
+    /// Where the voice is in `document`, in utf16 units.
+    fn position(&mut self) -> usize {
+        match self.started_at {
+            Some(start) => start,
+            None => self.voice.get_status().word_range.start,
+        }
+    }
+
+    /// What to save, to carry on after a restart.
+    fn session(&mut self) -> Session {
+        let mut documents = Vec::new();
+        if !self.message && !self.document.text().is_empty() {
+            let done = self.voice.get_status().running_state == RunningState::Done;
+            documents.push(SavedDocument {
+                raw: self.document.raw().to_string(),
+                position: if done {
+                    self.document.text().len() - 1
+                } else {
+                    self.position()
+                },
+                history_id: self.history_id,
+                placement: Some(self.window.placement()),
+            });
+        }
+        documents.extend(self.background.iter().map(|d| SavedDocument {
+            raw: d.document.raw().to_string(),
+            position: d.position,
+            history_id: d.history_id,
+            placement: Some(d.window.placement()),
+        }));
+        let queue = self
+            .queue
+            .iter()
+            .map(|q| SavedDocument {
+                raw: q.document.raw().to_string(),
+                position: q.start,
+                history_id: q.history,
+                placement: None,
+            })
+            .collect();
+        Session { documents, queue }
+    }
+
+    /// Open the windows and queue saved in `session`.
+    /// The document that was being read is paused where it was left, for play to carry on.
+    fn restore(&mut self, session: Session) {
+        let rate = self.ui.settings().rate;
+        for saved in session.queue.into_iter().rev() {
+            let doc = self.new_document(saved.raw);
+            let position = doc.clamp(saved.position);
+            self.queue.push_front(doc, position, saved.history_id);
+        }
+        let mut documents = session.documents.into_iter();
+        if let Some(saved) = documents.next() {
+            if let Some(placement) = &saved.placement {
+                self.window.set_placement(placement);
+            }
+            let doc = self.new_document(saved.raw);
+            let position = doc.clamp(saved.position);
+            self.read_document(doc, saved.history_id);
+            if position > 0 {
+                self.jump_to(position);
+            }
+            self.pause();
+        }
+        for saved in documents {
+            let mut window = self.ui.new_view();
+            let document = self.new_document(saved.raw);
+            let position = document.clamp(saved.position);
+            window.set_text(document.text());
+            window.set_rate(rate);
+            if let Some(placement) = &saved.placement {
+                window.set_placement(placement);
+            }
+            self.background.push(OpenDocument {
+                window,
+                document,
+                history_id: saved.history_id,
+                position,
+            });
+        }
+        self.show_queue();
+    }
+
+    /// The user closed a window, if it was the last one it is time to quit.
+    fn close_document(&mut self, window: usize) {
+        if let Some(i) = self.background.iter().position(|d| d.window.id() == window) {
+            self.background.remove(i);
+        } else if self.window.id() == window {
+            match self.background.len() {
+                0 => self.ui.quit(),
+                n => {
+                    self.activate(n - 1);
+                    self.pause();
+                    self.background.pop();
+                }
+            }
+        }
+    }
+
+    /// Read from `at`, out of `PROGRESS_RANGE`, of the document in `window`.
+    fn seek_document(&mut self, window: usize, at: i32) {
+        if self.window.id() != window {
+            match self.background.iter().position(|d| d.window.id() == window) {
+                Some(i) => self.activate(i),
+                None => return,
+            }
+        }
+        let len = self.document.text().len() - 1;
+        let target = len * at.clamp(0, PROGRESS_RANGE) as usize / PROGRESS_RANGE as usize;
+        if let Some(to) = nearest_start(&self.document.word_starts(), target) {
+            self.jump_to(to);
+        }
+    }
+
+    /// Is there something being read, or paused, that should not be interrupted.
+    fn is_busy(&mut self) -> bool {
+        !self.document.text().is_empty()
+            && self.voice.get_status().running_state != RunningState::Done
+    }
+
+    /// Read the selection after everything else, or now if there is nothing else.
+    fn enqueue(&mut self) {
+        let x = match self.text_for(Action::Enqueue) {
+            Ok(x) => x,
+            Err(x) => {
+                self.resume();
+                println!("{}", x);
+                self.speak(x);
+                return;
+            }
+        };
+        let doc = self.new_document(x);
+        let id = self.remember(&doc);
+        if self.is_busy() {
+            self.queue.push_back(doc, id);
+            self.show_queue();
+        } else {
+            self.resume();
+            self.open_document(doc, id);
+        }
+    }
+
+    /// Read `doc` from the start, but first say where it was left if it has a bookmark.
+    fn read_offering_bookmark(&mut self, doc: Document, history_id: Option<u64>) {
+        let mark = bookmark_key(doc.text())
+            .and_then(|key| self.bookmarks.get(&key).map(|mark| (key, mark.clone())));
+        match mark {
+            Some((key, mark)) => {
+                self.queue.push_front(doc, 0, history_id);
+                self.show_queue();
+                self.speak(format!("bookmark at {:.0} percent.", mark.percent()));
+                self.resume_offer = Some((key, mark.position));
+            }
+            None => self.read_document(doc, history_id),
+        }
+    }
+
+    /// Jump to where the document was left last time it was read.
+    fn resume_bookmark(&mut self) {
+        if let Some((key, position)) = self.resume_offer.clone() {
+            if self.bookmark_key.as_ref() != Some(&key) {
+                // still saying where the bookmark is
+                self.next_in_queue();
+            }
+            if self.bookmark_key.as_ref() == Some(&key) {
+                self.jump_to(position);
+                self.resume_offer = None;
+            }
+        }
+    }
+
+    fn next_in_queue(&mut self) {
+        if let Some(next) = self.queue.pop_front() {
+            self.resume();
+            self.read_document(next.document, next.history);
+            if next.start > 0 {
+                self.jump_to(next.start);
+            }
+            self.show_queue();
+        }
+    }
+
+    fn clear_queue(&mut self) {
+        self.queue.clear();
+        self.show_queue();
+    }
+
+    /// Say how many documents are queued, then go back to what was being read.
+    fn announce_queue(&mut self) {
+        let message = match self.queue.len() {
+            0 => "nothing queued".to_string(),
+            1 => "1 item queued".to_string(),
+            n => format!("{} items queued", n),
+        };
+        if self.is_busy() {
+            let start = self.position();
+            let doc = std::mem::replace(&mut self.document, Document::new(String::new(), &[]));
+            self.queue.push_front(doc, start, self.history_id);
+            self.show_queue();
+        }
+        self.resume();
+        self.speak(message);
+    }
+
+    /// Save the history, or if it is not kept forget it, on disk too.
+    fn store_history(&mut self) {
+        let settings = self.ui.settings();
+        let limit = if settings.keep_history {
+            settings.history_limit
+        } else {
+            0
+        };
+        self.ui.history_mut().truncate(limit);
+        self.ui.refresh_history();
+        self.ui.save_history();
+    }
+
+    /// Add `doc` to the history, if it is kept.
+    fn remember(&mut self, doc: &Document) -> Option<u64> {
+        let settings = self.ui.settings();
+        if !settings.keep_history {
+            return None;
+        }
+        let (voice, rate, limit) = (
+            settings.voice.clone(),
+            settings.rate,
+            settings.history_limit,
+        );
+        let id =
+            self.ui
+                .history_mut()
+                .push(doc.raw(), &doc.text().as_string(), &voice, rate, limit);
+        self.ui.refresh_history();
+        Some(id)
+    }
+
+    fn read_history(&mut self, id: u64) {
+        let raw = match self.ui.history().get(id) {
+            Some(entry) => entry.raw.clone(),
+            None => return,
+        };
+        self.resume();
+        let doc = self.new_document(raw);
+        self.read_document(doc, Some(id));
+    }
+
+    /// Read again what was read before this, or the last thing read if this is not in the history.
+    fn history_back(&mut self) {
+        let before = self.ui.history().before(self.history_id);
+        if let Some(id) = before.map(|e| e.id) {
+            self.read_history(id);
+        }
+    }
+
+    fn history_forward(&mut self) {
+        let after = self.history_id.and_then(|id| self.ui.history().after(id));
+        if let Some(id) = after.map(|e| e.id) {
+            self.read_history(id);
+        }
+    }
+
+    fn listen_for_copies(&mut self) {
+        let settings = &self.ui.settings().speak_on_copy;
+        let (enabled, debounce_ms) = (settings.enabled, settings.debounce_ms);
+        self.ui.listen_for_copies(enabled, debounce_ms);
+    }
+
+    fn toggle_speak_on_copy(&mut self) {
+        let settings = &mut self.ui.settings_mut().speak_on_copy;
+        settings.enabled = !settings.enabled;
+        let enabled = settings.enabled;
+        self.ui.save_settings();
+        self.listen_for_copies();
+        self.resume();
+        self.speak(if enabled {
+            "speak on copy on."
+        } else {
+            "speak on copy off."
+        });
+    }
+
+    /// The clipboard settled after a change, read it if the user copied something worth reading.
+    fn clipboard_changed(&mut self) {
+        let change = match self.ui.clipboard_change() {
+            Some(change) => change,
+            None => return,
+        };
+        let settings = &self.ui.settings().speak_on_copy;
+        if !settings.enabled
+            || !self.copy_filter.take(change.seq, change.own_seq)
+            || change.private
+            // a program that can not be named might be one of the excluded ones
+            || change.owner.is_none_or(|exe| settings.is_excluded(&exe))
+        {
+            return;
+        }
+        match self.ui.clipboard_text() {
+            Some(text) if !text.trim().is_empty() => {
+                self.resume();
+                let doc = self.new_document(text);
+                let id = self.remember(&doc);
+                self.open_document(doc, id);
+            }
+            _ => {}
+        }
+    }
+
+    fn show_history(&mut self) {
+        self.ui.show_history();
+    }
+
+    fn show_queue(&mut self) {
+        self.window.set_queue(&self.queue.summaries());
+        self.progress
+            .set_queued(self.queue.len(), self.queue.utf16_len());
+    }
+
+    fn export_wav(&mut self) {
+        self.resume();
+        let x = match self.text_for(Action::ExportWav) {
+            Ok(x) => x,
+            Err(x) => {
+                println!("{}", x);
+                self.speak(x);
+                return;
+            }
+        };
+        let doc = self.new_document(x);
+        let name = chrono::Local::now()
+            .format("%Y-%m-%d_%H-%M-%S.wav")
+            .to_string();
+        let path = self.ui.settings().export_dir.join(&name);
+        self.window.set_text(doc.text());
+        let exported = wav::export(&mut *self.voice, doc.text().clone(), &path);
+        self.document = doc;
+        self.history_id = None;
+        match exported {
+            Ok(_) => self.speak(format!("exported {}", name)),
             Err(x) => {
-                self.voice.speak("oops. error.");
+                self.speak("oops. export failed.");
                 println!("{:?}", x);
             }
         }
@@ -54,76 +639,260 @@ impl State {
 
     fn reload_settings(&mut self) {
         let mut speech = String::new();
-        if self.settings.get_mut_inner_settings().reload_from_file() {
-            self.hk.clear();
-            self.hk = setup_hotkeys(self.settings.get_mut_inner_settings());
-            self.settings.get_mut_inner_settings().rate =
-                self.voice.set_rate(self.settings.get_inner_settings().rate);
-            self.settings.get_mut_inner_settings().voice = self
+        if self.ui.settings_mut().reload_from_file() {
+            self.ui.register_hotkeys();
+            self.set_rate(self.ui.settings().rate);
+            let voice = self
                 .voice
-                .set_voice_by_name(self.settings.get_mut_inner_settings().voice.clone());
-            self.voice
-                .set_time_estimater(self.settings.get_inner_settings().time_estimater.clone());
-            self.settings.inner_to_file();
+                .set_voice_by_name(self.ui.settings().voice.clone());
+            self.ui.settings_mut().voice = voice;
+            self.progress
+                .set_time_estimator(&self.ui.settings().time_estimator);
+            self.bookmarks.prune(self.ui.settings().bookmark_days);
+            self.store_history();
+            let ticker = self.ui.settings().ticker.clone();
+            self.ui.set_ticker_settings(&ticker);
+            self.listen_for_copies();
+            let settings = self.ui.settings();
+            self.sources = settings.text_sources.open(&settings.copy_profiles);
+            self.ui.save_settings();
             speech += "reloaded settings.\r\n";
         } else {
             speech += "failed to reload settings.\r\n";
         }
-        speech += &make_speech(self.settings.get_inner_settings(), &self.hk);
-        self.voice.resume();
-        self.voice.speak(speech);
+        speech += &make_speech(self.ui.settings(), &self.ui.hotkey_names());
+        self.resume();
+        self.speak(speech);
+    }
+
+    /// The pronunciation page was saved.
+    fn save_dictionary(&mut self) {
+        let dictionary = self.ui.edited_dictionary();
+        self.ui.settings_mut().dictionary = dictionary;
+        self.ui.settings().to_file();
+        self.reload_settings();
     }
 
     fn show_settings(&mut self) {
-        self.settings.get_mut_inner_settings().time_estimater = self.voice.get_time_estimater();
-        self.settings.inner_to_file();
-        self.settings.show_window();
+        self.ui.settings_mut().time_estimator = self.progress.get_time_estimator();
+        self.ui.save_settings();
+        self.ui.show_settings();
     }
 
     fn toggle_window_visible(&mut self) {
-        self.voice.toggle_window_visible();
+        let visible = !self.window.is_visible();
+        self.window.set_visible(visible);
+        for doc in &mut self.background {
+            doc.window.set_visible(visible);
+        }
+    }
+
+    fn pause(&mut self) {
+        self.voice.pause();
+        self.progress.reset();
+    }
+
+    fn resume(&mut self) {
+        self.voice.resume();
+        self.progress.reset();
     }
 
     fn play_pause(&mut self) {
-        match self.voice.get_status().dwRunningState {
-            2 => self.voice.pause(),
-            _ => self.voice.resume(),
+        if let Some(i) = self.focused_background() {
+            return self.activate(i);
+        }
+        match self.voice.get_status().running_state {
+            RunningState::Speaking => self.pause(),
+            _ => self.resume(),
+        }
+    }
+
+    /// Start reading again from the first of `starts` after, or before, `pos`.
+    fn skip(&mut self, starts: &[usize], pos: usize, forward: bool) {
+        let to = if forward {
+            next_start(starts, pos)
+        } else {
+            previous_start(starts, pos)
+        };
+        if let Some(to) = to {
+            self.jump_to(to);
+        }
+    }
+
+    fn jump_to(&mut self, to: usize) {
+        self.resume();
+        self.voice.speak_from(to);
+        self.started_at = Some(to);
+        self.progress.reset();
+        self.window.select(&(to..to));
+    }
+
+    fn skip_sentence(&mut self, forward: bool) {
+        let status = self.voice.get_status();
+        // the voice knows best where the sentence it is reading starts
+        let pos = if status.sent_range.is_empty() {
+            status.word_range.start
+        } else {
+            status.sent_range.start
+        };
+        self.skip(&self.document.sentence_starts(), pos, forward);
+    }
+
+    fn skip_paragraph(&mut self, forward: bool) {
+        let pos = self.voice.get_status().word_range.start;
+        self.skip(&self.document.paragraph_starts(), pos, forward);
+    }
+
+    /// Jump about `skip_seconds` forward or back, to the nearest word.
+    fn seek(&mut self, forward: bool) {
+        let seconds = self.ui.settings().skip_seconds;
+        let rate = self.voice.get_rate();
+        let distance = self.progress.utf16_in(
+            &self.ui.settings().voice,
+            rate,
+            chrono::Duration::milliseconds((seconds * 1000.0) as i64),
+        );
+        let pos = self.voice.get_status().word_range.start;
+        let target = if forward {
+            pos + distance
+        } else {
+            pos.saturating_sub(distance)
+        };
+        let starts = self.document.word_starts();
+        let to = match nearest_start(&starts, target) {
+            // always move at least one word
+            Some(to) if forward && to <= pos => next_start(&starts, pos),
+            Some(to) if !forward && to >= pos => previous_start(&starts, pos),
+            to => to,
+        };
+        if let Some(to) = to {
+            self.jump_to(to);
+        }
+    }
+
+    fn set_rate(&mut self, rate: i32) {
+        let rate = self.voice.set_rate(rate);
+        self.show_rate(rate);
+    }
+
+    fn show_rate(&mut self, rate: i32) {
+        self.window.set_rate(rate);
+        for doc in &mut self.background {
+            doc.window.set_rate(rate);
         }
+        self.progress.reset();
+        self.ui.settings_mut().rate = rate;
     }
 
     fn rate_change(&mut self, val: i32) {
-        self.settings.get_mut_inner_settings().rate = self.voice.change_rate(val);
-        self.settings.get_mut_inner_settings().time_estimater = self.voice.get_time_estimater();
-        self.settings.inner_to_file();
-        println!("rate: {:?}", self.settings.get_inner_settings().rate);
+        let rate = self.voice.change_rate(val);
+        self.show_rate(rate);
+        self.ui.settings_mut().time_estimator = self.progress.get_time_estimator();
+        self.ui.save_settings();
+        println!("rate: {:?}", self.ui.settings().rate);
+    }
+
+    /// Have the ticker and the progress bar follow along with the speech events.
+    fn follow_along(&mut self) {
+        self.events
+            .subscribe(&[EventKind::Word], |event, state: &mut Self| {
+                if let SpeechEvent::Word(word) = event {
+                    state.ui.show_word(state.document.text(), &word.cleaned);
+                }
+            });
+        self.events.subscribe(
+            &[EventKind::Word, EventKind::End],
+            |event, state: &mut Self| {
+                let rate = state.voice.get_rate();
+                let voice = &state.ui.settings().voice;
+                if let Some(update) =
+                    state
+                        .progress
+                        .observe(event, voice, rate, state.voice.last_read())
+                {
+                    state
+                        .window
+                        .set_progress(update.percent, &update.bar_text());
+                    state.window.set_title(&update.title.into());
+                    if let Some(word_range) = update.word_range {
+                        state.window.select(&word_range);
+                    }
+                }
+            },
+        );
+    }
+
+    /// The voice has passed a word boundary, or finished.
+    fn speech_event(&mut self) {
+        let status = self.voice.get_status();
+        for event in self.events.observe(&status, &self.document) {
+            match &event {
+                SpeechEvent::Word(word) => {
+                    self.started_at = None;
+                    let pos = word.cleaned.start;
+                    if let (Some(key), true) = (&self.bookmark_key, pos > 0) {
+                        let len = self.document.text().len() - 1;
+                        self.bookmarks.set(key, pos, len);
+                    }
+                    if let Some(id) = self.history_id {
+                        self.ui.history_mut().set_position(id, pos);
+                    }
+                }
+                SpeechEvent::Bookmark { name, .. } if name == RESUME_MARK => {
+                    // read past where it was left, so nothing to go back to
+                    self.resume_offer = None;
+                }
+                SpeechEvent::End => {
+                    if let Some(key) = &self.bookmark_key {
+                        self.bookmarks.remove(key);
+                    }
+                }
+                _ => {}
+            }
+            SpeechEvents::notify(self, |state| &mut state.events, &event);
+        }
+        if self.bookmark_key.is_some() {
+            self.bookmarks
+                .store_every(std::time::Duration::from_secs(30));
+        }
+        if status.running_state == RunningState::Done && !self.queue.is_empty() {
+            self.next_in_queue();
+        }
     }
 
     fn match_hotkey_id(&mut self, act: Action) {
         use crate::Action::*;
         match act {
-            Read => self.read(),
-            Close => close(),
+            Read | ReadClipboard => self.read(act),
+            Close => self.ui.quit(),
             ReloadSettings => self.reload_settings(),
             ShowSettings => self.show_settings(),
             ToggleWindowVisible => self.toggle_window_visible(),
             PlayPause => self.play_pause(),
             RateDown => self.rate_change(-1),
             RateUp => self.rate_change(1),
+            ExportWav => self.export_wav(),
+            NextSentence => self.skip_sentence(true),
+            PreviousSentence => self.skip_sentence(false),
+            NextParagraph => self.skip_paragraph(true),
+            PreviousParagraph => self.skip_paragraph(false),
+            SeekForward => self.seek(true),
+            SeekBack => self.seek(false),
+            Enqueue => self.enqueue(),
+            NextInQueue => self.next_in_queue(),
+            ClearQueue => self.clear_queue(),
+            AnnounceQueue => self.announce_queue(),
+            HistoryBack => self.history_back(),
+            HistoryForward => self.history_forward(),
+            ShowHistory => self.show_history(),
+            ResumeBookmark => self.resume_bookmark(),
+            ToggleTicker => self.ui.toggle_ticker(),
+            ToggleSpeakOnCopy => self.toggle_speak_on_copy(),
         }
     }
 }
 
-fn setup_hotkeys(settings: &mut Settings) -> Vec<HotKey> {
-    assert_eq!(ACTION_LIST.len(), settings.hotkeys.len());
-    ACTION_LIST
-        .iter()
-        .zip(settings.hotkeys.iter())
-        .map(|(&act, &(modifiers, vk))| {
-            HotKey::new(modifiers, vk, act as i32).unwrap() // make HotKey
-        })
-        .collect()
-}
-
+#[cfg(windows)]
 fn press_hotkey(id: Action) {
     unsafe {
         wm::PostThreadMessageW(
@@ -135,7 +904,8 @@ fn press_hotkey(id: Action) {
     };
 }
 
-fn make_speech(settings: &Settings, hk: &[HotKey]) -> String {
+/// What the settings are, with `hotkeys` as `Ui::hotkey_names` writes them.
+fn make_speech(settings: &Settings, hotkeys: &[String]) -> String {
     let mut out = "Reading from settings at:".to_string();
     out += "\r\n";
     out += &settings.get_dir().to_string_lossy();
@@ -146,40 +916,177 @@ fn make_speech(settings: &Settings, hk: &[HotKey]) -> String {
     out += "voice: ";
     out += &settings.voice;
     out += "\r\n";
+    out += "seek by: ";
+    out += &settings.skip_seconds.to_string();
+    out += " seconds\r\n";
     out += "hotkeys\r\n";
-    for (act, h) in ACTION_LIST.iter().zip(hk.iter()) {
+    for (act, h) in ACTION_LIST.iter().zip(hotkeys) {
         out += &format!("{}: {}\r\n", act, h);
     }
     out += "Ready!";
     out
 }
 
+/// `rust_reader --wav OUT.wav [FILE]...` renders the files to OUT.wav instead of reading them.
+fn export_cli<V: SpeechEngine>(voice: &mut V, settings: &Settings, args: &Args) {
+    let wav = args.wav.as_ref().expect("no --wav");
+    voice.set_voice_by_name(settings.voice.clone());
+    voice.set_rate(settings.rate);
+    let text = clean_text_with_dictionary::<WideString>(
+        &args.read_input(),
+        &settings.cleaners,
+        &settings.dictionary,
+    );
+    match wav::export(voice, text, wav) {
+        Ok(sidecar) => println!("wrote {} and {}", wav.display(), sidecar.display()),
+        Err(e) => {
+            eprintln!("failed to write {}: {}", wav.display(), e);
+            std::process::exit(1)
+        }
+    }
+}
+
+/// `rust_reader --import-pls IN.pls --export-pls OUT.pls` adds the lexicon to the pronunciations,
+/// and saves them, and the cleaners that only replace a word, as a lexicon.
+fn lexicon_cli(settings: &mut Settings, args: &Args) {
+    let mut report = Vec::new();
+    let mut failed = false;
+    if !args.import_pls.is_empty() {
+        let mut entries = settings.dictionary.entries().to_vec();
+        for path in &args.import_pls {
+            match std::fs::read_to_string(path)
+                .map_err(|e| e.to_string())
+                .and_then(|xml| read_pls(&xml))
+            {
+                Ok(lexicon) => {
+                    report.push(format!(
+                        "{}: {} pronunciations, skipped {} with only phonemes",
+                        path.display(),
+                        lexicon.entries.len(),
+                        lexicon.skipped
+                    ));
+                    if let Some(lang) = lexicon.lang {
+                        settings.lexicon_lang = lang;
+                    }
+                    for entry in lexicon.entries {
+                        insert_pronunciation(&mut entries, entry);
+                    }
+                }
+                Err(e) => {
+                    report.push(format!("failed to import {}: {}", path.display(), e));
+                    failed = true;
+                }
+            }
+        }
+        settings.dictionary = Dictionary::new(entries);
+        settings.to_file();
+    }
+    if let Some(path) = &args.export_pls {
+        let cleaners: Vec<Pronunciation> = settings
+            .cleaners
+            .iter()
+            .filter_map(RegexCleanerPair::as_pronunciation)
+            .collect();
+        // the dictionary goes first, so its entries win over the cleaners
+        let xml = write_pls(
+            settings.dictionary.entries().iter().chain(&cleaners),
+            &settings.lexicon_lang,
+        );
+        match std::fs::write(path, xml) {
+            Ok(()) => report.push(format!("wrote {}", path.display())),
+            Err(e) => {
+                report.push(format!("failed to write {}: {}", path.display(), e));
+                failed = true;
+            }
+        }
+    }
+    show_report(&report.join("\n"), failed);
+    if failed {
+        std::process::exit(1)
+    }
+}
+
+/// Started from the command line, but a windows program has no console to print to.
+#[cfg(windows)]
+fn show_report(report: &str, failed: bool) {
+    message_box("rust_reader", report, failed);
+}
+
+#[cfg(not(windows))]
+fn show_report(report: &str, failed: bool) {
+    if failed {
+        eprintln!("{}", report);
+    } else {
+        println!("{}", report);
+    }
+}
+
+fn parse_args() -> Args {
+    Args::parse(std::env::args_os().skip(1)).unwrap_or_else(|e| {
+        show_report(&e, true);
+        std::process::exit(2)
+    })
+}
+
+#[cfg(windows)]
 fn main() {
+    let args = parse_args();
+    if args.is_lexicon_only() {
+        lexicon_cli(&mut Settings::from_file(), &args);
+        return;
+    }
     let com = Com::new();
-    let mut voice = SpVoice::new(&com);
-    let mut settings = Settings::from_file();
+    // not one of the reader windows, as any of them can be closed
+    let mut voice = SpVoice::new(&com, create_message_window());
+    let settings = Settings::from_file();
+    if args.wav.is_some() {
+        export_cli(&mut *voice, &settings, &args);
+        return;
+    }
     let voices = voice.available_voice_names();
-    voice.set_rate(settings.rate);
     voice.set_voice_by_name(settings.voice.clone());
-    voice.set_time_estimater(settings.time_estimater.clone());
-    let hk = setup_hotkeys(&mut settings);
     clipboard_setup();
+    let mut bookmarks = Bookmarks::from_file();
+    bookmarks.prune(settings.bookmark_days);
 
     let mut state = State {
         voice,
-        settings: SettingsWindow::new(settings, voices),
-        hk,
+        window: ReaderWindow::new(),
+        sources: settings.text_sources.open(&settings.copy_profiles),
+        progress: Progress::new(settings.time_estimator.clone()),
+        ui: WindowsUi::new(settings, voices, History::from_file()),
+        document: Document::new(String::new(), &[]),
+        message: false,
+        started_at: None,
+        background: Vec::new(),
+        history_id: None,
+        bookmark_key: None,
+        bookmarks,
+        resume_offer: None,
+        queue: ReadingQueue::default(),
+        events: SpeechEvents::default(),
+        copy_filter: CopyFilter::default(),
     };
-
-    state
-        .voice
-        .speak(make_speech(state.settings.get_inner_settings(), &state.hk));
+    state.set_rate(state.ui.settings().rate);
+    state.follow_along();
+    state.listen_for_copies();
+    state.restore(Session::from_file());
+    // the greeting would take the place of a restored document
+    if state.document.text().is_empty() {
+        state.speak(make_speech(state.ui.settings(), &state.ui.hotkey_names()));
+    }
 
     while let Some(msg) = get_message() {
         match msg.message {
-            wm::WM_HOTKEY if msg.wParam.0 < state.hk.len() => {
+            wm::WM_HOTKEY if msg.wParam.0 < ACTION_LIST.len() => {
                 state.match_hotkey_id(ACTION_LIST[msg.wParam.0])
             }
+            WM_SAPI_EVENT => state.speech_event(),
+            WM_APP_READ_HISTORY => state.read_history(msg.wParam.0 as u64),
+            WM_APP_SAVE_DICTIONARY => state.save_dictionary(),
+            WM_APP_CLIPBOARD_CHANGED => state.clipboard_changed(),
+            WM_APP_CLOSE_DOCUMENT => state.close_document(msg.wParam.0),
+            WM_APP_SEEK_DOCUMENT => state.seek_document(msg.wParam.0, msg.lParam.0 as i32),
             _ => {
                 // println!("{:?}", msg);
                 unsafe {
@@ -189,8 +1096,533 @@ fn main() {
             }
         }
     }
-    state.voice.resume();
-    state.voice.speak_wait("bye!");
-    state.settings.get_mut_inner_settings().time_estimater = state.voice.get_time_estimater();
-    state.settings.inner_to_file();
+    state.session().to_file();
+    state.resume();
+    state.voice.speak_wait("bye!".into());
+    state.ui.settings_mut().time_estimator = state.progress.get_time_estimator();
+    state.ui.save_settings();
+    state.store_history();
+    state.bookmarks.store();
+}
+
+/// Without a window to select text in, read the files named on the command line,
+/// or stdin if there are none.
+#[cfg(not(windows))]
+fn main() {
+    use std::io::Write;
+
+    let args = parse_args();
+    if args.is_lexicon_only() {
+        lexicon_cli(&mut Settings::from_file(), &args);
+        return;
+    }
+    let (window, speech_events) = std::sync::mpsc::channel();
+    let mut voice = EspeakVoice::new(window).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1)
+    });
+    let mut settings = Settings::from_file();
+    if args.wav.is_some() {
+        export_cli(&mut *voice, &settings, &args);
+        return;
+    }
+    settings.voice = voice.set_voice_by_name(settings.voice.clone());
+    settings.rate = voice.set_rate(settings.rate);
+    let mut progress = Progress::new(settings.time_estimator.clone());
+
+    let document =
+        Document::with_dictionary(args.read_input(), &settings.cleaners, &settings.dictionary);
+    let mut history = History::from_file();
+    let history_id = history.push(
+        document.raw(),
+        &document.text().as_string(),
+        &settings.voice,
+        settings.rate,
+        settings.history_limit,
+    );
+    let mut events: SpeechEvents = SpeechEvents::default();
+    voice.speak(document.text().clone());
+    while speech_events.recv().is_ok() {
+        let status = voice.get_status();
+        let rate = voice.get_rate();
+        for event in events.observe(&status, &document) {
+            if let SpeechEvent::Word(word) = &event {
+                history.set_position(history_id, word.cleaned.start);
+            }
+            if let Some(update) = progress.observe(&event, &settings.voice, rate, voice.last_read())
+            {
+                print!("\r\x1b[K{}", update.title);
+                std::io::stdout().flush().ok();
+            }
+        }
+        if status.running_state == RunningState::Done {
+            println!();
+            break;
+        }
+    }
+    settings.time_estimator = progress.get_time_estimator();
+    settings.to_file();
+    if !settings.keep_history {
+        history.truncate(0);
+    }
+    history.to_file();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_engine::*;
+    use crate::memory_ui::*;
+    use crate::time_estimator::TimeEstimator;
+
+    fn test_state() -> State<MemoryEngine, MemoryUi> {
+        let mut ui = MemoryUi::new(Settings::new());
+        let mut state = State {
+            voice: MemoryEngine::new(vec![ui.settings.voice.clone()]),
+            window: ui.new_view(),
+            ui,
+            document: Document::new(String::new(), &[]),
+            message: false,
+            started_at: None,
+            background: Vec::new(),
+            history_id: None,
+            bookmark_key: None,
+            bookmarks: Bookmarks::default(),
+            resume_offer: None,
+            queue: ReadingQueue::default(),
+            events: SpeechEvents::default(),
+            progress: Progress::new(Default::default()),
+            copy_filter: CopyFilter::default(),
+            sources: BTreeMap::new(),
+        };
+        state.follow_along();
+        state
+    }
+
+    #[test]
+    fn play_pause() {
+        let mut state = test_state();
+        state.speak("Hello world");
+        state.match_hotkey_id(Action::PlayPause);
+        state.match_hotkey_id(Action::PlayPause);
+        assert_eq!(
+            state.voice.calls,
+            vec![
+                Call::Speak("Hello world".to_string()),
+                Call::Pause,
+                Call::Resume
+            ]
+        );
+    }
+
+    #[test]
+    fn play_pause_when_done() {
+        let mut state = test_state();
+        state.speak("Hello");
+        state.voice.wait();
+        state.match_hotkey_id(Action::PlayPause);
+        assert_eq!(state.voice.calls.last(), Some(&Call::Resume));
+    }
+
+    #[test]
+    fn skip_sentences() {
+        let mut state = test_state();
+        state.speak("One. Two. Three.");
+        state.voice.emit_word_boundary();
+        state.match_hotkey_id(Action::NextSentence);
+        assert_eq!(state.voice.calls.last(), Some(&Call::SpeakFrom(5)));
+        state.voice.emit_word_boundary();
+        state.match_hotkey_id(Action::NextSentence);
+        assert_eq!(state.voice.calls.last(), Some(&Call::SpeakFrom(10)));
+        state.voice.emit_word_boundary();
+        state.match_hotkey_id(Action::PreviousSentence);
+        assert_eq!(state.voice.calls.last(), Some(&Call::SpeakFrom(5)));
+    }
+
+    #[test]
+    fn skip_paragraphs() {
+        let mut state = test_state();
+        state.speak("One.\nTwo.\nThree.");
+        state.voice.emit_word_boundary();
+        state.match_hotkey_id(Action::NextParagraph);
+        assert_eq!(state.voice.calls.last(), Some(&Call::SpeakFrom(5)));
+        state.match_hotkey_id(Action::PreviousParagraph);
+        assert_eq!(state.voice.calls.last(), Some(&Call::SpeakFrom(0)));
+    }
+
+    #[test]
+    fn seek_by_time() {
+        let mut state = test_state();
+        state.ui.settings.skip_seconds = 1.0;
+        let mut t = TimeEstimator::default();
+        let voice = state.ui.settings.voice.clone();
+        t.learn(&voice, 0, 100_000.0); // 10 utf16 a second
+        state.progress.set_time_estimator(&t);
+        state.speak("zero one two three four five six seven eight nine");
+        state.voice.emit_word_boundary();
+        state.match_hotkey_id(Action::SeekForward);
+        // "two" at 9 is closer to 10 than "three" at 13
+        assert_eq!(state.voice.calls.last(), Some(&Call::SpeakFrom(9)));
+        state.voice.emit_word_boundary();
+        state.match_hotkey_id(Action::SeekBack);
+        assert_eq!(state.voice.calls.last(), Some(&Call::SpeakFrom(0)));
+    }
+
+    #[test]
+    fn seek_by_clicking_progress() {
+        let mut state = test_state();
+        state.speak("zero one two three four five six seven eight nine");
+        state.voice.emit_word_boundary();
+        let window = state.window.id();
+        state.seek_document(window, PROGRESS_RANGE / 2);
+        // "five" at 24 is nearest the middle at 25
+        assert_eq!(state.voice.calls.last(), Some(&Call::SpeakFrom(24)));
+        state.seek_document(window, PROGRESS_RANGE);
+        assert_eq!(state.voice.calls.last(), Some(&Call::SpeakFrom(45)));
+        // not one of ours
+        state.seek_document(0, 0);
+        assert_eq!(state.voice.calls.last(), Some(&Call::SpeakFrom(45)));
+    }
+
+    #[test]
+    fn queue_is_read_in_order() {
+        let mut state = test_state();
+        state.speak("One");
+        state
+            .queue
+            .push_back(Document::new("Two".to_string(), &[]), None);
+        state
+            .queue
+            .push_back(Document::new("Three".to_string(), &[]), None);
+        state.show_queue();
+        state.voice.wait();
+        state.speech_event();
+        assert_eq!(
+            state.voice.calls.last(),
+            Some(&Call::Speak("Two".to_string()))
+        );
+        state.match_hotkey_id(Action::NextInQueue);
+        assert_eq!(
+            state.voice.calls.last(),
+            Some(&Call::Speak("Three".to_string()))
+        );
+        assert!(state.queue.is_empty());
+        state.voice.wait();
+        state.speech_event();
+        assert_eq!(state.voice.calls.last(), Some(&Call::Wait));
+    }
+
+    #[test]
+    fn announce_then_resume() {
+        let mut state = test_state();
+        state.speak("Hello big world");
+        for _ in 0..2 {
+            state.voice.emit_word_boundary();
+            state.speech_event();
+        }
+        state
+            .queue
+            .push_back(Document::new("Next".to_string(), &[]), None);
+        state.match_hotkey_id(Action::AnnounceQueue);
+        assert_eq!(
+            state.voice.calls.last(),
+            Some(&Call::Speak("1 item queued".to_string()))
+        );
+        assert_eq!(state.queue.len(), 2);
+        state.voice.wait();
+        state.speech_event();
+        assert_eq!(state.voice.calls.last(), Some(&Call::SpeakFrom(6)));
+        assert_eq!(state.document.text().as_string(), "Hello big world");
+        state.match_hotkey_id(Action::ClearQueue);
+        assert!(state.queue.is_empty());
+    }
+
+    #[test]
+    fn step_through_history() {
+        let mut state = test_state();
+        for text in ["one", "two", "three"] {
+            let doc = Document::new(text.to_string(), &[]);
+            let id = state.remember(&doc);
+            state.read_document(doc, id);
+            state.voice.emit_word_boundary();
+            state.speech_event();
+        }
+        state.speak("reloaded settings");
+        state.match_hotkey_id(Action::HistoryBack);
+        assert_eq!(
+            state.voice.calls.last(),
+            Some(&Call::Speak("three".to_string()))
+        );
+        state.match_hotkey_id(Action::HistoryBack);
+        state.match_hotkey_id(Action::HistoryBack);
+        assert_eq!(
+            state.voice.calls.last(),
+            Some(&Call::Speak("one".to_string()))
+        );
+        state.match_hotkey_id(Action::HistoryForward);
+        assert_eq!(
+            state.voice.calls.last(),
+            Some(&Call::Speak("two".to_string()))
+        );
+        let entry = state.ui.history.before(None).unwrap();
+        assert_eq!(entry.position, 0);
+        assert_eq!(entry.cleaned, "three");
+    }
+
+    #[test]
+    fn history_can_be_disabled() {
+        let mut state = test_state();
+        state.ui.settings.keep_history = false;
+        assert_eq!(state.remember(&Document::new("one".to_string(), &[])), None);
+        assert!(state.ui.history.before(None).is_none());
+
+        // what was kept before it was turned off is forgotten too
+        let mut state = test_state();
+        state.remember(&Document::new("one".to_string(), &[]));
+        state.store_history();
+        assert!(state.ui.history.before(None).is_some());
+        state.ui.settings.keep_history = false;
+        state.store_history();
+        assert!(state.ui.history.before(None).is_none());
+    }
+
+    #[test]
+    fn offer_to_resume() {
+        let mut state = test_state();
+        let text = "word ".repeat(MIN_BOOKMARK_LEN);
+        state.read_document(Document::new(text.clone(), &[]), None);
+        for _ in 0..3 {
+            state.voice.emit_word_boundary();
+            state.speech_event();
+        }
+        state.speak("something else");
+        state.read_offering_bookmark(Document::new(text.clone(), &[]), None);
+        assert_eq!(
+            state.voice.calls.last(),
+            Some(&Call::Speak("bookmark at 0 percent.".to_string()))
+        );
+        state.match_hotkey_id(Action::ResumeBookmark);
+        assert_eq!(state.voice.calls.last(), Some(&Call::SpeakFrom(10)));
+        assert!(state.queue.is_empty());
+        // read to the end, so there is nothing left to resume
+        while state.voice.emit_word_boundary().is_some() {
+            state.speech_event();
+        }
+        state.speech_event();
+        state.read_offering_bookmark(Document::new(text, &[]), None);
+        assert_eq!(
+            state.voice.calls.last().unwrap(),
+            &Call::Speak("word ".repeat(MIN_BOOKMARK_LEN))
+        );
+    }
+
+    #[test]
+    fn read_opens_windows() {
+        let mut state = test_state();
+        state.ui.settings.read_opens_window = true;
+        state.open_document(Document::new("one two".to_string(), &[]), None);
+        assert!(state.background.is_empty());
+        for _ in 0..2 {
+            state.voice.emit_word_boundary();
+            state.speech_event();
+        }
+        state.open_document(Document::new("three".to_string(), &[]), None);
+        assert_eq!(state.background.len(), 1);
+        assert_eq!(state.background[0].position, 4);
+        state.activate(0);
+        assert_eq!(state.document.text().as_string(), "one two");
+        assert_eq!(state.voice.calls.last(), Some(&Call::SpeakFrom(4)));
+        assert_eq!(state.background[0].document.text().as_string(), "three");
+        let window = state.window.id();
+        state.close_document(window);
+        assert!(state.background.is_empty());
+        assert_eq!(state.document.text().as_string(), "three");
+        assert_eq!(state.voice.calls.last(), Some(&Call::Pause));
+    }
+
+    #[test]
+    fn session_round_trip() {
+        let mut state = test_state();
+        state.ui.settings.read_opens_window = true;
+        state.open_document(Document::new("one two".to_string(), &[]), Some(1));
+        state.open_document(Document::new("three".to_string(), &[]), None);
+        state.voice.emit_word_boundary();
+        state
+            .queue
+            .push_back(Document::new("four".to_string(), &[]), None);
+        state
+            .queue
+            .push_front(Document::new("five six".to_string(), &[]), 5, None);
+        let session = state.session();
+        assert_eq!(session.documents.len(), 2);
+        assert_eq!(session.queue.len(), 2);
+        state.speak("not saved");
+        assert_eq!(state.session().documents.len(), 1);
+
+        let mut state = test_state();
+        state.restore(session);
+        assert_eq!(state.background.len(), 1);
+        assert_eq!(state.background[0].history_id, Some(1));
+        let raw: Vec<&str> = state.queue.iter().map(|q| q.document.raw()).collect();
+        assert_eq!(raw, vec!["five six", "four"]);
+        // waits for play, not reading on its own
+        assert_eq!(state.document.text().as_string(), "three");
+        assert_eq!(state.voice.get_status().running_state, RunningState::Idle);
+        assert_eq!(state.voice.emit_word_boundary(), None);
+        state.match_hotkey_id(Action::PlayPause);
+        assert!(state.voice.emit_word_boundary().is_some());
+    }
+
+    #[test]
+    fn restore_where_it_was_left() {
+        let saved = |raw: &str, position| SavedDocument {
+            raw: raw.to_string(),
+            position,
+            history_id: None,
+            placement: None,
+        };
+        let session = Session {
+            documents: vec![saved("one two three", 4), saved("four", 2)],
+            queue: vec![saved("five", 3)],
+        };
+        let mut state = test_state();
+        state.restore(session);
+        assert_eq!(state.voice.calls.last(), Some(&Call::Pause));
+        assert!(state.voice.calls.contains(&Call::SpeakFrom(4)));
+        // nothing was read yet, so it is still where it was left
+        assert_eq!(state.session().documents[0].position, 4);
+
+        // the text got shorter since it was saved
+        let session = Session {
+            documents: vec![saved("one", 40), saved("two", 50)],
+            queue: vec![saved("three", 60)],
+        };
+        let mut state = test_state();
+        state.restore(session);
+        assert!(state.voice.calls.contains(&Call::SpeakFrom(3)));
+        assert_eq!(state.background[0].position, 3);
+        assert_eq!(state.queue.iter().next().unwrap().start, 5);
+        state.activate(0);
+        assert_eq!(state.document.text().as_string(), "two");
+        assert_eq!(state.voice.calls.last(), Some(&Call::SpeakFrom(3)));
+    }
+
+    #[test]
+    fn reading_past_a_bookmark_forgets_the_offer() {
+        let mut state = test_state();
+        let doc = Document::new("word ".repeat(300), &[]);
+        let key = bookmark_key(doc.text()).unwrap();
+        state.bookmarks.set(&key, 10, doc.text().len() - 1);
+        state.read_offering_bookmark(doc, None);
+        assert_eq!(state.resume_offer, Some((key, 10)));
+        while state.voice.emit_word_boundary().is_some() {
+            state.speech_event();
+        }
+        // done saying where the bookmark is, on to the document
+        state.speech_event();
+        for _ in 0..2 {
+            state.voice.emit_word_boundary();
+            state.speech_event();
+        }
+        assert!(state.resume_offer.is_some());
+        state.voice.emit_word_boundary();
+        state.speech_event();
+        assert_eq!(state.resume_offer, None);
+    }
+
+    #[test]
+    fn read_from_the_bound_source() {
+        let mut state = test_state();
+        let script = [
+            Ok("from the script".to_string()),
+            Err("nothing".to_string()),
+        ];
+        state
+            .sources
+            .insert(Action::Read, Box::new(ScriptedSource::new(script)));
+        state.match_hotkey_id(Action::Read);
+        assert!(state
+            .document
+            .text()
+            .as_string()
+            .contains("from the script"));
+        assert!(!state.message);
+        state.match_hotkey_id(Action::Read);
+        assert!(state.message);
+        assert_eq!(state.document.text().as_string(), "nothing");
+        // nothing is bound to it here
+        state.match_hotkey_id(Action::ReadClipboard);
+        assert!(state.document.text().as_string().contains("no text source"));
+    }
+
+    #[test]
+    fn speak_on_copy() {
+        let mut state = test_state();
+        state.ui.settings.speak_on_copy.enabled = true;
+        let copy =
+            |state: &mut State<MemoryEngine, MemoryUi>, seq: u32, private, owner: Option<&str>| {
+                let change = ClipboardChange {
+                    seq,
+                    own_seq: None,
+                    private,
+                    owner: owner.map(str::to_string),
+                };
+                state.ui.clipboard = Some((change, format!("copy {}", seq)));
+                state.clipboard_changed();
+                state.document.text().as_string()
+            };
+        let notepad = Some(r"C:\Windows\notepad.exe");
+        assert!(copy(&mut state, 1, false, notepad).contains("copy 1"));
+        assert!(!copy(&mut state, 2, true, notepad).contains("copy 2"));
+        assert!(!copy(&mut state, 3, false, Some("KeePass.exe")).contains("copy 3"));
+        // the owner could not be found, so it could be a password manager
+        assert!(!copy(&mut state, 4, false, None).contains("copy 4"));
+        assert!(copy(&mut state, 5, false, notepad).contains("copy 5"));
+    }
+
+    #[test]
+    fn speech_event_follows_words() {
+        let mut state = test_state();
+        state.speak("Hello world");
+        state.speech_event();
+        while state.voice.emit_word_boundary().is_some() {
+            state.speech_event();
+        }
+        state.speech_event();
+        assert_eq!(state.window.title, "100.0% 0:00 rust_reader");
+    }
+
+    #[test]
+    fn ticker_and_progress_follow_words() {
+        let mut state = test_state();
+        state.speak("Hello big world");
+        state.voice.emit_word_boundary();
+        state.speech_event();
+        state.voice.emit_word_boundary();
+        state.speech_event();
+        assert_eq!(state.ui.ticker_word.as_deref(), Some("big"));
+        assert_eq!(state.window.selection, 6..9);
+        assert_eq!(state.window.progress.0, 37.5);
+        assert!(state.window.title.starts_with("37.5% "));
+        assert!(state.window.title.ends_with(" \"big\" rust_reader"));
+    }
+
+    #[test]
+    fn windows_are_shown_and_closed_together() {
+        let mut state = test_state();
+        state.ui.settings.read_opens_window = true;
+        state.open_document(Document::new("one".to_string(), &[]), None);
+        state.open_document(Document::new("two".to_string(), &[]), None);
+        state.match_hotkey_id(Action::ToggleWindowVisible);
+        assert!(!state.window.is_visible());
+        assert!(!state.background[0].window.is_visible());
+        state.match_hotkey_id(Action::ToggleWindowVisible);
+        assert!(state.background[0].window.is_visible());
+        let background = state.background[0].window.id();
+        state.close_document(background);
+        assert!(!state.ui.quit);
+        let window = state.window.id();
+        state.close_document(window);
+        assert!(state.ui.quit);
+    }
 }
@@ -0,0 +1,252 @@
+use std::cmp::min;
+use std::fs::File;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+
+use crate::speech_engine::*;
+use crate::wav::*;
+use rust_reader::wide_string::*;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Call {
+    Speak(String),
+    SpeakFrom(usize),
+    SpeakToWav(String),
+    Wait,
+    Pause,
+    Resume,
+    SetRate(i32),
+    SetVoice(String),
+    SetVolume(u16),
+}
+
+/// A `SpeechEngine` that makes no sound, for testing without COM.
+///
+/// It records every call made to it, and only moves on to the next word
+/// when `emit_word_boundary` is called.
+pub struct MemoryEngine {
+    pub calls: Vec<Call>,
+    voices: Vec<String>,
+    voice: String,
+    rate: i32,
+    volume: u16,
+    paused: bool,
+    /// Where `speak_from` asked to start.
+    start: usize,
+    status: Status,
+    last_read: WideString,
+}
+
+impl MemoryEngine {
+    pub fn new(voices: Vec<String>) -> Box<MemoryEngine> {
+        Box::new(MemoryEngine {
+            calls: Vec::new(),
+            voice: voices.first().cloned().unwrap_or("unknown".to_string()),
+            voices,
+            rate: 0,
+            volume: 100,
+            paused: false,
+            start: 0,
+            status: Status {
+                running_state: RunningState::Idle,
+                word_range: 0..0,
+                sent_range: 0..0,
+            },
+            last_read: WideString::new(),
+        })
+    }
+
+    fn start(&mut self, text: WideString) {
+        self.status = Status {
+            running_state: RunningState::Speaking,
+            word_range: 0..0,
+            sent_range: 0..(text.len() - 1),
+        };
+        self.last_read = text;
+        self.start = 0;
+    }
+
+    /// Pretend to read the next word, returns its range in `last_read`.
+    ///
+    /// Returns `None` once all the words have been read or while paused.
+    pub fn emit_word_boundary(&mut self) -> Option<Range<usize>> {
+        if self.paused || self.status.running_state != RunningState::Speaking {
+            return None;
+        }
+        let text = self.last_read.as_string();
+        let mut start = None;
+        let mut pos = 0;
+        for c in text.chars() {
+            if pos >= self.status.word_range.end && pos >= self.start {
+                match (c.is_whitespace(), start) {
+                    (true, Some(s)) => {
+                        self.status.word_range = s..pos;
+                        return Some(s..pos);
+                    }
+                    (false, None) => start = Some(pos),
+                    _ => {}
+                }
+            }
+            pos += c.len_utf16();
+        }
+        match start {
+            Some(s) => {
+                self.status.word_range = s..pos;
+                Some(s..pos)
+            }
+            None => {
+                self.status.running_state = RunningState::Done;
+                None
+            }
+        }
+    }
+}
+
+impl SpeechEngine for MemoryEngine {
+    fn speak(&mut self, text: WideString) {
+        self.calls.push(Call::Speak(text.as_string()));
+        self.start(text);
+    }
+
+    fn speak_from(&mut self, offset: usize) {
+        self.calls.push(Call::SpeakFrom(offset));
+        self.status = Status {
+            running_state: RunningState::Speaking,
+            word_range: 0..0,
+            sent_range: offset..(self.last_read.len() - 1),
+        };
+        self.start = offset;
+    }
+
+    /// Writes a silent wav file, pretending each word takes 100ms.
+    fn speak_to_wav(&mut self, text: WideString, path: &Path) -> io::Result<Vec<WordTiming>> {
+        self.calls.push(Call::SpeakToWav(text.as_string()));
+        self.start(text);
+        let mut timings = vec![];
+        while let Some(word_range) = self.emit_word_boundary() {
+            timings.push(WordTiming {
+                ms: 100 * timings.len() as u64,
+                word_range,
+            });
+        }
+        write_wav(File::create(path)?, 22050, &[])?;
+        Ok(timings)
+    }
+
+    fn wait(&mut self) {
+        self.calls.push(Call::Wait);
+        while self.emit_word_boundary().is_some() {}
+    }
+
+    fn pause(&mut self) {
+        self.calls.push(Call::Pause);
+        self.paused = true;
+    }
+
+    fn resume(&mut self) {
+        self.calls.push(Call::Resume);
+        self.paused = false;
+    }
+
+    fn set_rate(&mut self, rate: i32) -> i32 {
+        self.calls.push(Call::SetRate(rate));
+        self.rate = rate.clamp(-10, 10);
+        self.rate
+    }
+
+    fn get_rate(&mut self) -> i32 {
+        self.rate
+    }
+
+    fn available_voice_names(&mut self) -> Vec<String> {
+        self.voices.clone()
+    }
+
+    fn set_voice_by_name(&mut self, voice_name: String) -> String {
+        self.calls.push(Call::SetVoice(voice_name.clone()));
+        if self.voices.contains(&voice_name) {
+            self.voice = voice_name;
+        }
+        self.voice.clone()
+    }
+
+    fn set_volume(&mut self, volume: u16) {
+        self.calls.push(Call::SetVolume(volume));
+        self.volume = min(volume, 100);
+    }
+
+    fn get_volume(&mut self) -> u16 {
+        self.volume
+    }
+
+    fn get_status(&mut self) -> Status {
+        let mut status = self.status.clone();
+        if self.paused && status.running_state == RunningState::Speaking {
+            status.running_state = RunningState::Idle;
+        }
+        status
+    }
+
+    fn last_read(&self) -> &WideString {
+        &self.last_read
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn emits_each_word() {
+        let mut engine = MemoryEngine::new(vec![]);
+        engine.speak("Hello  big \u{1d565}world".into());
+        assert_eq!(engine.emit_word_boundary(), Some(0..5));
+        assert_eq!(engine.emit_word_boundary(), Some(7..10));
+        assert_eq!(engine.emit_word_boundary(), Some(11..18));
+        assert_eq!(engine.get_status().running_state, RunningState::Speaking);
+        assert_eq!(engine.emit_word_boundary(), None);
+        assert_eq!(engine.get_status().running_state, RunningState::Done);
+        assert_eq!(engine.get_status().word_range, 11..18);
+    }
+
+    #[test]
+    fn paused_does_not_move() {
+        let mut engine = MemoryEngine::new(vec![]);
+        engine.speak("Hello world".into());
+        engine.pause();
+        assert_eq!(engine.get_status().running_state, RunningState::Idle);
+        assert_eq!(engine.emit_word_boundary(), None);
+        engine.resume();
+        assert_eq!(engine.emit_word_boundary(), Some(0..5));
+        assert_eq!(
+            engine.calls,
+            vec![
+                Call::Speak("Hello world".to_string()),
+                Call::Pause,
+                Call::Resume
+            ]
+        );
+    }
+
+    #[test]
+    fn speak_from_the_middle() {
+        let mut engine = MemoryEngine::new(vec![]);
+        engine.speak("Hello big world".into());
+        assert_eq!(engine.emit_word_boundary(), Some(0..5));
+        engine.speak_from(6);
+        assert_eq!(engine.get_status().word_range, 0..0);
+        assert_eq!(engine.emit_word_boundary(), Some(6..9));
+        engine.speak_from(0);
+        assert_eq!(engine.emit_word_boundary(), Some(0..5));
+    }
+
+    #[test]
+    fn voice_must_be_available() {
+        let mut engine = MemoryEngine::new(vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(engine.set_voice_by_name("b".to_string()), "b");
+        assert_eq!(engine.set_voice_by_name("c".to_string()), "b");
+        assert_eq!(engine.set_rate(11), 10);
+        assert_eq!(engine.change_rate(-1), 9);
+    }
+}
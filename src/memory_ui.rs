@@ -0,0 +1,189 @@
+use std::ops::Range;
+use std::time::Instant;
+
+use crate::history::History;
+use crate::session::Placement;
+use crate::settings::Settings;
+use crate::speak_on_copy::ClipboardChange;
+use crate::ticker::TickerSettings;
+use crate::ui::*;
+use rust_reader::clean_text::Dictionary;
+use rust_reader::wide_string::WideString;
+
+/// A `DocumentView` that only remembers what it was last told to show.
+#[derive(Debug)]
+pub struct MemoryView {
+    pub id: usize,
+    pub text: WideString,
+    pub rate: i32,
+    pub title: String,
+    pub selection: Range<usize>,
+    pub progress: (f64, String),
+    pub queue: Vec<String>,
+    pub placement: Placement,
+    /// Set it to pretend the user switched to this view.
+    pub activated: Option<Instant>,
+}
+
+impl DocumentView for MemoryView {
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    fn set_text(&mut self, text: &WideString) {
+        self.text = text.clone();
+    }
+
+    fn set_rate(&mut self, rate: i32) {
+        self.rate = rate;
+    }
+
+    fn set_title(&mut self, title: &WideString) {
+        self.title = title.as_string();
+    }
+
+    fn select(&mut self, range: &Range<usize>) {
+        self.selection = range.clone();
+    }
+
+    fn set_progress(&mut self, percent: f64, text: &str) {
+        self.progress = (percent, text.to_string());
+    }
+
+    fn set_queue(&mut self, items: &[String]) {
+        self.queue = items.to_vec();
+    }
+
+    fn placement(&self) -> Placement {
+        self.placement
+    }
+
+    fn set_placement(&mut self, placement: &Placement) {
+        self.placement = *placement;
+    }
+
+    fn last_activated(&self) -> Option<Instant> {
+        self.activated
+    }
+
+    fn is_visible(&self) -> bool {
+        self.placement.visible
+    }
+
+    fn set_visible(&mut self, visible: bool) {
+        self.placement.visible = visible;
+    }
+}
+
+/// A `Ui` without windows, for testing `State`.
+///
+/// Nothing is saved to disk, and the clipboard is whatever the test puts in `clipboard`.
+pub struct MemoryUi {
+    pub settings: Settings,
+    pub history: History,
+    next_id: usize,
+    /// The last word shown on the ticker.
+    pub ticker_word: Option<String>,
+    pub listening: bool,
+    pub clipboard: Option<(ClipboardChange, String)>,
+    pub quit: bool,
+}
+
+impl MemoryUi {
+    pub fn new(settings: Settings) -> MemoryUi {
+        MemoryUi {
+            settings,
+            history: History::default(),
+            next_id: 1,
+            ticker_word: None,
+            listening: false,
+            clipboard: None,
+            quit: false,
+        }
+    }
+}
+
+impl Ui for MemoryUi {
+    type View = MemoryView;
+
+    fn new_view(&mut self) -> Box<MemoryView> {
+        self.next_id += 1;
+        Box::new(MemoryView {
+            id: self.next_id,
+            text: WideString::new(),
+            rate: 0,
+            title: String::new(),
+            selection: 0..0,
+            progress: (0.0, String::new()),
+            queue: Vec::new(),
+            placement: Placement {
+                left: 0,
+                top: 0,
+                right: 0,
+                bottom: 0,
+                visible: true,
+            },
+            activated: None,
+        })
+    }
+
+    fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    fn settings_mut(&mut self) -> &mut Settings {
+        &mut self.settings
+    }
+
+    fn save_settings(&mut self) {}
+
+    fn show_settings(&mut self) {}
+
+    fn edited_dictionary(&self) -> Dictionary {
+        self.settings.dictionary.clone()
+    }
+
+    fn register_hotkeys(&mut self) {}
+
+    fn hotkey_names(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn history(&self) -> &History {
+        &self.history
+    }
+
+    fn history_mut(&mut self) -> &mut History {
+        &mut self.history
+    }
+
+    fn refresh_history(&mut self) {}
+
+    fn save_history(&mut self) {}
+
+    fn show_history(&mut self) {}
+
+    fn set_ticker_settings(&mut self, _settings: &TickerSettings) {}
+
+    fn show_word(&mut self, text: &WideString, word: &Range<usize>) {
+        self.ticker_word = Some(text.get_slice(word.clone()));
+    }
+
+    fn toggle_ticker(&mut self) {}
+
+    fn listen_for_copies(&mut self, enabled: bool, _debounce_ms: u32) {
+        self.listening = enabled;
+    }
+
+    fn clipboard_change(&mut self) -> Option<ClipboardChange> {
+        self.clipboard.as_ref().map(|c| c.0.clone())
+    }
+
+    fn clipboard_text(&mut self) -> Option<String> {
+        self.clipboard.as_ref().map(|c| c.1.clone())
+    }
+
+    fn quit(&mut self) {
+        self.quit = true;
+    }
+}
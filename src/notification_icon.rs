@@ -0,0 +1,103 @@
+use std::mem::size_of;
+use std::mem::zeroed;
+
+use windows::core::PCWSTR;
+use windows::w;
+use windows::Win32::{
+    Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, WPARAM},
+    System::LibraryLoader,
+    UI::Shell,
+    UI::WindowsAndMessaging as wm,
+};
+
+use crate::window::*;
+
+pub const WM_APP_NOTIFICATION_ICON: u32 = wm::WM_APP + 16;
+
+/// The icon in the notification area, in a hidden window of its own,
+/// so it stays as long as the reader runs whichever reader windows are closed.
+pub struct NotificationIcon {
+    window: HWND,
+    nicon: Shell::NOTIFYICONDATAW,
+}
+
+impl NotificationIcon {
+    pub fn new() -> Box<NotificationIcon> {
+        let mut out = Box::new(NotificationIcon {
+            window: HWND(0),
+            nicon: unsafe { zeroed() },
+        });
+
+        let window_class_name = w!("notification_icon_class_name");
+        unsafe {
+            wm::RegisterClassW(&wm::WNDCLASSW {
+                style: wm::WNDCLASS_STYLES(0),
+                lpfnWndProc: Some(window_proc_generic::<NotificationIcon>),
+                cbClsExtra: 0,
+                cbWndExtra: 0,
+                hInstance: HINSTANCE(0),
+                hIcon: wm::HICON(0),
+                hCursor: wm::HCURSOR(0),
+                hbrBackground: windows::Win32::Graphics::Gdi::HBRUSH(0),
+                lpszMenuName: PCWSTR::null(),
+                lpszClassName: window_class_name,
+            });
+            out.window = wm::CreateWindowExW(
+                wm::WINDOW_EX_STYLE(0),
+                window_class_name,
+                PCWSTR(&mut 0u16),
+                wm::WINDOW_STYLE(0),
+                0,
+                0,
+                0,
+                0,
+                wm::HWND_MESSAGE,
+                wm::HMENU(0),
+                HINSTANCE(0),
+                Some(&mut *out as *mut _ as *mut _),
+            );
+
+            let nicon = &mut out.nicon;
+            nicon.cbSize = size_of::<Shell::NOTIFYICONDATAW>() as u32;
+            nicon.hWnd = out.window;
+            nicon.uCallbackMessage = WM_APP_NOTIFICATION_ICON;
+            nicon.uID = 1 as u32;
+            nicon.uFlags |= Shell::NIF_ICON;
+            nicon.hIcon = wm::LoadIconW(
+                LibraryLoader::GetModuleHandleW(PCWSTR::null()).unwrap(),
+                PCWSTR::from_raw(1 as *const u16),
+            )
+            .expect("failed to load icon");
+            nicon.uFlags |= Shell::NIF_MESSAGE;
+            nicon.Anonymous.uVersion = Shell::NOTIFYICON_VERSION_4;
+            let err = Shell::Shell_NotifyIconW(Shell::NIM_ADD, nicon);
+            if err == false {
+                panic!("failed for Shell_NotifyIconW NIM_ADD");
+            }
+
+            let err = Shell::Shell_NotifyIconW(Shell::NIM_SETVERSION, nicon);
+            if err == false {
+                panic!("failed for Shell_NotifyIconW ");
+            }
+        }
+        out
+    }
+}
+
+impl Windowed for NotificationIcon {
+    fn window_proc(&mut self, msg: u32, _w_param: WPARAM, l_param: LPARAM) -> Option<LRESULT> {
+        if msg == WM_APP_NOTIFICATION_ICON && (l_param.0 & 0xffff) as u32 == wm::WM_LBUTTONUP {
+            crate::press_hotkey(crate::Action::ToggleWindowVisible);
+            return Some(LRESULT(0));
+        }
+        None
+    }
+}
+
+impl Drop for NotificationIcon {
+    fn drop(&mut self) {
+        unsafe { Shell::Shell_NotifyIconW(Shell::NIM_DELETE, &self.nicon) };
+        destroy_window(self.window);
+        println!("drop for NotificationIcon");
+    }
+}
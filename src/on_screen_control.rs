@@ -90,6 +90,12 @@ impl OnScreenControlWindow {
     }
 }
 
+impl Drop for OnScreenControlWindow {
+    fn drop(&mut self) {
+        destroy_window(self.window);
+    }
+}
+
 impl Windowed for OnScreenControlWindow {
     fn window_proc(&mut self, msg: u32, w_param: WPARAM, l_param: LPARAM) -> Option<LRESULT> {
         match msg {
@@ -0,0 +1,282 @@
+use std::ops::Range;
+use std::time::Duration;
+
+use crate::speech_events::SpeechEvent;
+use crate::time_estimator::*;
+use rust_reader::wide_string::WideString;
+
+/// What to show the user after a word boundary.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgressUpdate {
+    pub title: String,
+    /// The word now being read, if there is one to highlight.
+    pub word_range: Option<Range<usize>>,
+    pub percent: f64,
+    /// How long this document has been read for, not counting pauses.
+    pub elapsed: chrono::Duration,
+    /// How long until the end of this document, not counting the queue.
+    pub time_left: chrono::Duration,
+}
+
+impl ProgressUpdate {
+    /// For the text next to the progress bar.
+    pub fn bar_text(&self) -> String {
+        format!(
+            "{:.1}%  {} elapsed  {} left",
+            self.percent,
+            format_duration(self.elapsed),
+            format_duration(self.time_left)
+        )
+    }
+}
+
+/// Keeps track of how fast we are reading so we can say how long is left.
+///
+/// It follows the `SpeechEvents` of the document being read.
+pub struct Progress {
+    elapsed: Duration,
+    estimator: TimeEstimator,
+    /// How many documents are queued after this one, and their length in utf16 units.
+    queued: (usize, usize),
+}
+
+impl Progress {
+    pub fn new(estimator: TimeEstimator) -> Progress {
+        Progress {
+            elapsed: Duration::ZERO,
+            estimator,
+            queued: (0, 0),
+        }
+    }
+
+    /// Use what was learned in `t`, keeping the clock.
+    pub fn set_time_estimator(&mut self, t: &TimeEstimator) {
+        self.estimator.load(t);
+    }
+
+    pub fn get_time_estimator(&self) -> TimeEstimator {
+        self.estimator.clone()
+    }
+
+    /// Include the queue in the time left.
+    pub fn set_queued(&mut self, items: usize, utf16_len: usize) {
+        self.queued = (items, utf16_len);
+    }
+
+    /// Forget the last word boundary, so the time between now and the next one is not counted.
+    ///
+    /// Call this whenever reading starts, stops, or changes speed.
+    pub fn reset(&mut self) {
+        self.estimator.reset();
+    }
+
+    /// A new document is being read.
+    pub fn start(&mut self) {
+        self.reset();
+        self.elapsed = Duration::ZERO;
+    }
+
+    /// What to show after `event` of `last_read`, if it changes anything.
+    pub fn observe(
+        &mut self,
+        event: &SpeechEvent,
+        voice: &str,
+        rate: i32,
+        last_read: &WideString,
+    ) -> Option<ProgressUpdate> {
+        match event {
+            SpeechEvent::Word(word) => Some(self.word(&word.cleaned, voice, rate, last_read)),
+            SpeechEvent::End => Some(self.end()),
+            _ => None,
+        }
+    }
+
+    fn end(&mut self) -> ProgressUpdate {
+        self.reset();
+        ProgressUpdate {
+            title: "100.0% 0:00 rust_reader".to_string(),
+            word_range: None,
+            percent: 100.0,
+            elapsed: chrono::Duration::from_std(self.elapsed).expect("bad time diffrence."),
+            time_left: chrono::Duration::zero(),
+        }
+    }
+
+    fn word(
+        &mut self,
+        word_range: &Range<usize>,
+        voice: &str,
+        rate: i32,
+        last_read: &WideString,
+    ) -> ProgressUpdate {
+        let word_range = word_range.clone();
+        self.elapsed += self.estimator.boundary(voice, rate, &word_range);
+        let len_left = last_read.len() - word_range.end;
+        let estimate = self.estimator.estimate(voice, rate, len_left);
+        let time_left = estimate.expected;
+        let percent = 100.0 * (word_range.start as f64) / (last_read.len() as f64);
+        let mut title_time = format_estimate(&estimate);
+        if self.queued.0 > 0 {
+            title_time += &format!(
+                " (+{} queued: {})",
+                self.queued.0,
+                format_estimate(
+                    &self
+                        .estimator
+                        .estimate(voice, rate, len_left + self.queued.1)
+                )
+            );
+        }
+        ProgressUpdate {
+            title: format!(
+                "{:.1}% {} \"{}\" rust_reader",
+                percent,
+                title_time,
+                last_read.get_slice(word_range.clone())
+            ),
+            word_range: Some(word_range),
+            percent,
+            elapsed: chrono::Duration::from_std(self.elapsed).expect("bad time diffrence."),
+            time_left,
+        }
+    }
+
+    /// About how many utf16 units `voice` will read in `d` at `rate`.
+    pub fn utf16_in(&self, voice: &str, rate: i32, d: chrono::Duration) -> usize {
+        self.estimator.utf16_in(voice, rate, d)
+    }
+}
+
+/// The expected time, then the range it is likely in if that is more than a second wide.
+pub fn format_estimate(e: &Estimate) -> String {
+    let expected = format_duration(e.expected);
+    if (e.high - e.low).num_seconds() < 1 {
+        expected
+    } else {
+        format!(
+            "{} ({}-{})",
+            expected,
+            format_duration(e.low),
+            format_duration(e.high)
+        )
+    }
+}
+
+pub fn format_duration(d: chrono::Duration) -> String {
+    let h = d.num_hours();
+    let m = d.num_minutes() - d.num_hours() * 60;
+    let s = d.num_seconds() - d.num_minutes() * 60;
+    if d.num_hours() == 0 {
+        format!("{}:{:0>#2}", m, s)
+    } else {
+        format!("{}:{:0>#2}:{:0>#2}", h, m, s)
+    }
+}
+
+#[test]
+fn test_format_duration() {
+    let duration = chrono::Duration::hours(25);
+    assert_eq!(format_duration(duration), "25:00:00");
+    let duration = chrono::Duration::hours(1) + chrono::Duration::seconds(1);
+    assert_eq!(format_duration(duration), "1:00:01");
+    let duration = chrono::Duration::hours(1) - chrono::Duration::seconds(1);
+    assert_eq!(format_duration(duration), "59:59");
+    let duration = chrono::Duration::seconds(61);
+    assert_eq!(format_duration(duration), "1:01");
+    let duration = chrono::Duration::seconds(60);
+    assert_eq!(format_duration(duration), "1:00");
+    let duration = chrono::Duration::seconds(59);
+    assert_eq!(format_duration(duration), "0:59");
+    let duration = chrono::Duration::seconds(9);
+    assert_eq!(format_duration(duration), "0:09");
+    let duration = chrono::Duration::seconds(0);
+    assert_eq!(format_duration(duration), "0:00");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::speech_events::Span;
+
+    fn word(cleaned: Range<usize>) -> SpeechEvent {
+        SpeechEvent::Word(Span {
+            original: cleaned.clone(),
+            cleaned,
+        })
+    }
+
+    #[test]
+    fn follow_events() {
+        let clock = ManualClock::new();
+        let mut progress = Progress::new(TimeEstimator::with_clock(clock.clone()));
+        let text: WideString = "Hello world".into();
+        assert_eq!(progress.observe(&SpeechEvent::Start, "", 0, &text), None);
+        let up = progress.observe(&word(0..5), "", 0, &text).unwrap();
+        assert_eq!(up.word_range, Some(0..5));
+        assert!(up.title.starts_with("0.0% "));
+        assert!(up.title.ends_with(" \"Hello\" rust_reader"));
+        clock.advance(Duration::from_millis(300));
+        let up = progress.observe(&word(6..11), "", 0, &text).unwrap();
+        assert_eq!(up.word_range, Some(6..11));
+        assert_eq!(
+            progress.get_time_estimator().speed("", 0).unwrap().samples,
+            1
+        );
+        assert_eq!(up.percent, 50.0);
+        assert_eq!(up.elapsed, chrono::Duration::milliseconds(300));
+        assert!(up.bar_text().starts_with("50.0%  0:00 elapsed  "));
+        let up = progress.observe(&SpeechEvent::End, "", 0, &text).unwrap();
+        assert_eq!(up.title, "100.0% 0:00 rust_reader");
+        assert_eq!(up.word_range, None);
+    }
+
+    #[test]
+    fn time_left_includes_queue() {
+        let mut t = TimeEstimator::default();
+        t.learn("voice", 0, 500_000.0);
+        let mut progress = Progress::new(t);
+        progress.set_queued(2, 60);
+        let up = progress
+            .observe(&word(0..5), "voice", 0, &"Hello world".into())
+            .unwrap();
+        assert!(up.title.contains(" (+2 queued: 0:33)"));
+        // the bar is only for this document
+        assert_eq!(up.time_left, chrono::Duration::milliseconds(3500));
+        assert!(up.title.ends_with(" \"Hello\" rust_reader"));
+    }
+
+    #[test]
+    fn jumps_are_not_learned() {
+        let clock = ManualClock::new();
+        let mut progress = Progress::new(TimeEstimator::with_clock(clock.clone()));
+        let text: WideString = "a ".repeat(100).as_str().into();
+        let read = |progress: &mut Progress, cleaned: Range<usize>| {
+            clock.advance(Duration::from_millis(100));
+            progress.observe(&word(cleaned), "", 0, &text).unwrap();
+        };
+        read(&mut progress, 100..101);
+        // back
+        read(&mut progress, 10..11);
+        // forward a long way
+        read(&mut progress, 150..151);
+        assert_eq!(progress.get_time_estimator().speed("", 0), None);
+        // the next word
+        read(&mut progress, 152..153);
+        assert_eq!(
+            progress.get_time_estimator().speed("", 0).unwrap().samples,
+            1
+        );
+    }
+
+    #[test]
+    fn time_to_utf16() {
+        let mut progress = Progress::new(Default::default());
+        let ten_seconds = chrono::Duration::seconds(10);
+        assert_eq!(progress.utf16_in("voice", 0, ten_seconds), 166);
+        assert!(progress.utf16_in("voice", 10, ten_seconds) > 400);
+        let mut t = TimeEstimator::default();
+        t.learn("voice", 0, 100_000.0);
+        progress.set_time_estimator(&t);
+        assert_eq!(progress.utf16_in("voice", 0, ten_seconds), 100);
+    }
+}
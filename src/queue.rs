@@ -0,0 +1,120 @@
+use std::collections::VecDeque;
+
+use crate::document::Document;
+
+/// How much of each queued document to show in the window.
+const SUMMARY_LEN: usize = 60;
+
+/// A document waiting to be read, and where to start reading it.
+pub struct Queued {
+    pub document: Document,
+    /// In utf16 units of `document.text()`.
+    pub start: usize,
+    /// The id of its entry in the `History`.
+    pub history: Option<u64>,
+}
+
+/// The documents to read after the one being read now.
+#[derive(Default)]
+pub struct ReadingQueue {
+    items: VecDeque<Queued>,
+}
+
+impl ReadingQueue {
+    pub fn push_back(&mut self, document: Document, history: Option<u64>) {
+        self.items.push_back(Queued {
+            document,
+            start: 0,
+            history,
+        });
+    }
+
+    /// Put `document` first, to go back to it after an interruption.
+    pub fn push_front(&mut self, document: Document, start: usize, history: Option<u64>) {
+        self.items.push_front(Queued {
+            document,
+            start,
+            history,
+        });
+    }
+
+    pub fn pop_front(&mut self) -> Option<Queued> {
+        self.items.pop_front()
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Queued> {
+        self.items.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// How much there is left to read in the queue, in utf16 units.
+    pub fn utf16_len(&self) -> usize {
+        self.items
+            .iter()
+            .map(|q| (q.document.text().len() - 1).saturating_sub(q.start))
+            .sum()
+    }
+
+    /// The start of each queued document, for listing the queue.
+    pub fn summaries(&self) -> Vec<String> {
+        self.items
+            .iter()
+            .map(|q| {
+                let text = q.document.text();
+                let end = (q.start + SUMMARY_LEN).min(text.len() - 1);
+                let mut out = text.get_slice(q.start..end);
+                if end < text.len() - 1 {
+                    out += "...";
+                }
+                out
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc(s: &str) -> Document {
+        Document::new(s.to_string(), &[])
+    }
+
+    #[test]
+    fn first_in_first_out() {
+        let mut queue = ReadingQueue::default();
+        queue.push_back(doc("one"), None);
+        queue.push_back(doc("two"), Some(2));
+        queue.push_front(doc("zero and more"), 5, Some(1));
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.utf16_len(), 8 + 3 + 3);
+        assert_eq!(queue.summaries(), vec!["and more", "one", "two"]);
+        let first = queue.pop_front().unwrap();
+        assert_eq!(first.start, 5);
+        assert_eq!(first.history, Some(1));
+        assert_eq!(first.document.text().as_string(), "zero and more");
+        queue.clear();
+        assert!(queue.is_empty());
+        assert!(queue.pop_front().is_none());
+    }
+
+    #[test]
+    fn long_summaries_are_cut() {
+        let mut queue = ReadingQueue::default();
+        queue.push_back(doc(&"word ".repeat(20)), None);
+        let summary = &queue.summaries()[0];
+        assert_eq!(summary.len(), SUMMARY_LEN + 3);
+        assert!(summary.ends_with("..."));
+    }
+}
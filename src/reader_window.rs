@@ -0,0 +1,312 @@
+use std::ops::Range;
+use std::time::Instant;
+
+use windows::core::PCWSTR;
+use windows::w;
+use windows::Win32::{
+    Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, RECT, WPARAM},
+    Graphics::Gdi,
+    System::LibraryLoader,
+    System::Threading::GetCurrentThreadId,
+    UI::WindowsAndMessaging as wm,
+};
+
+use crate::on_screen_control::*;
+use crate::session::Placement;
+use crate::ui::*;
+use crate::window::*;
+
+/// Posted to the main thread with the `id` of the `ReaderWindow` to close in the `WPARAM`.
+pub const WM_APP_CLOSE_DOCUMENT: u32 = wm::WM_APP + 18;
+/// Posted to the main thread with the `id` of the `ReaderWindow` in the `WPARAM`,
+/// and where in its text to read from in the `LPARAM`, out of `PROGRESS_RANGE`.
+pub const WM_APP_SEEK_DOCUMENT: u32 = wm::WM_APP + 19;
+
+/// A window that shows the text of a document.
+pub struct ReaderWindow {
+    window: HWND,
+    controls: Box<OnScreenControlWindow>,
+    edit: HWND,
+    /// The reading queue, only shown when it is not empty.
+    queue: HWND,
+    queue_shown: bool,
+    progress: HWND,
+    progress_text: HWND,
+    /// Where `progress` is, to find where it was clicked.
+    progress_rect: RECT,
+    rate: HWND,
+    reload_settings: HWND,
+    show_controls: HWND,
+    /// When the user last switched to this window.
+    activated: Option<Instant>,
+}
+
+impl ReaderWindow {
+    pub fn new() -> Box<ReaderWindow> {
+        println!("new for ReaderWindow");
+
+        unsafe {
+            let mut out = Box::new(ReaderWindow {
+                window: HWND(0),
+                controls: OnScreenControlWindow::new(),
+                edit: HWND(0),
+                queue: HWND(0),
+                queue_shown: false,
+                progress: HWND(0),
+                progress_text: HWND(0),
+                progress_rect: RECT::default(),
+                rate: HWND(0),
+                reload_settings: HWND(0),
+                show_controls: HWND(0),
+                activated: None,
+            });
+
+            let window_class_name = w!("SAPI_event_window_class_name");
+            wm::RegisterClassW(&wm::WNDCLASSW {
+                style: wm::WNDCLASS_STYLES(0),
+                lpfnWndProc: Some(window_proc_generic::<ReaderWindow>),
+                cbClsExtra: 0,
+                cbWndExtra: 0,
+                hInstance: HINSTANCE(0),
+                hIcon: wm::LoadIconW(
+                    LibraryLoader::GetModuleHandleW(PCWSTR::null()).unwrap(),
+                    PCWSTR::from_raw(1 as *const u16),
+                )
+                .expect("failed to load icon"),
+                hCursor: wm::LoadCursorW(HINSTANCE(0), wm::IDI_APPLICATION)
+                    .expect("failed to load icon"),
+                hbrBackground: Gdi::HBRUSH(16),
+                lpszMenuName: PCWSTR::null(),
+                lpszClassName: window_class_name,
+            });
+            out.window = wm::CreateWindowExW(
+                wm::WINDOW_EX_STYLE(0),
+                window_class_name,
+                PCWSTR(&mut 0u16),
+                wm::WS_OVERLAPPEDWINDOW | wm::WS_CLIPSIBLINGS | wm::WS_CLIPCHILDREN,
+                0,
+                0,
+                0,
+                0,
+                wm::GetDesktopWindow(),
+                wm::HMENU(0),
+                HINSTANCE(0),
+                Some(&mut *out as *mut _ as _),
+            );
+
+            out.edit = create_edit_window(
+                out.window,
+                wm::WS_VSCROLL
+                    | wm::WINDOW_STYLE(wm::ES_MULTILINE as u32 | wm::ES_AUTOVSCROLL as u32),
+            );
+            out.queue = create_list_window(out.window, wm::WINDOW_STYLE(wm::LBS_NOSEL as u32));
+            out.progress = create_progress_window(out.window, PROGRESS_RANGE);
+            out.progress_text = create_static_window(out.window, None);
+            out.rate = create_static_window(out.window, None);
+            out.reload_settings = create_button_window(out.window, w!("Show Settings"));
+            out.show_controls = create_button_window(out.window, w!("Show Controls"));
+            move_window(
+                out.window,
+                &RECT {
+                    left: 0,
+                    top: 0,
+                    right: 400,
+                    bottom: 400,
+                },
+            );
+            out
+        }
+    }
+
+    fn layout(&mut self) {
+        let rect = get_client_rect(self.window);
+        let (up, down) = rect.inset(3).split_rows(25);
+        let (down, bar) = down.split_rows(down.bottom - 25);
+        let (bar, bar_text) = bar.split_columns(bar.right - 220);
+        self.progress_rect = bar.inset(3);
+        move_window(self.progress, &self.progress_rect);
+        move_window(self.progress_text, &bar_text.inset(3));
+        if self.queue_shown {
+            let queue_height = (down.bottom / 3).min(100);
+            let (text, queue) = down.split_rows(down.bottom - queue_height);
+            move_window(self.edit, &text.inset(3));
+            move_window(self.queue, &queue.inset(3));
+        } else {
+            move_window(self.edit, &down.inset(3));
+        }
+        let (left, right) = up.split_columns(240);
+        let (left_button, right_button) = left.split_columns(120);
+        move_window(self.reload_settings, &left_button.inset(3));
+        move_window(self.show_controls, &right_button.inset(3));
+        unsafe {
+            Gdi::InvalidateRect(self.rate, None, true);
+        }
+        move_window(self.rate, &right.inset(3));
+    }
+}
+
+impl DocumentView for ReaderWindow {
+    fn id(&self) -> usize {
+        self.window.0 as usize
+    }
+
+    fn is_visible(&self) -> bool {
+        is_window_visible(self.window)
+    }
+
+    fn set_visible(&mut self, visible: bool) {
+        show_window(self.window, if visible { wm::SW_SHOW } else { wm::SW_HIDE });
+    }
+
+    fn placement(&self) -> Placement {
+        let rect = get_normal_position(self.window);
+        Placement {
+            left: rect.left,
+            top: rect.top,
+            right: rect.right,
+            bottom: rect.bottom,
+            visible: self.is_visible(),
+        }
+    }
+
+    fn set_placement(&mut self, placement: &Placement) {
+        let rect = RECT {
+            left: placement.left,
+            top: placement.top,
+            right: placement.right,
+            bottom: placement.bottom,
+        };
+        let cmd = if placement.visible {
+            wm::SW_SHOWNORMAL
+        } else {
+            wm::SW_HIDE
+        };
+        set_normal_position(self.window, &rect, cmd);
+    }
+
+    fn last_activated(&self) -> Option<Instant> {
+        self.activated
+    }
+
+    fn set_text(&mut self, text: &WideString) {
+        set_window_text(self.edit, text);
+    }
+
+    fn set_rate(&mut self, rate: i32) {
+        set_window_text(self.rate, &format!("reading at rate: {}", rate).into());
+    }
+
+    /// Also the title of the console.
+    fn set_title(&mut self, title: &WideString) {
+        set_console_title(title);
+        set_window_text(self.window, title);
+    }
+
+    fn select(&mut self, range: &Range<usize>) {
+        set_edit_selection(self.edit, range);
+        set_edit_scroll_caret(self.edit);
+    }
+
+    fn set_progress(&mut self, percent: f64, text: &str) {
+        set_progress_position(
+            self.progress,
+            (percent * PROGRESS_RANGE as f64 / 100.0) as i32,
+        );
+        set_window_text(self.progress_text, &text.into());
+    }
+
+    /// Hides the list if there is nothing.
+    fn set_queue(&mut self, items: &[String]) {
+        set_list_items(self.queue, items);
+        if self.queue_shown == items.is_empty() {
+            self.queue_shown = !items.is_empty();
+            let cmd = if self.queue_shown {
+                wm::SW_SHOW
+            } else {
+                wm::SW_HIDE
+            };
+            show_window(self.queue, cmd);
+            self.layout();
+        }
+    }
+}
+
+impl Windowed for ReaderWindow {
+    fn window_proc(&mut self, msg: u32, w_param: WPARAM, l_param: LPARAM) -> Option<LRESULT> {
+        match msg {
+            wm::WM_QUERYENDSESSION | wm::WM_ENDSESSION => close(),
+            wm::WM_CLOSE => {
+                // the main loop decides if this is the last document, and so time to quit
+                unsafe {
+                    wm::PostThreadMessageW(
+                        GetCurrentThreadId(),
+                        WM_APP_CLOSE_DOCUMENT,
+                        WPARAM(self.id()),
+                        LPARAM(0),
+                    )
+                };
+                return Some(LRESULT(0));
+            }
+            wm::WM_ACTIVATE => {
+                if (w_param.0 & 0xffff) as u32 != wm::WA_INACTIVE {
+                    self.activated = Some(Instant::now());
+                }
+            }
+            wm::WM_SIZE => {
+                let rect = get_client_rect(self.window);
+                if (w_param.0 <= 2) && rect.right > 0 && rect.bottom > 0 {
+                    self.layout();
+                    return Some(LRESULT(0));
+                }
+            }
+            wm::WM_PARENTNOTIFY if (w_param.0 & 0xffff) as u32 == wm::WM_LBUTTONDOWN => {
+                // the progress bar does not say where it was clicked, but this does
+                let x = (l_param.0 & 0xffff) as i16 as i32;
+                let y = ((l_param.0 >> 16) & 0xffff) as i16 as i32;
+                let bar = self.progress_rect;
+                if (bar.left..bar.left + bar.right).contains(&x)
+                    && (bar.top..bar.top + bar.bottom).contains(&y)
+                {
+                    let at = (x - bar.left) * PROGRESS_RANGE / bar.right;
+                    unsafe {
+                        wm::PostThreadMessageW(
+                            GetCurrentThreadId(),
+                            WM_APP_SEEK_DOCUMENT,
+                            WPARAM(self.id()),
+                            LPARAM(at as isize),
+                        )
+                    };
+                    return Some(LRESULT(0));
+                }
+            }
+            wm::WM_GETMINMAXINFO => {
+                let data = unsafe { &mut *(l_param.0 as *mut u32 as *mut wm::MINMAXINFO) };
+                data.ptMinTrackSize.x = 300;
+                data.ptMinTrackSize.y = 140;
+                return Some(LRESULT(0));
+            }
+            wm::WM_COMMAND => {
+                use crate::press_hotkey;
+                use crate::Action;
+                if ((w_param.0 >> 16) & 0xffff) as u32 == wm::BN_CLICKED {
+                    if self.reload_settings.0 == l_param.0 {
+                        press_hotkey(Action::ShowSettings);
+                        return Some(LRESULT(0));
+                    } else if self.show_controls.0 == l_param.0 {
+                        self.controls.toggle_controls_visible();
+                        return Some(LRESULT(0));
+                    }
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+impl Drop for ReaderWindow {
+    fn drop(&mut self) {
+        destroy_window(self.window);
+        println!("drop for ReaderWindow");
+    }
+}
@@ -0,0 +1,575 @@
+//! Turn the Rich Text that word processors put on the clipboard into text to read.
+
+use std::collections::HashMap;
+
+use encoding_rs::{Decoder, Encoding};
+
+/// Groups that are not part of the text.
+const SKIPPED: &[&str] = &[
+    "author",
+    "colortbl",
+    "comment",
+    "datastore",
+    "fldinst",
+    "footer",
+    "footerf",
+    "footerl",
+    "footerr",
+    "header",
+    "headerf",
+    "headerl",
+    "headerr",
+    "info",
+    "listoverridetable",
+    "listtable",
+    "nonshppict",
+    "object",
+    "pict",
+    "revtbl",
+    "rsidtbl",
+    "stylesheet",
+    "themedata",
+    "xmlnstbl",
+];
+
+/// The code page the Symbol font is in.
+const SYMBOL: u32 = 42;
+
+/// What is read from Rich Text.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct RtfText {
+    /// A line for each paragraph, then the footnotes.
+    pub text: String,
+    /// How many list items had a marker.
+    pub list_items: usize,
+    pub footnotes: usize,
+}
+
+/// If the plain text on the clipboard lost something that the Rich Text still has.
+pub fn plain_text_is_degraded(plain: &str, rtf: &RtfText) -> bool {
+    plain.trim().is_empty()
+        || rtf.list_items > 0
+        || rtf.footnotes > 0
+        || plain.contains(char::REPLACEMENT_CHARACTER)
+        || plain.matches('?').count() > rtf.text.matches('?').count()
+}
+
+/// The code page of the `\fcharset` of a font.
+fn charset_code_page(charset: i32, default: u32) -> u32 {
+    match charset {
+        0 => 1252,
+        2 => SYMBOL,
+        77 => 10000,
+        128 => 932,
+        129 => 949,
+        130 => 1361,
+        134 => 936,
+        136 => 950,
+        161 => 1253,
+        162 => 1254,
+        163 => 1258,
+        177 => 1255,
+        178 => 1256,
+        186 => 1257,
+        204 => 1251,
+        222 => 874,
+        238 => 1250,
+        255 => 437,
+        _ => default,
+    }
+}
+
+/// The decoder for the code page `cp`, `None` for the Symbol font and code pages we can not read.
+fn encoding(cp: u32) -> Option<&'static Encoding> {
+    Some(match cp {
+        866 => encoding_rs::IBM866,
+        874 => encoding_rs::WINDOWS_874,
+        932 => encoding_rs::SHIFT_JIS,
+        936 => encoding_rs::GBK,
+        949 => encoding_rs::EUC_KR,
+        950 => encoding_rs::BIG5,
+        1250 => encoding_rs::WINDOWS_1250,
+        1251 => encoding_rs::WINDOWS_1251,
+        1252 | 28591 => encoding_rs::WINDOWS_1252,
+        1253 => encoding_rs::WINDOWS_1253,
+        1254 => encoding_rs::WINDOWS_1254,
+        1255 => encoding_rs::WINDOWS_1255,
+        1256 => encoding_rs::WINDOWS_1256,
+        1257 => encoding_rs::WINDOWS_1257,
+        1258 => encoding_rs::WINDOWS_1258,
+        10000 => encoding_rs::MACINTOSH,
+        10007 => encoding_rs::X_MAC_CYRILLIC,
+        20866 => encoding_rs::KOI8_R,
+        21866 => encoding_rs::KOI8_U,
+        28592 => encoding_rs::ISO_8859_2,
+        28593 => encoding_rs::ISO_8859_3,
+        28594 => encoding_rs::ISO_8859_4,
+        28595 => encoding_rs::ISO_8859_5,
+        28596 => encoding_rs::ISO_8859_6,
+        28597 => encoding_rs::ISO_8859_7,
+        28598 => encoding_rs::ISO_8859_8,
+        28603 => encoding_rs::ISO_8859_13,
+        28605 => encoding_rs::ISO_8859_15,
+        65001 => encoding_rs::UTF_8,
+        _ => return None,
+    })
+}
+
+/// The character `b` is when there is no decoder for the code page `cp`.
+fn decode_single_byte(cp: u32, b: u8) -> char {
+    match (cp, b) {
+        (_, 0..=0x7f) => b as char,
+        (SYMBOL, 0xb7) => '\u{2022}',
+        // where Windows puts the rest of a symbol font
+        (SYMBOL, _) => char::from_u32(0xf000 + u32::from(b)).unwrap_or(char::REPLACEMENT_CHARACTER),
+        _ => char::REPLACEMENT_CHARACTER,
+    }
+}
+
+/// A list marker is a number or letter with punctuation, anything else is a bullet.
+fn say_marker(marker: &str) -> String {
+    let marker = marker.trim();
+    let numbered = marker.chars().any(|c| c.is_ascii_digit())
+        || (marker.ends_with(['.', ')'])
+            && marker[..marker.len() - 1].chars().all(char::is_alphabetic)
+            && marker.len() > 1);
+    if numbered {
+        marker.to_string()
+    } else {
+        "Bullet:".to_string()
+    }
+}
+
+#[derive(Clone)]
+struct Group {
+    /// A destination that is not part of the text.
+    skip: bool,
+    font_table: bool,
+    code_page: u32,
+    /// How many fallback characters follow each `\u`.
+    uc: usize,
+    hidden: bool,
+    /// In the marker of a list item.
+    marker: bool,
+    footnote: bool,
+}
+
+struct Parser {
+    group: Group,
+    outer: Vec<Group>,
+    code_page: u32,
+    /// The code page of each font.
+    fonts: HashMap<i32, u32>,
+    /// The font being defined in the font table.
+    font: i32,
+    default_font: i32,
+    /// Fallback characters still to skip after a `\u`.
+    fallback: usize,
+    /// Holds the first bytes of a character that takes more than one, with the code page it is in.
+    decoder: Option<(u32, Decoder)>,
+    lines: Vec<String>,
+    line: String,
+    marker: String,
+    footnote: String,
+    footnotes: Vec<String>,
+    list_items: usize,
+}
+
+impl Parser {
+    fn text(&mut self, c: char) {
+        if self.fallback > 0 {
+            self.fallback -= 1;
+            return;
+        }
+        let g = &self.group;
+        if g.skip || g.font_table || g.hidden {
+            return;
+        }
+        if g.marker {
+            self.marker.push(c);
+        } else if g.footnote {
+            self.footnote.push(c);
+        } else {
+            self.line.push(c);
+        }
+    }
+
+    fn text_str(&mut self, s: &str) {
+        let fallback = std::mem::take(&mut self.fallback);
+        s.chars().for_each(|c| self.text(c));
+        self.fallback = fallback;
+    }
+
+    /// A byte of text in the code page of the group.
+    fn byte(&mut self, b: u8) {
+        if self.fallback > 0 {
+            self.fallback -= 1;
+            return;
+        }
+        if self.group.skip || self.group.font_table {
+            return;
+        }
+        let cp = self.group.code_page;
+        if self.decoder.as_ref().is_some_and(|d| d.0 != cp) {
+            self.flush();
+        }
+        if b < 0x80 && self.decoder.is_none() {
+            return self.text(b as char);
+        }
+        let Some(encoding) = encoding(cp) else {
+            return self.text(decode_single_byte(cp, b));
+        };
+        let decoder = &mut self
+            .decoder
+            .get_or_insert_with(|| (cp, encoding.new_decoder_without_bom_handling()))
+            .1;
+        let decoded = decode(decoder, &[b], false);
+        self.text_decoded(&decoded);
+    }
+
+    /// Read what is left of a character that was not finished, as U+FFFD.
+    fn flush(&mut self) {
+        if let Some((_, mut decoder)) = self.decoder.take() {
+            let decoded = decode(&mut decoder, &[], true);
+            self.text_decoded(&decoded);
+        }
+    }
+
+    fn text_decoded(&mut self, s: &str) {
+        // bytes that are not in the code page come out as C1 controls
+        let s: String = s
+            .chars()
+            .map(|c| match c {
+                '\u{80}'..='\u{9f}' => char::REPLACEMENT_CHARACTER,
+                c => c,
+            })
+            .collect();
+        self.text_str(&s);
+    }
+
+    fn paragraph(&mut self) {
+        if self.group.skip || self.group.marker {
+            return;
+        }
+        if self.group.footnote {
+            self.footnote.push(' ');
+            return;
+        }
+        let line = collapse(&self.line);
+        if !line.is_empty() {
+            self.lines.push(line);
+        }
+        self.line.clear();
+    }
+
+    /// The code page of the document, and of text before any font is chosen.
+    fn set_code_page(&mut self, cp: u32) {
+        self.code_page = cp;
+        self.group.code_page = cp;
+    }
+
+    fn font_code_page(&self, font: i32) -> u32 {
+        self.fonts.get(&font).copied().unwrap_or(self.code_page)
+    }
+
+    fn control_word(&mut self, word: &str, param: Option<i32>) {
+        match word {
+            "par" | "sect" | "page" | "line" | "row" => self.paragraph(),
+            "tab" => self.text(' '),
+            "cell" => self.text_str("; "),
+            "emdash" => self.text('\u{2014}'),
+            "endash" => self.text('\u{2013}'),
+            "bullet" => self.text('\u{2022}'),
+            "lquote" => self.text('\u{2018}'),
+            "rquote" => self.text('\u{2019}'),
+            "ldblquote" => self.text('\u{201c}'),
+            "rdblquote" => self.text('\u{201d}'),
+            "emspace" | "enspace" | "qmspace" => self.text(' '),
+            "u" => {
+                if let Some(n) = param {
+                    // negative for the top half of the 16 bits
+                    let c = char::from_u32(n as u16 as u32).unwrap_or(char::REPLACEMENT_CHARACTER);
+                    self.fallback = 0;
+                    self.text(c);
+                    self.fallback = self.group.uc;
+                }
+            }
+            "uc" => self.group.uc = param.unwrap_or(1).max(0) as usize,
+            "ansi" => self.set_code_page(1252),
+            "mac" => self.set_code_page(10000),
+            "pc" => self.set_code_page(437),
+            "pca" => self.set_code_page(850),
+            "ansicpg" => {
+                if let Some(cp) = param.filter(|&cp| cp > 0) {
+                    self.set_code_page(cp as u32);
+                }
+            }
+            "deff" => self.default_font = param.unwrap_or(0),
+            "fonttbl" => self.group.font_table = true,
+            "f" if self.group.font_table => self.font = param.unwrap_or(0),
+            "f" => self.group.code_page = self.font_code_page(param.unwrap_or(0)),
+            "fcharset" if self.group.font_table => {
+                let cp = charset_code_page(param.unwrap_or(0), self.code_page);
+                self.fonts.insert(self.font, cp);
+            }
+            "cpg" if self.group.font_table => {
+                if let Some(cp) = param.filter(|&cp| cp > 0) {
+                    self.fonts.insert(self.font, cp as u32);
+                }
+            }
+            "plain" => {
+                self.group.code_page = self.font_code_page(self.default_font);
+                self.group.hidden = false;
+            }
+            "v" => self.group.hidden = param != Some(0),
+            "listtext" | "pntext" => self.group.marker = true,
+            "footnote" => self.group.footnote = true,
+            "chftn" if !self.group.footnote && !self.group.marker => {
+                let n = self.footnotes.len() + 1;
+                self.text_str(&format!(" (footnote {}) ", n));
+            }
+            _ if SKIPPED.contains(&word) => self.group.skip = true,
+            _ => {}
+        }
+    }
+
+    fn close_group(&mut self) {
+        let inner = std::mem::replace(&mut self.group, self.outer.pop().expect("unbalanced"));
+        self.fallback = 0;
+        if inner.marker && !self.group.marker {
+            let marker = std::mem::take(&mut self.marker);
+            if !marker.trim().is_empty() {
+                self.list_items += 1;
+                let said = say_marker(&marker);
+                self.text_str(&format!("{} ", said));
+            }
+        }
+        if inner.footnote && !self.group.footnote {
+            let footnote = collapse(&std::mem::take(&mut self.footnote));
+            let n = self.footnotes.len() + 1;
+            self.footnotes.push(format!("Footnote {}: {}", n, footnote));
+        }
+    }
+}
+
+fn decode(decoder: &mut Decoder, bytes: &[u8], last: bool) -> String {
+    let len = decoder.max_utf8_buffer_length(bytes.len()).unwrap_or(16);
+    let mut out = String::with_capacity(len);
+    let _ = decoder.decode_to_string(bytes, &mut out, last);
+    out
+}
+
+fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The text of `rtf`, a line for each paragraph, with the markers of list items
+/// and the footnotes at the end.
+pub fn rtf_to_speech(rtf: &[u8]) -> Result<RtfText, String> {
+    let start = rtf
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(rtf.len());
+    if !rtf[start..].starts_with(b"{\\rtf") {
+        return Err("not rich text".to_string());
+    }
+    let mut p = Parser {
+        group: Group {
+            skip: false,
+            font_table: false,
+            code_page: 1252,
+            uc: 1,
+            hidden: false,
+            marker: false,
+            footnote: false,
+        },
+        outer: Vec::new(),
+        code_page: 1252,
+        fonts: HashMap::new(),
+        font: 0,
+        default_font: 0,
+        fallback: 0,
+        decoder: None,
+        lines: Vec::new(),
+        line: String::new(),
+        marker: String::new(),
+        footnote: String::new(),
+        footnotes: Vec::new(),
+        list_items: 0,
+    };
+    // outside of everything, so the last "}" has somewhere to go back to
+    let mut depth = 0;
+    let mut i = start;
+    while i < rtf.len() {
+        let b = rtf[i];
+        i += 1;
+        // the bytes of a character come one after another, as text or as `\'`
+        let same_text = match b {
+            b'{' | b'}' | b'\t' => false,
+            b'\\' => rtf.get(i) == Some(&b'\''),
+            _ => true,
+        };
+        if !same_text {
+            p.flush();
+        }
+        match b {
+            b'{' => {
+                depth += 1;
+                p.outer.push(p.group.clone());
+            }
+            b'}' => {
+                if depth == 0 {
+                    return Err("unbalanced \"}\"".to_string());
+                }
+                depth -= 1;
+                p.close_group();
+                if depth == 0 {
+                    break;
+                }
+            }
+            b'\r' | b'\n' | 0 => {}
+            b'\\' => {
+                let next = match rtf.get(i) {
+                    Some(&n) => n,
+                    None => break,
+                };
+                if next.is_ascii_alphabetic() {
+                    let name_end = rtf[i..]
+                        .iter()
+                        .position(|b| !b.is_ascii_alphabetic())
+                        .map_or(rtf.len(), |e| i + e);
+                    let word = std::str::from_utf8(&rtf[i..name_end]).unwrap_or("");
+                    let mut param_end = name_end;
+                    if rtf.get(param_end) == Some(&b'-') {
+                        param_end += 1;
+                    }
+                    while rtf.get(param_end).is_some_and(u8::is_ascii_digit) {
+                        param_end += 1;
+                    }
+                    let param = std::str::from_utf8(&rtf[name_end..param_end])
+                        .ok()
+                        .and_then(|s| s.parse::<i64>().ok())
+                        .map(|n| n.clamp(i32::MIN.into(), i32::MAX.into()) as i32);
+                    i = param_end;
+                    // one space ends a control word and is not text
+                    if rtf.get(i) == Some(&b' ') {
+                        i += 1;
+                    }
+                    if word == "bin" {
+                        i += param.unwrap_or(0).max(0) as usize;
+                        continue;
+                    }
+                    if p.fallback > 0 && word != "u" {
+                        p.fallback -= 1;
+                        continue;
+                    }
+                    p.control_word(word, param);
+                    continue;
+                }
+                i += 1;
+                match next {
+                    b'\'' => {
+                        let hex = rtf.get(i..i + 2).and_then(|h| std::str::from_utf8(h).ok());
+                        i += 2;
+                        if let Some(byte) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                            p.byte(byte);
+                        }
+                    }
+                    b'*' => p.group.skip = true,
+                    b'~' => p.text('\u{a0}'),
+                    b'_' => p.text('-'),
+                    b'\\' | b'{' | b'}' => p.text(next as char),
+                    b'\r' | b'\n' => p.paragraph(),
+                    _ => {}
+                }
+            }
+            b'\t' => p.text(' '),
+            b => p.byte(b),
+        }
+    }
+    p.flush();
+    p.paragraph();
+    let mut lines = p.lines;
+    if !p.footnotes.is_empty() {
+        lines.push("Footnotes.".to_string());
+        lines.extend(p.footnotes.iter().cloned());
+    }
+    Ok(RtfText {
+        text: lines.join("\n"),
+        list_items: p.list_items,
+        footnotes: p.footnotes.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORD: &str = r#"{\rtf1\ansi\ansicpg1252\deff0{\fonttbl{\f0\froman\fcharset0 Times New Roman;}{\f1\fnil\fcharset2 Symbol;}{\f2\fswiss\fcharset204 Arial CYR;}}
+{\colortbl;\red0\green0\blue0;}{\stylesheet{\s0 Normal;}}{\info{\author Someone}}
+{\*\generator Riched20 10.0.19041}\viewkind4\uc1
+\pard\f0\fs24 Caf\'e9 \'93quoted\'94 text\par
+{\listtext\pard\plain\f1\'b7\tab}First item\par
+{\listtext\pard\plain\f0 2.\tab}Second\line item\par
+\f2\'cf\'f0\'e8\'e2\'e5\'f2 and \u8364?5\par
+A claim{\super\chftn}{\footnote\pard{\super\chftn} See the source.}\~done.\par
+}"#;
+
+    #[test]
+    fn word_pad() {
+        let out = rtf_to_speech(WORD.as_bytes()).unwrap();
+        assert_eq!(
+            out.text,
+            [
+                "Caf\u{e9} \u{201c}quoted\u{201d} text",
+                "Bullet: First item",
+                "2. Second",
+                "item",
+                "\u{41f}\u{440}\u{438}\u{432}\u{435}\u{442} and \u{20ac}5",
+                "A claim (footnote 1) done.",
+                "Footnotes.",
+                "Footnote 1: See the source.",
+            ]
+            .join("\n")
+        );
+        assert_eq!(out.list_items, 2);
+        assert_eq!(out.footnotes, 1);
+    }
+
+    #[test]
+    fn code_pages() {
+        let text = |cp: u32, bytes: &str| {
+            let rtf = format!("{{\\rtf1\\ansi\\ansicpg{} {}}}", cp, bytes);
+            rtf_to_speech(rtf.as_bytes()).unwrap().text
+        };
+        assert_eq!(text(1252, r"\'80"), "\u{20ac}");
+        assert_eq!(text(1252, r"\'e9"), "\u{e9}");
+        assert_eq!(text(1252, r"\'81"), "\u{fffd}");
+        assert_eq!(text(1251, r"\'ff"), "\u{44f}");
+        assert_eq!(text(1250, r"\'b9"), "\u{105}");
+        assert_eq!(text(1254, r"\'fd"), "\u{131}");
+        assert_eq!(text(1253, r"\'e1"), "\u{3b1}");
+        // Shift-JIS, the second byte can be written as text
+        assert_eq!(text(932, r"\'82\'a0\'82\'a2 a"), "\u{3042}\u{3044} a");
+        assert_eq!(text(932, r"\'83n\'83\'8d"), "\u{30cf}\u{30ed}");
+        assert_eq!(text(936, r"\'c4\'e3\'ba\'c3"), "\u{4f60}\u{597d}");
+        // a first byte on its own is not a character
+        assert_eq!(text(932, r"{\'82}a"), "\u{fffd}a");
+        // code pages we can not read do not stop the rest
+        assert_eq!(text(437, r"\'82ok"), "\u{fffd}ok");
+        assert_eq!(text(1361, r"\'88\'61"), "\u{fffd}a");
+        assert!(rtf_to_speech(b"plain text").is_err());
+    }
+
+    #[test]
+    fn degraded() {
+        let rtf = rtf_to_speech(br"{\rtf1 just text\par}").unwrap();
+        assert!(!plain_text_is_degraded("just text", &rtf));
+        assert!(plain_text_is_degraded("", &rtf));
+        let rtf = rtf_to_speech("{\\rtf1 na\\u239?ve}".as_bytes()).unwrap();
+        assert_eq!(rtf.text, "na\u{ef}ve");
+        assert!(plain_text_is_degraded("na?ve", &rtf));
+        let rtf = rtf_to_speech(WORD.as_bytes()).unwrap();
+        assert!(plain_text_is_degraded("First item", &rtf));
+    }
+}
@@ -1,32 +1,32 @@
-use average::{Estimate, Variance};
-use chrono;
-use std::mem::size_of;
-use std::mem::{zeroed, MaybeUninit};
+use std::mem::MaybeUninit;
 
-use windows::core::PCWSTR;
+use windows::core::{IUnknown, GUID, PCWSTR};
 use windows::w;
 use windows::Win32::{
-    Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, RECT, WPARAM},
-    Graphics::Gdi,
+    Foundation::{HWND, LPARAM, WPARAM},
+    Media::Audio,
     Media::Speech,
     System::Com as syscom,
-    System::LibraryLoader,
     System::WindowsProgramming::INFINITE,
-    UI::Shell,
     UI::WindowsAndMessaging as wm,
 };
 
 use std::cmp::{max, min};
+use std::io;
 use std::mem;
 use std::ops::Range;
+use std::path::Path;
 use std::ptr::null_mut;
-use std::time::Instant;
 
-use crate::on_screen_control::*;
-use crate::window::*;
+use crate::speech_engine::*;
+use crate::wav::WordTiming;
+use rust_reader::wide_string::*;
 
 pub const WM_SAPI_EVENT: u32 = wm::WM_APP + 15;
-pub const WM_APP_NOTIFICATION_ICON: u32 = wm::WM_APP + 16;
+
+// SPDFID_WaveFormatEx from sapi.h, it is missing from the windows crate.
+const SPDFID_WAVE_FORMAT_EX: GUID = GUID::from_u128(0xc31adbae_527f_4ff5_a230_f62bb61ff70c);
+const WAV_SAMPLE_RATE: u32 = 22050;
 
 pub struct Com {}
 
@@ -50,206 +50,46 @@ impl Drop for Com {
 pub struct SpVoice {
     // https://msdn.microsoft.com/en-us/library/ms723602.aspx
     voice: Speech::ISpVoice,
-    window: HWND,
-    controls: Box<OnScreenControlWindow>,
-    edit: HWND,
-    rate: HWND,
-    reload_settings: HWND,
-    show_controls: HWND,
-    nicon: Shell::NOTIFYICONDATAW,
     last_read: WideString,
-    last_update: Option<(Instant, Range<usize>)>,
-    us_per_utf16: [Variance; 21],
+    /// Where in `last_read` the voice was last told to start, SAPI counts from there.
+    offset: usize,
 }
 
 impl SpVoice {
-    pub fn new<'c>(_con: &'c Com) -> Box<SpVoice> {
+    /// `window` will be sent a `WM_SAPI_EVENT` at each word boundary.
+    pub fn new<'c>(_con: &'c Com, window: HWND) -> Box<SpVoice> {
         println!("new for SpVoice");
 
-        unsafe {
-            let mut out = Box::new(SpVoice {
-                voice: syscom::CoCreateInstance(&Speech::SpVoice, None, syscom::CLSCTX_ALL)
-                    .expect("failed for SpVoice at CoCreateInstance"),
-                window: HWND(0),
-                controls: OnScreenControlWindow::new(),
-                edit: HWND(0),
-                rate: HWND(0),
-                reload_settings: HWND(0),
-                show_controls: HWND(0),
-                nicon: zeroed(),
-                last_read: WideString::new(),
-                last_update: None,
-                us_per_utf16: Default::default(),
-            });
-
-            let window_class_name = w!("SAPI_event_window_class_name");
-            wm::RegisterClassW(&wm::WNDCLASSW {
-                style: wm::WNDCLASS_STYLES(0),
-                lpfnWndProc: Some(window_proc_generic::<SpVoice>),
-                cbClsExtra: 0,
-                cbWndExtra: 0,
-                hInstance: HINSTANCE(0),
-                hIcon: wm::LoadIconW(
-                    LibraryLoader::GetModuleHandleW(PCWSTR::null()).unwrap(),
-                    PCWSTR::from_raw(1 as *const u16),
-                )
-                .expect("failed to load icon"),
-                hCursor: wm::LoadCursorW(HINSTANCE(0), wm::IDI_APPLICATION)
-                    .expect("failed to load icon"),
-                hbrBackground: Gdi::HBRUSH(16),
-                lpszMenuName: PCWSTR::null(),
-                lpszClassName: window_class_name,
-            });
-            out.window = wm::CreateWindowExW(
-                wm::WINDOW_EX_STYLE(0),
-                window_class_name,
-                PCWSTR(&mut 0u16),
-                wm::WS_OVERLAPPEDWINDOW | wm::WS_CLIPSIBLINGS | wm::WS_CLIPCHILDREN,
-                0,
-                0,
-                0,
-                0,
-                wm::GetDesktopWindow(),
-                wm::HMENU(0),
-                HINSTANCE(0),
-                Some(&mut *out as *mut _ as _),
-            );
-
-            out.nicon.cbSize = size_of::<Shell::NOTIFYICONDATAW>() as u32;
-            out.nicon.hWnd = out.window;
-            out.nicon.uCallbackMessage = WM_APP_NOTIFICATION_ICON;
-            out.nicon.uID = 1 as u32;
-            out.nicon.uFlags |= Shell::NIF_ICON;
-            out.nicon.hIcon = wm::LoadIconW(
-                LibraryLoader::GetModuleHandleW(PCWSTR::null()).unwrap(),
-                PCWSTR::from_raw(1 as *const u16),
-            )
-            .expect("failed to load icon");
-            out.nicon.uFlags |= Shell::NIF_MESSAGE;
-            out.nicon.Anonymous.uVersion = Shell::NOTIFYICON_VERSION_4;
-            let err = Shell::Shell_NotifyIconW(Shell::NIM_ADD, &mut out.nicon);
-            if err == false {
-                panic!("failed for Shell_NotifyIconW NIM_ADD");
-            }
-
-            let err = Shell::Shell_NotifyIconW(Shell::NIM_SETVERSION, &mut out.nicon);
-            if err == false {
-                panic!("failed for Shell_NotifyIconW ");
-            }
-
-            out.edit = create_edit_window(
-                out.window,
-                wm::WS_VSCROLL
-                    | wm::WINDOW_STYLE(wm::ES_MULTILINE as u32 | wm::ES_AUTOVSCROLL as u32),
-            );
-            out.rate = create_static_window(out.window, None);
-            out.reload_settings = create_button_window(out.window, w!("Show Settings"));
-            out.show_controls = create_button_window(out.window, w!("Show Controls"));
-            move_window(
-                out.window,
-                &RECT {
-                    left: 0,
-                    top: 0,
-                    right: 400,
-                    bottom: 400,
-                },
-            );
-            out.set_notify_window_message();
-            out.set_volume(100);
-            out.set_alert_boundary(Speech::SPEI_PHONEME);
-            out.set_interest(
-                &[
-                    Speech::SPEI_WORD_BOUNDARY,
-                    Speech::SPEI_START_INPUT_STREAM,
-                    Speech::SPEI_END_INPUT_STREAM,
-                ],
-                &[],
-            );
-            out
-        }
-    }
-
-    #[allow(dead_code)]
-    pub fn get_window_handle(&mut self) -> HWND {
-        self.window
-    }
-
-    pub fn set_time_estimater(&mut self, t: [Variance; 21]) {
-        self.us_per_utf16 = t;
-    }
-
-    pub fn get_time_estimater(&self) -> [Variance; 21] {
-        self.us_per_utf16.clone()
-    }
-
-    pub fn toggle_window_visible(&self) -> bool {
-        toggle_window_visible(self.window)
+        let mut out = Box::new(SpVoice {
+            voice: unsafe { syscom::CoCreateInstance(&Speech::SpVoice, None, syscom::CLSCTX_ALL) }
+                .expect("failed for SpVoice at CoCreateInstance"),
+            last_read: WideString::new(),
+            offset: 0,
+        });
+        out.set_notify_window_message(window);
+        out.set_volume(100);
+        out.set_alert_boundary(Speech::SPEI_PHONEME);
+        out.set_interest(
+            &[
+                Speech::SPEI_WORD_BOUNDARY,
+                Speech::SPEI_START_INPUT_STREAM,
+                Speech::SPEI_END_INPUT_STREAM,
+            ],
+            &[],
+        );
+        out
     }
 
     #[allow(dead_code)]
     pub fn get_status_word(&mut self) -> String {
         let status = self.get_status();
-        self.last_read.get_slice(status.word_range())
+        self.last_read.get_slice(status.word_range)
     }
 
     #[allow(dead_code)]
     pub fn get_status_sent(&mut self) -> String {
         let status = self.get_status();
-        self.last_read.get_slice(status.sent_range())
-    }
-
-    pub fn speak<T: Into<WideString>>(&mut self, string: T) {
-        self.last_read = string.into();
-        set_window_text(self.edit, &self.last_read);
-        unsafe {
-            self.voice.Speak(
-                PCWSTR::from_raw(self.last_read.as_ptr()),
-                (Speech::SVSFlagsAsync.0 | Speech::SVSFPurgeBeforeSpeak.0 | Speech::SVSFIsNotXML.0)
-                    .try_into()
-                    .unwrap(),
-                None,
-            )
-        }
-        .unwrap();
-        self.last_update = None;
-    }
-
-    pub fn wait(&mut self) {
-        unsafe { self.voice.WaitUntilDone(INFINITE) }.unwrap();
-    }
-
-    pub fn speak_wait<T: Into<WideString>>(&mut self, string: T) {
-        self.speak(string);
-        self.wait();
-    }
-
-    pub fn pause(&mut self) {
-        unsafe { self.voice.Pause() }.unwrap();
-        self.last_update = None;
-    }
-
-    pub fn resume(&mut self) {
-        unsafe { self.voice.Resume() }.unwrap();
-        self.last_update = None;
-    }
-
-    pub fn set_rate(&mut self, rate: i32) -> i32 {
-        let rate = max(min(rate, 10), -10);
-        unsafe { self.voice.SetRate(rate) }.unwrap();
-        self.last_update = None;
-        self.get_rate()
-    }
-
-    pub fn get_rate(&mut self) -> i32 {
-        let mut rate = 0;
-        unsafe { self.voice.GetRate(&mut rate) }.unwrap();
-        set_window_text(self.rate, &format!("reading at rate: {}", rate).into());
-        rate
-    }
-
-    pub fn change_rate(&mut self, delta: i32) -> i32 {
-        let rate = self.get_rate() + delta;
-        self.set_rate(rate)
+        self.last_read.get_slice(status.sent_range)
     }
 
     fn get_voice_name(token: Speech::ISpObjectToken) -> String {
@@ -296,43 +136,10 @@ impl SpVoice {
         voices
     }
 
-    pub fn available_voice_names(&mut self) -> Vec<String> {
-        self.available_voices()
-            .iter()
-            .map(|t| SpVoice::get_voice_name(t.clone()))
-            .collect::<Vec<_>>()
-    }
-
     fn set_voice(&mut self, token: Speech::ISpObjectToken) {
         unsafe { self.voice.SetVoice(&token).ok() };
     }
 
-    pub fn set_voice_by_name(&mut self, voice_name: String) -> String {
-        if let Some(t) = self
-            .available_voices()
-            .iter()
-            .find(|&t| voice_name == SpVoice::get_voice_name(t.clone()))
-        {
-            self.set_voice(t.clone());
-        }
-        // Return name of voice now in use
-        match unsafe { self.voice.GetVoice().ok() } {
-            Some(t) => SpVoice::get_voice_name(t),
-            None => "unknown".to_string(),
-        }
-    }
-
-    pub fn set_volume(&mut self, volume: u16) {
-        unsafe { self.voice.SetVolume(min(volume, 100)) }.unwrap();
-    }
-
-    #[allow(dead_code)]
-    pub fn get_volume(&mut self) -> u16 {
-        let mut volume = 0;
-        unsafe { self.voice.GetVolume(&mut volume) }.unwrap();
-        volume
-    }
-
     pub fn set_alert_boundary(&mut self, boundary: Speech::SPEVENTENUM) {
         unsafe { self.voice.SetAlertBoundary(boundary) }.unwrap();
     }
@@ -344,16 +151,16 @@ impl SpVoice {
         boundary
     }
 
-    pub fn get_status(&mut self) -> Speech::SPVOICESTATUS {
+    pub fn get_sapi_status(&mut self) -> Speech::SPVOICESTATUS {
         let mut status: Speech::SPVOICESTATUS = unsafe { mem::zeroed() };
         unsafe { self.voice.GetStatus(&mut status, null_mut()) }.unwrap();
         status
     }
 
-    fn set_notify_window_message(&mut self) {
+    fn set_notify_window_message(&mut self, window: HWND) {
         unsafe {
             self.voice
-                .SetNotifyWindowMessage(self.window, WM_SAPI_EVENT, WPARAM(0), LPARAM(0))
+                .SetNotifyWindowMessage(window, WM_SAPI_EVENT, WPARAM(0), LPARAM(0))
         }
         .unwrap();
     }
@@ -373,143 +180,178 @@ impl SpVoice {
             .fold(queued, |acc, x| acc | x);
         unsafe { self.voice.SetInterest(event, queued) }.unwrap();
     }
-}
 
-fn format_duration(d: chrono::Duration) -> String {
-    let h = d.num_hours();
-    let m = d.num_minutes() - d.num_hours() * 60;
-    let s = d.num_seconds() - d.num_minutes() * 60;
-    if d.num_hours() == 0 {
-        format!("{}:{:0>#2}", m, s)
-    } else {
-        format!("{}:{:0>#2}:{:0>#2}", h, m, s)
+    /// Take the queued word boundaries, with the time they were reached in the output.
+    fn get_word_timings(&mut self, bytes_per_sec: u64) -> Vec<WordTiming> {
+        let mut out = vec![];
+        loop {
+            let mut event: Speech::SPEVENT = unsafe { mem::zeroed() };
+            let mut fetched = 0;
+            if unsafe { self.voice.GetEvents(1, &mut event, &mut fetched) }.is_err() || fetched == 0
+            {
+                return out;
+            }
+            // the low word of _bitfield is the eEventId
+            if event._bitfield & 0xffff == Speech::SPEI_WORD_BOUNDARY.0 {
+                let start = event.lParam.0 as usize;
+                out.push(WordTiming {
+                    ms: event.ullAudioStreamOffset * 1000 / bytes_per_sec,
+                    word_range: start..start + event.wParam.0,
+                });
+            }
+        }
     }
 }
 
-#[test]
-fn test_format_duration() {
-    let duration = chrono::Duration::hours(25);
-    assert_eq!(format_duration(duration), "25:00:00");
-    let duration = chrono::Duration::hours(1) + chrono::Duration::seconds(1);
-    assert_eq!(format_duration(duration), "1:00:01");
-    let duration = chrono::Duration::hours(1) - chrono::Duration::seconds(1);
-    assert_eq!(format_duration(duration), "59:59");
-    let duration = chrono::Duration::seconds(61);
-    assert_eq!(format_duration(duration), "1:01");
-    let duration = chrono::Duration::seconds(60);
-    assert_eq!(format_duration(duration), "1:00");
-    let duration = chrono::Duration::seconds(59);
-    assert_eq!(format_duration(duration), "0:59");
-    let duration = chrono::Duration::seconds(9);
-    assert_eq!(format_duration(duration), "0:09");
-    let duration = chrono::Duration::seconds(0);
-    assert_eq!(format_duration(duration), "0:00");
-}
+impl SpeechEngine for SpVoice {
+    fn speak(&mut self, text: WideString) {
+        self.last_read = text;
+        self.speak_from(0);
+    }
 
-impl Windowed for SpVoice {
-    fn window_proc(&mut self, msg: u32, w_param: WPARAM, l_param: LPARAM) -> Option<LRESULT> {
-        match msg {
-            wm::WM_DESTROY | wm::WM_QUERYENDSESSION | wm::WM_ENDSESSION => close(),
-            WM_SAPI_EVENT => {
-                let status = self.get_status();
-                // convert rate from range (-10, 10) to (0, 20)
-                let rate_shifted = 10u32
-                    .checked_add_signed(self.get_rate())
-                    .expect("bad rate < -10") as usize;
-                let word_range = status.word_range();
-                if word_range.end == 0 {
-                    // called before start of reading.
-                    self.last_update = None;
-                    return Some(LRESULT(0));
-                }
-                if status.dwRunningState == 3 {
-                    // called before end of reading.
-                    let window_title = "100.0% 0:00 rust_reader".into();
-                    set_console_title(&window_title);
-                    set_window_text(self.window, &window_title);
-                    self.last_update = None;
-                    return Some(LRESULT(0));
-                }
-                if let Some((ref old_time, ref old_word_range)) = self.last_update {
-                    if old_word_range.start == word_range.start {
-                        return Some(LRESULT(0));
-                    }
-                    let elapsed = chrono::Duration::from_std(old_time.elapsed())
-                        .expect("bad time diffrence.")
-                        .num_microseconds()
-                        .expect("bad time diffrence.");
-                    let new_rate =
-                        (elapsed as f64) / ((word_range.start - old_word_range.start) as f64);
-                    self.us_per_utf16[rate_shifted].add(new_rate);
-                }
-                self.last_update = Some((Instant::now(), word_range.clone()));
-                let len_left = (self.last_read.len() - word_range.end) as f64;
-                let ms_left = len_left * self.us_per_utf16[rate_shifted].mean()
-                    + (len_left * self.us_per_utf16[rate_shifted].sample_variance()).sqrt();
-                let window_title = format!(
-                    "{:.1}% {} \"{}\" rust_reader",
-                    100.0 * (word_range.start as f64) / (self.last_read.len() as f64),
-                    format_duration(chrono::Duration::microseconds(ms_left as i64)),
-                    self.last_read.get_slice(word_range.clone())
-                )
-                .into();
-                set_console_title(&window_title);
-                set_window_text(self.window, &window_title);
-                set_edit_selection(self.edit, &word_range);
-                set_edit_scroll_caret(self.edit);
-                return Some(LRESULT(0));
-            }
-            wm::WM_SIZE => {
-                let rect = get_client_rect(self.window);
-                if (w_param.0 <= 2) && rect.right > 0 && rect.bottom > 0 {
-                    let (up, down) = rect.inset(3).split_rows(25);
-                    move_window(self.edit, &down.inset(3));
-                    let (left, right) = up.split_columns(240);
-                    let (left_button, right_button) = left.split_columns(120);
-                    move_window(self.reload_settings, &left_button.inset(3));
-                    move_window(self.show_controls, &right_button.inset(3));
-                    unsafe {
-                        Gdi::InvalidateRect(self.rate, None, true);
-                    }
-                    move_window(self.rate, &right.inset(3));
-                    return Some(LRESULT(0));
-                }
-            }
-            wm::WM_GETMINMAXINFO => {
-                let data = unsafe { &mut *(l_param.0 as *mut u32 as *mut wm::MINMAXINFO) };
-                data.ptMinTrackSize.x = 300;
-                data.ptMinTrackSize.y = 110;
-                return Some(LRESULT(0));
-            }
-            wm::WM_COMMAND => {
-                use crate::press_hotkey;
-                use crate::Action;
-                if ((w_param.0 >> 16) & 0xffff) as u32 == wm::BN_CLICKED {
-                    if self.reload_settings.0 == l_param.0 {
-                        press_hotkey(Action::ShowSettings);
-                        return Some(LRESULT(0));
-                    } else if self.show_controls.0 == l_param.0 {
-                        self.controls.toggle_controls_visible();
-                        return Some(LRESULT(0));
-                    }
-                }
-            }
-            WM_APP_NOTIFICATION_ICON => {
-                if (l_param.0 & 0xffff) as u32 == wm::WM_LBUTTONUP {
-                    self.toggle_window_visible();
-                    return Some(LRESULT(0));
-                }
+    fn speak_from(&mut self, offset: usize) {
+        self.offset = offset.min(self.last_read.len() - 1);
+        unsafe {
+            self.voice.Speak(
+                PCWSTR::from_raw(self.last_read.as_ptr().add(self.offset)),
+                (Speech::SVSFlagsAsync.0 | Speech::SVSFPurgeBeforeSpeak.0 | Speech::SVSFIsNotXML.0)
+                    .try_into()
+                    .unwrap(),
+                None,
+            )
+        }
+        .unwrap();
+    }
+
+    fn speak_to_wav(&mut self, text: WideString, path: &Path) -> io::Result<Vec<WordTiming>> {
+        let format = Audio::WAVEFORMATEX {
+            wFormatTag: Audio::WAVE_FORMAT_PCM as u16,
+            nChannels: 1,
+            nSamplesPerSec: WAV_SAMPLE_RATE,
+            nAvgBytesPerSec: WAV_SAMPLE_RATE * 2,
+            nBlockAlign: 2,
+            wBitsPerSample: 16,
+            cbSize: 0,
+        };
+        let stream: Speech::ISpStream =
+            unsafe { syscom::CoCreateInstance(&Speech::SpStream, None, syscom::CLSCTX_ALL) }?;
+        let wide_path: WideString = path.to_string_lossy().into();
+        unsafe {
+            stream.BindToFile(
+                PCWSTR::from_raw(wide_path.as_ptr()),
+                Speech::SPFM_CREATE_ALWAYS,
+                Some(&SPDFID_WAVE_FORMAT_EX),
+                Some(&format),
+                0,
+            )
+        }?;
+        unsafe { self.voice.SetOutput(&stream, true) }?;
+        // queue the word boundaries so we can read back where they are in the audio
+        let events = [
+            Speech::SPEI_WORD_BOUNDARY,
+            Speech::SPEI_START_INPUT_STREAM,
+            Speech::SPEI_END_INPUT_STREAM,
+        ];
+        self.set_interest(&events, &[Speech::SPEI_WORD_BOUNDARY]);
+        self.get_word_timings(format.nAvgBytesPerSec.into());
+        self.last_read = text;
+        self.offset = 0;
+        let spoken = unsafe {
+            self.voice.Speak(
+                PCWSTR::from_raw(self.last_read.as_ptr()),
+                (Speech::SVSFPurgeBeforeSpeak.0 | Speech::SVSFIsNotXML.0)
+                    .try_into()
+                    .unwrap(),
+                None,
+            )
+        };
+        let timings = self.get_word_timings(format.nAvgBytesPerSec.into());
+        self.set_interest(&events, &[]);
+        unsafe { self.voice.SetOutput(None::<&IUnknown>, true) }.unwrap();
+        unsafe { stream.Close() }?;
+        spoken?;
+        Ok(timings)
+    }
+
+    fn wait(&mut self) {
+        unsafe { self.voice.WaitUntilDone(INFINITE) }.unwrap();
+    }
+
+    fn pause(&mut self) {
+        unsafe { self.voice.Pause() }.unwrap();
+    }
+
+    fn resume(&mut self) {
+        unsafe { self.voice.Resume() }.unwrap();
+    }
+
+    fn set_rate(&mut self, rate: i32) -> i32 {
+        let rate = max(min(rate, 10), -10);
+        unsafe { self.voice.SetRate(rate) }.unwrap();
+        self.get_rate()
+    }
+
+    fn get_rate(&mut self) -> i32 {
+        let mut rate = 0;
+        unsafe { self.voice.GetRate(&mut rate) }.unwrap();
+        rate
+    }
+
+    fn available_voice_names(&mut self) -> Vec<String> {
+        self.available_voices()
+            .iter()
+            .map(|t| SpVoice::get_voice_name(t.clone()))
+            .collect::<Vec<_>>()
+    }
+
+    fn set_voice_by_name(&mut self, voice_name: String) -> String {
+        if let Some(t) = self
+            .available_voices()
+            .iter()
+            .find(|&t| voice_name == SpVoice::get_voice_name(t.clone()))
+        {
+            self.set_voice(t.clone());
+        }
+        // Return name of voice now in use
+        match unsafe { self.voice.GetVoice().ok() } {
+            Some(t) => SpVoice::get_voice_name(t),
+            None => "unknown".to_string(),
+        }
+    }
+
+    fn set_volume(&mut self, volume: u16) {
+        unsafe { self.voice.SetVolume(min(volume, 100)) }.unwrap();
+    }
+
+    fn get_volume(&mut self) -> u16 {
+        let mut volume = 0;
+        unsafe { self.voice.GetVolume(&mut volume) }.unwrap();
+        volume
+    }
+
+    fn get_status(&mut self) -> Status {
+        let status = self.get_sapi_status();
+        let shift = |r: Range<usize>| {
+            if r.end == 0 {
+                r
+            } else {
+                r.start + self.offset..r.end + self.offset
             }
-            _ => {}
+        };
+        Status {
+            // dwRunningState is a mix of SPRS_DONE = 1 and SPRS_IS_SPEAKING = 2
+            running_state: match status.dwRunningState {
+                0 => RunningState::Idle,
+                2 => RunningState::Speaking,
+                _ => RunningState::Done,
+            },
+            word_range: shift(status.word_range()),
+            sent_range: shift(status.sent_range()),
         }
-        None
     }
-}
 
-impl Drop for SpVoice {
-    fn drop(&mut self) {
-        unsafe { Shell::Shell_NotifyIconW(Shell::NIM_DELETE, &mut self.nicon) };
-        println!("drop for SpVoice");
+    fn last_read(&self) -> &WideString {
+        &self.last_read
     }
 }
 
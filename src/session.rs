@@ -0,0 +1,112 @@
+use preferences::Preferences;
+use serde::{Deserialize, Serialize};
+
+use crate::settings::APP_INFO;
+
+/// Where a window was on the screen.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Placement {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+    #[serde(default)]
+    pub visible: bool,
+}
+
+/// A document as it was when the reader closed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SavedDocument {
+    pub raw: String,
+    /// Where to continue reading from, in utf16 units of the cleaned text.
+    #[serde(default)]
+    pub position: usize,
+    #[serde(default)]
+    pub history_id: Option<u64>,
+    /// `None` for queued documents, that have no window.
+    #[serde(default)]
+    pub placement: Option<Placement>,
+}
+
+/// Everything needed to carry on reading after a restart.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct Session {
+    /// The documents in reader windows, the one being read first.
+    #[serde(default)]
+    pub documents: Vec<SavedDocument>,
+    #[serde(default)]
+    pub queue: Vec<SavedDocument>,
+}
+
+impl Session {
+    /// A missing or corrupt file is an empty session, a reader that does not start is worse.
+    pub fn from_file() -> Session {
+        Session::load(&APP_INFO, "session")
+            .unwrap_or_else(|_| {
+                println!("failed to lode session.");
+                Session::default()
+            })
+            .cleaned()
+    }
+
+    pub fn to_file(&self) {
+        if self.save(&APP_INFO, "session").is_err() {
+            println!("failed to save session.");
+        }
+    }
+
+    /// Drop what can not be restored.
+    fn cleaned(mut self) -> Session {
+        self.documents.retain(|d| !d.raw.trim().is_empty());
+        self.queue.retain(|d| !d.raw.trim().is_empty());
+        for p in self
+            .documents
+            .iter_mut()
+            .filter_map(|d| d.placement.as_mut())
+        {
+            if p.right <= p.left || p.bottom <= p.top {
+                p.visible = false;
+            }
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_file() {
+        let session: Session =
+            serde_json::from_str(r#"{"documents": [{"raw": "Hello"}, {"raw": " "}]}"#).unwrap();
+        let session = session.cleaned();
+        assert_eq!(session.documents.len(), 1);
+        assert_eq!(session.documents[0].position, 0);
+        assert_eq!(session.documents[0].placement, None);
+        assert!(session.queue.is_empty());
+    }
+
+    #[test]
+    fn bad_placement_is_hidden() {
+        let session = Session {
+            documents: vec![SavedDocument {
+                raw: "Hello".to_string(),
+                position: 2,
+                history_id: Some(3),
+                placement: Some(Placement {
+                    left: 10,
+                    top: 10,
+                    right: 10,
+                    bottom: 50,
+                    visible: true,
+                }),
+            }],
+            queue: vec![],
+        };
+        let json = serde_json::to_string(&session).unwrap();
+        let back: Session = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, session);
+        assert!(!back.cleaned().documents[0].placement.unwrap().visible);
+    }
+}
@@ -1,618 +1,153 @@
-use crate::clean_text::RegexCleanerPair;
+use crate::actions::ACTION_LIST;
+use crate::copy_profile::{default_copy_profiles, CopyProfile};
 use crate::hot_key::*;
-use crate::wide_string::WideString;
-use crate::window::*;
-use average::Variance;
-use itertools::Itertools;
+use crate::speak_on_copy::SpeakOnCopySettings;
+use crate::text_source::TextSourceSettings;
+use crate::ticker::TickerSettings;
+use crate::time_estimator::TimeEstimator;
 use preferences::{prefs_base_dir, AppInfo, Preferences};
+use rust_reader::clean_text::{default_cleaners, Dictionary, RegexCleanerPair};
 use serde::{Deserialize, Serialize};
-use windows::core::PCWSTR;
-use windows::w;
-use windows::Win32::{
-    Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, RECT, WPARAM},
-    Graphics::Gdi,
-    System::LibraryLoader,
-    UI::Controls,
-    UI::Input::KeyboardAndMouse::{VK_ESCAPE, VK_OEM_2, VK_OEM_MINUS, VK_OEM_PERIOD, VK_OEM_PLUS},
-    UI::WindowsAndMessaging as wm,
-};
+use std::path::PathBuf;
 
-const APP_INFO: AppInfo = AppInfo {
+pub const APP_INFO: AppInfo = AppInfo {
     name: "rust_reader",
     author: "us",
 };
 
-// TBM_SETPOS is defined in winrows crate, but TBM_GETPOS is missing?
-pub const TBM_GETPOS: u32 = Controls::TBM_SETPOS - 5;
-
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Settings {
     pub rate: i32,
     pub voice: String,
-    pub hotkeys: [(u32, u32); 8],
+    /// One for each of `ACTION_LIST`, (0, 0) for no hotkey.
+    pub hotkeys: Vec<(u32, u32)>,
     pub cleaners: Vec<RegexCleanerPair>,
+    /// Words to say differently, looked for before the `cleaners` are applied.
+    #[serde(default)]
+    pub dictionary: Dictionary,
+    /// The language of the `dictionary`, from the last lexicon imported and given to the next exported.
+    #[serde(default = "default_lexicon_lang")]
+    pub lexicon_lang: String,
+    /// How fast each voice reads, learned while reading.
+    #[serde(default, alias = "time_estimater")]
+    pub time_estimator: TimeEstimator,
+    /// How far `SeekForward` and `SeekBack` jump.
+    #[serde(default = "default_skip_seconds")]
+    pub skip_seconds: f64,
+    /// Where `ExportWav` saves its files.
+    #[serde(default = "default_export_dir")]
+    pub export_dir: PathBuf,
+    /// Remember what was read, for `HistoryBack` and the history window.
+    #[serde(default = "default_keep_history")]
+    pub keep_history: bool,
+    /// How many things to remember.
+    #[serde(default = "default_history_limit")]
+    pub history_limit: usize,
+    /// Forget bookmarks that have not been read for this many days.
+    #[serde(default = "default_bookmark_days")]
+    pub bookmark_days: i64,
+    /// `Read` opens a new window for each text, instead of replacing the text in the focused one.
+    #[serde(default)]
+    pub read_opens_window: bool,
+    /// How `toggle_ticker` shows the words being read.
+    #[serde(default)]
+    pub ticker: TickerSettings,
+    /// Read every new text on the clipboard, turned on and off by `toggle_speak_on_copy`.
     #[serde(default)]
-    pub time_estimater: [Variance; 21],
+    pub speak_on_copy: SpeakOnCopySettings,
+    /// Where `Read`, `Enqueue`, `ExportWav` and `ReadClipboard` get their text.
+    #[serde(default)]
+    pub text_sources: TextSourceSettings,
+    /// How to copy from each program, the first that matches the program in front is used.
+    #[serde(default = "default_copy_profiles")]
+    pub copy_profiles: Vec<CopyProfile>,
 }
 
-pub struct SettingsWindow {
-    settings: Settings,
-    available_voices: Vec<String>,
-    window: HWND,
-    rate: (HWND, HWND),
-    voice: (HWND, HWND),
-    hotkeys: [(HWND, HWND); 8],
-    cleaners: Vec<(Option<bool>, HWND, HWND, HWND, HWND)>,
-    add_cleaner: HWND,
-    reset: HWND,
-    save: HWND,
+fn default_keep_history() -> bool {
+    true
 }
 
-impl SettingsWindow {
-    pub fn new(s: Settings, voice_list: Vec<String>) -> Box<SettingsWindow> {
-        let mut out = Box::new(SettingsWindow {
-            settings: s,
-            available_voices: voice_list,
-            window: HWND(0),
-            rate: (HWND(0), HWND(0)),
-            voice: (HWND(0), HWND(0)),
-            hotkeys: [(HWND(0), HWND(0)); 8],
-            cleaners: Vec::new(),
-            add_cleaner: HWND(0),
-            reset: HWND(0),
-            save: HWND(0),
-        });
-
-        let window_class_name = w!("setings_window_class_name");
-        unsafe {
-            wm::RegisterClassW(&wm::WNDCLASSW {
-                style: wm::WNDCLASS_STYLES(0),
-                lpfnWndProc: Some(window_proc_generic::<SettingsWindow>),
-                cbClsExtra: 0,
-                cbWndExtra: 0,
-                hInstance: HINSTANCE(0),
-                hIcon: wm::LoadIconW(
-                    LibraryLoader::GetModuleHandleW(PCWSTR::null()).unwrap(),
-                    PCWSTR::from_raw(1 as *const u16),
-                )
-                .expect("failed to load icon"),
-                hCursor: wm::LoadCursorW(HINSTANCE(0), wm::IDI_APPLICATION)
-                    .expect("failed to load icon"),
-                hbrBackground: Gdi::HBRUSH(16),
-                lpszMenuName: PCWSTR::null(),
-                lpszClassName: window_class_name,
-            });
-            out.window = wm::CreateWindowExW(
-                wm::WINDOW_EX_STYLE(0),
-                window_class_name,
-                PCWSTR(&mut 0u16),
-                wm::WS_OVERLAPPEDWINDOW | wm::WS_CLIPSIBLINGS,
-                0,
-                0,
-                0,
-                0,
-                wm::GetDesktopWindow(),
-                wm::HMENU(0),
-                HINSTANCE(0),
-                Some(&mut *out as *mut _ as *mut _),
-            );
-            Controls::InitCommonControls();
-            out.rate.1 = wm::CreateWindowExW(
-                wm::WINDOW_EX_STYLE(0),
-                Controls::TRACKBAR_CLASSW,
-                PCWSTR(&mut 0u16),
-                wm::WS_CHILD
-                    | wm::WS_VISIBLE
-                    | wm::WINDOW_STYLE(Controls::TBS_AUTOTICKS | Controls::TBS_BOTTOM),
-                0,
-                0,
-                0,
-                0,
-                out.window,
-                wm::HMENU(0),
-                HINSTANCE(0),
-                None,
-            );
-            wm::SendMessageW(
-                out.rate.1,
-                Controls::TBM_SETRANGE,
-                WPARAM(0),
-                LPARAM((20 << 16) as isize),
-            );
-            wm::SendMessageW(out.rate.1, Controls::TBM_SETPAGESIZE, WPARAM(0), LPARAM(1));
-            out.rate.0 = create_static_window(out.window, None);
-
-            let voice_label: WideString = "voice".into();
-            out.voice.0 = create_static_window(out.window, Some(&voice_label));
-            out.voice.1 = wm::CreateWindowExW(
-                wm::WINDOW_EX_STYLE(0),
-                Controls::WC_COMBOBOX,
-                PCWSTR(&mut 0u16),
-                wm::WS_CHILD
-                    | wm::WS_VISIBLE
-                    | wm::WS_OVERLAPPED
-                    | wm::WINDOW_STYLE(
-                        (wm::CBS_DROPDOWNLIST | wm::CBS_SORT | wm::CBS_HASSTRINGS)
-                            .try_into()
-                            .unwrap(),
-                    ),
-                0,
-                0,
-                0,
-                0,
-                out.window,
-                wm::HMENU(0),
-                HINSTANCE(0),
-                None,
-            );
-            // Populate combobox with all available voices
-            for voice in out.available_voices.iter() {
-                let wide_voice: WideString = voice.as_str().into();
-                wm::SendMessageW(
-                    out.voice.1,
-                    wm::CB_ADDSTRING,
-                    WPARAM(0),
-                    LPARAM(wide_voice.as_ptr() as isize),
-                );
-            }
-
-            out.add_cleaner = create_button_window(out.window, w!("add cleaner"));
-            out.save = create_button_window(out.window, w!("save"));
-            out.reset = create_button_window(out.window, w!("reset"));
-            let window = out.window;
-
-            let mut icex: Controls::INITCOMMONCONTROLSEX = ::std::mem::zeroed();
-            icex.dwSize = ::std::mem::size_of::<Controls::INITCOMMONCONTROLSEX>() as u32;
-            icex.dwICC = Controls::ICC_HOTKEY_CLASS;
-            Controls::InitCommonControlsEx(&icex);
-
-            for (act, ht) in crate::actions::ACTION_LIST
-                .iter()
-                .zip(out.hotkeys.iter_mut())
-            {
-                let wide_hotkey_name: WideString = format!("{}", act).into();
-                ht.0 = create_static_window(window, Some(&wide_hotkey_name));
-                ht.1 = wm::CreateWindowExW(
-                    wm::WINDOW_EX_STYLE(0),
-                    Controls::HOTKEY_CLASSW,
-                    PCWSTR(&mut 0u16),
-                    wm::WS_CHILD | wm::WS_VISIBLE,
-                    0,
-                    0,
-                    0,
-                    0,
-                    window,
-                    wm::HMENU(0),
-                    HINSTANCE(0),
-                    None,
-                );
-                wm::SendMessageW(
-                    ht.1,
-                    Controls::HKM_SETRULES,
-                    WPARAM((Controls::HKCOMB_NONE | Controls::HKCOMB_S) as usize),
-                    LPARAM(Controls::HOTKEYF_CONTROL as isize),
-                );
-            }
-        }
-        set_window_text(out.window, &"reader settings".into());
-        out.get_inner_all();
-        move_window(
-            out.window,
-            &RECT {
-                left: 0,
-                top: 0,
-                right: 400,
-                bottom: 400,
-            },
-        );
-        show_window(out.window, wm::SW_SHOWNORMAL);
-        out.toggle_window_visible();
-        out
-    }
-
-    fn add_cleaner(&mut self) {
-        self.cleaners.push((
-            None,
-            create_edit_window(self.window, wm::WINDOW_STYLE(0)),
-            create_edit_window(self.window, wm::WINDOW_STYLE(0)),
-            create_button_window(self.window, w!("^")),
-            create_button_window(self.window, w!("X")),
-        ));
-    }
-
-    fn remove_cleaner(&mut self, index: usize) {
-        let hwnd = self.cleaners.remove(index);
-        destroy_window(hwnd.1);
-        destroy_window(hwnd.2);
-        destroy_window(hwnd.3);
-        destroy_window(hwnd.4);
-    }
-
-    fn swap_cleaner(&mut self, index: usize) {
-        if index >= 1 {
-            self.cleaners.swap(index - 1, index);
-        }
-    }
-
-    pub fn get_inner_settings(&self) -> &Settings {
-        &self.settings
-    }
-
-    pub fn get_mut_inner_settings(&mut self) -> &mut Settings {
-        &mut self.settings
-    }
-
-    pub fn toggle_window_visible(&self) -> bool {
-        toggle_window_visible(self.window)
-    }
-
-    pub fn show_window(&self) -> bool {
-        show_window(self.window, wm::SW_SHOW)
-    }
-
-    pub fn get_inner_rate(&mut self) -> i32 {
-        let rate = self.settings.rate;
-        unsafe {
-            wm::SendMessageW(
-                self.rate.1,
-                Controls::TBM_SETPOS,
-                WPARAM(1),
-                LPARAM((rate + 10) as isize),
-            );
-        }
-        set_window_text(self.rate.0, &format!("reading at rate: {}", rate).into());
-        rate
-    }
-
-    pub fn get_inner_voice(&mut self) {
-        unsafe {
-            // Find position of setting in voice list
-            let wide_voice: WideString = self.settings.voice.as_str().into();
-            let index = wm::SendMessageW(
-                self.voice.1,
-                wm::CB_FINDSTRING,
-                WPARAM(0),
-                LPARAM(wide_voice.as_ptr() as isize),
-            )
-            .0;
-            // If voice from settings file was found, set combobox selection
-            if index >= 0 {
-                wm::SendMessageW(
-                    self.voice.1,
-                    wm::CB_SETCURSEL,
-                    WPARAM(index as usize),
-                    LPARAM(0),
-                );
-            }
-        }
-    }
-
-    pub fn get_selected_voice(&self) -> String {
-        let index =
-            unsafe { wm::SendMessageW(self.voice.1, wm::CB_GETCURSEL, WPARAM(0), LPARAM(0)) }.0
-                as usize;
-        let item_length = unsafe {
-            wm::SendMessageW(self.voice.1, wm::CB_GETLBTEXTLEN, WPARAM(index), LPARAM(0))
-        }
-        .0;
-        if item_length < 0 {
-            // no voice selected
-            return "unknown".to_string();
-        }
-        let mut buf = vec![0u16; item_length as usize + 1];
-        unsafe {
-            wm::SendMessageW(
-                self.voice.1,
-                wm::CB_GETLBTEXT,
-                WPARAM(index),
-                LPARAM(buf.as_mut_ptr() as isize),
-            );
-        }
-        WideString::from_raw(buf).as_string()
-    }
-
-    pub fn get_inner_hotkeys(&self) -> [(u32, u32); 8] {
-        for (&(a, b), hwnd) in self.settings.hotkeys.iter().zip(self.hotkeys.iter()) {
-            unsafe {
-                wm::SendMessageW(
-                    hwnd.1,
-                    Controls::HKM_SETHOTKEY,
-                    WPARAM((b as u16 | ((convert_mod(a as u8) as u16) << 8)).into()),
-                    LPARAM(0),
-                );
-            }
-        }
-        self.settings.hotkeys
-    }
-
-    pub fn get_inner_cleaners(&mut self) -> &[RegexCleanerPair] {
-        if self.cleaners.len() != self.settings.cleaners.len() {
-            while self.cleaners.len() < self.settings.cleaners.len() {
-                self.add_cleaner();
-            }
-            while self.cleaners.len() > self.settings.cleaners.len() {
-                let i = self.cleaners.len() - 1;
-                self.remove_cleaner(i);
-            }
-            unsafe {
-                wm::SendMessageW(self.window, wm::WM_SIZE, WPARAM(0), LPARAM(0));
-            }
-        }
-        for (cl, rexpar) in self
-            .cleaners
-            .iter_mut()
-            .zip_eq(self.settings.cleaners.iter())
-        {
-            let (re, pal) = rexpar.to_parts();
-            cl.0 = None;
-            set_window_text(cl.1, &re.as_str().into());
-            set_window_text(cl.2, &pal.into());
-        }
-        &self.settings.cleaners
-    }
-
-    fn get_inner_all(&mut self) {
-        self.get_inner_rate();
-        self.get_inner_voice();
-        self.get_inner_hotkeys();
-        self.get_inner_cleaners();
-    }
-
-    pub fn inner_to_file(&mut self) {
-        self.get_inner_all();
-        self.settings.to_file()
-    }
+fn default_history_limit() -> usize {
+    200
 }
 
-impl Windowed for SettingsWindow {
-    fn window_proc(&mut self, msg: u32, w_param: WPARAM, l_param: LPARAM) -> Option<LRESULT> {
-        use itertools::EitherOrBoth::{Both, Left, Right};
-        match msg {
-            wm::WM_CLOSE => {
-                show_window(self.window, wm::SW_HIDE);
-                return Some(LRESULT(0));
-            }
-            wm::WM_SIZE => {
-                let rect = get_client_rect(self.window).inset(3);
-                if (w_param.0 <= 2) && rect.right > 0 && rect.bottom > 0 {
-                    let mut rect = rect.split_rows(rect.bottom - 50);
-                    let mut bot = rect.1.split_rows(25);
-                    bot.0 = bot.0.inset(3).shift_right(50);
-                    bot.0.right -= 50;
-                    move_window(self.add_cleaner, &bot.0);
-                    let (l, r) = bot.1.split_columns(bot.1.right / 2);
-                    move_window(self.reset, &l);
-                    move_window(self.save, &r);
-                    rect = rect.0.split_rows(25);
-                    let (l, r) = rect.0.split_columns(160);
-                    move_window(self.rate.0, &l);
-                    move_window(self.rate.1, &r);
-
-                    rect = rect.1.split_rows(25);
-                    let (l, r) = rect.0.split_columns(160);
-                    move_window(self.voice.0, &l);
-                    move_window(self.voice.1, &r);
-
-                    for &ht in &self.hotkeys {
-                        rect = rect.1.split_rows(25);
-                        let (l, r) = rect.0.split_columns(160);
-                        move_window(ht.0, &l);
-                        move_window(ht.1, &r);
-                    }
-                    let mll = self
-                        .cleaners
-                        .iter()
-                        .map(|&(_, a, _, _, _)| get_window_text_length(a))
-                        .max()
-                        .unwrap_or(0)
-                        + 1;
-                    let mlr = self
-                        .cleaners
-                        .iter()
-                        .map(|&(_, _, b, _, _)| get_window_text_length(b))
-                        .max()
-                        .unwrap_or(0)
-                        + 1;
-                    rect.1 = rect.1.shift_down(5);
-                    let split_at = (rect.1.right - 50) * mll / (mll + mlr);
-                    for &ht in &self.cleaners {
-                        rect = rect.1.split_rows(25);
-                        let (l, r) = rect.0.split_columns(rect.1.right - 50);
-                        let r = r.split_columns(25);
-                        unsafe {
-                            Gdi::InvalidateRect(ht.3, None, true);
-                        }
-                        move_window(ht.3, &r.0.inset(3));
-                        unsafe {
-                            Gdi::InvalidateRect(ht.4, None, true);
-                        }
-                        move_window(ht.4, &r.1.inset(3));
-                        let (l, r) = l.split_columns(split_at);
-                        move_window(ht.1, &l);
-                        move_window(ht.2, &r);
-                    }
-                    return Some(LRESULT(0));
-                }
-            }
-            wm::WM_GETMINMAXINFO => {
-                let data = unsafe { &mut *(l_param.0 as *mut wm::MINMAXINFO) };
-                data.ptMinTrackSize.x = 340;
-                data.ptMinTrackSize.y =
-                    (80 + 25 * (3 + self.hotkeys.len()) + 25 * self.cleaners.len()) as i32;
-                return Some(LRESULT(0));
-            }
-            wm::WM_COMMAND | wm::WM_HSCROLL => {
-                let mut changed = false;
-                let mut invalid = false;
-                let mut dirty_cleaners = false;
-                let hiword = ((w_param.0 >> 16) & 0xffff) as u32;
+fn default_bookmark_days() -> i64 {
+    30
+}
 
-                if hiword == wm::BN_CLICKED {
-                    if self.reset.0 == l_param.0 {
-                        self.get_inner_all();
-                    }
-                    if self.add_cleaner.0 == l_param.0 {
-                        self.add_cleaner();
-                        dirty_cleaners = true;
-                        unsafe {
-                            wm::SendMessageW(self.window, wm::WM_SIZE, WPARAM(0), LPARAM(0));
-                        }
-                    }
-                    if let Some(i) = self.cleaners.iter().position(|x| x.3 .0 == l_param.0) {
-                        self.swap_cleaner(i);
-                        dirty_cleaners = true;
-                        unsafe {
-                            wm::SendMessageW(self.window, wm::WM_SIZE, WPARAM(0), LPARAM(0));
-                        }
-                    }
-                    if let Some(i) = self.cleaners.iter().position(|x| x.4 .0 == l_param.0) {
-                        self.remove_cleaner(i);
-                        dirty_cleaners = true;
-                        unsafe {
-                            wm::SendMessageW(self.window, wm::WM_SIZE, WPARAM(0), LPARAM(0));
-                        }
-                    }
-                }
+fn default_lexicon_lang() -> String {
+    "en-US".to_string()
+}
 
-                let saving = self.save.0 == l_param.0 && hiword == wm::BN_CLICKED;
+fn default_skip_seconds() -> f64 {
+    10.0
+}
 
-                // rate change
-                let new_rate =
-                    unsafe { wm::SendMessageW(self.rate.1, TBM_GETPOS, WPARAM(0), LPARAM(0)) }.0
-                        - 10;
-                if self.settings.rate != new_rate as i32 {
-                    changed = true;
-                }
-                // voice change
-                let new_voice = self.get_selected_voice();
-                if new_voice != self.settings.voice {
-                    changed = true;
-                }
-                // hotkeys change
-                for (&(_, ht), hkt) in self.hotkeys.iter().zip_eq(self.settings.hotkeys.iter()) {
-                    let set_to = unsafe {
-                        wm::SendMessageW(ht, Controls::HKM_GETHOTKEY, WPARAM(0), LPARAM(0))
-                    }
-                    .0;
-                    let new = (
-                        u32::from(convert_mod(((set_to >> 8) & 0xff) as u8)),
-                        u32::from((set_to as u16) & 0xff),
-                    );
-                    if *hkt != new {
-                        changed = true;
-                    }
-                }
-                if self
-                    .cleaners
-                    .iter()
-                    .any(|x| x.1 .0 == l_param.0 || x.2 .0 == l_param.0)
-                    || dirty_cleaners
-                {
-                    // cleaners change
-                    for mat in self
-                        .cleaners
-                        .iter_mut()
-                        .zip_longest(self.settings.cleaners.iter())
-                    {
-                        match mat {
-                            Both(cl, rexpar) => {
-                                let (re, pal) = rexpar.to_parts();
-                                let new_a = get_window_text(cl.1).as_string();
-                                let new_b = get_window_text(cl.2).as_string();
-                                if !new_a.is_empty() || !new_b.is_empty() {
-                                    if (new_a != re.as_str()) || (new_b != pal) {
-                                        cl.0 = Some(RegexCleanerPair::new(new_a, new_b).is_ok());
-                                    } else {
-                                        cl.0 = None;
-                                    }
-                                }
-                            }
-                            Right(_) => (),
-                            Left(cl) => {
-                                let new_a = get_window_text(cl.1).as_string();
-                                let new_b = get_window_text(cl.2).as_string();
-                                if !new_a.is_empty() || !new_b.is_empty() {
-                                    cl.0 = Some(RegexCleanerPair::new(new_a, new_b).is_ok());
-                                }
-                            }
-                        }
-                    }
-                }
-                changed = changed
-                    || self.settings.cleaners.len() != self.cleaners.len()
-                    || self.cleaners.iter().any(|x| x.0.is_some());
-                invalid = invalid || self.cleaners.iter().any(|x| x.0 == Some(false));
-                enable_window(self.reset, changed);
-                enable_window(self.save, changed && !invalid);
-                if saving && changed && !invalid {
-                    use crate::press_hotkey;
-                    use crate::Action;
-                    self.settings.rate = new_rate as i32;
-                    self.settings.voice = new_voice;
-                    for (&(_, ht), hkt) in
-                        self.hotkeys.iter().zip_eq(self.settings.hotkeys.iter_mut())
-                    {
-                        let set_to = unsafe {
-                            wm::SendMessageW(ht, Controls::HKM_GETHOTKEY, WPARAM(0), LPARAM(0))
-                        }
-                        .0;
-                        *hkt = (
-                            u32::from(convert_mod(((set_to >> 8) & 0xff) as u8)),
-                            u32::from((set_to as u16) & 0xff),
-                        );
-                    }
-                    self.settings.cleaners = self
-                        .cleaners
-                        .iter()
-                        .map(|cl| {
-                            let new_a = get_window_text(cl.1).as_string();
-                            let new_b = get_window_text(cl.2).as_string();
-                            RegexCleanerPair::new(new_a, new_b).unwrap()
-                        })
-                        .collect();
-                    self.settings.to_file();
-                    enable_window(self.save, false);
-                    press_hotkey(Action::ReloadSettings);
-                }
-            }
-            _ => {}
-        }
-        None
-    }
+fn default_export_dir() -> PathBuf {
+    prefs_base_dir()
+        .map(|mut p| {
+            p.push("us");
+            p.push("rust_reader");
+            p.push("exports");
+            p
+        })
+        .unwrap_or_default()
 }
 
 impl Settings {
     pub fn new() -> Settings {
-        Settings {
+        let mut out = Settings {
             rate: 6,
             voice: "Microsoft David Desktop".to_string(),
-            hotkeys: [
-                (2, VK_OEM_2.0.into()),      // ctrl-? key
-                (7, VK_ESCAPE.0.into()),     // ctrl-alt-shift-esk
-                (7, 0x52 as u32),            // ctrl-alt-shift-r
-                (7, 0x53 as u32),            // ctrl-alt-shift-s
-                (3, VK_OEM_2.0.into()),      // ctrl-alt-?
-                (2, VK_OEM_PERIOD.0.into()), // ctrl-.
-                (3, VK_OEM_MINUS.0.into()),  // ctrl-alt--
-                (3, VK_OEM_PLUS.0.into()),   // ctrl-alt-=
+            hotkeys: vec![
+                (2, VK_OEM_2),      // ctrl-? key
+                (7, VK_ESCAPE),     // ctrl-alt-shift-esk
+                (7, 0x52),          // ctrl-alt-shift-r
+                (7, 0x53),          // ctrl-alt-shift-s
+                (3, VK_OEM_2),      // ctrl-alt-?
+                (2, VK_OEM_PERIOD), // ctrl-.
+                (3, VK_OEM_MINUS),  // ctrl-alt--
+                (3, VK_OEM_PLUS),   // ctrl-alt-=
+                (0, 0),             // export_wav
+                (0, 0),             // next_sentence
+                (0, 0),             // previous_sentence
+                (0, 0),             // next_paragraph
+                (0, 0),             // previous_paragraph
+                (0, 0),             // seek_forward
+                (0, 0),             // seek_back
+                (0, 0),             // enqueue
+                (0, 0),             // next_in_queue
+                (0, 0),             // clear_queue
+                (0, 0),             // announce_queue
+                (0, 0),             // history_back
+                (0, 0),             // history_forward
+                (0, 0),             // show_history
+                (0, 0),             // resume_bookmark
+                (0, 0),             // toggle_ticker
+                (0, 0),             // toggle_speak_on_copy
+                (0, 0),             // read_clipboard
             ],
-            cleaners: RegexCleanerPair::prep_list(&[
-                (r"\s+", " "),
-                (
-                    concat!(
-                        r"(https?://)?(?P<a>[-a-zA-Z0-9@:%._\+~#=]{2,256}",
-                        r"\.[a-z]{2,6})\b[-a-zA-Z0-9@:%_\+.~#?&//=]{10,}"
-                    ),
-                    "link to $a",
-                ),
-                (
-                    r"(?P<s>[0-9a-f]{6})([0-9]+[a-f]|[a-f]+[0-9])[0-9a-f]*",
-                    "hash $s",
-                ),
-            ])
-            .unwrap(),
-            time_estimater: Default::default(),
-        }
+            cleaners: default_cleaners(),
+            dictionary: Dictionary::default(),
+            lexicon_lang: default_lexicon_lang(),
+            time_estimator: TimeEstimator::default(),
+            skip_seconds: default_skip_seconds(),
+            export_dir: default_export_dir(),
+            keep_history: default_keep_history(),
+            history_limit: default_history_limit(),
+            bookmark_days: default_bookmark_days(),
+            read_opens_window: false,
+            ticker: TickerSettings::default(),
+            speak_on_copy: SpeakOnCopySettings::default(),
+            text_sources: TextSourceSettings::default(),
+            copy_profiles: default_copy_profiles(),
+        };
+        out.fill_hotkeys();
+        out
+    }
+    /// Settings saved before an action was added have no hotkey for it.
+    fn fill_hotkeys(&mut self) {
+        self.hotkeys.resize(ACTION_LIST.len(), (0, 0));
     }
     pub fn get_dir(&self) -> ::std::path::PathBuf {
         prefs_base_dir()
@@ -625,15 +160,18 @@ impl Settings {
             .unwrap_or_default()
     }
     pub fn from_file() -> Settings {
-        Settings::load(&APP_INFO, "setings").unwrap_or_else(|_| {
+        let mut out = Settings::load(&APP_INFO, "setings").unwrap_or_else(|_| {
             println!("failed to lode settings.");
             Settings::new()
-        })
+        });
+        out.fill_hotkeys();
+        out
     }
     pub fn reload_from_file(&mut self) -> bool {
         if let Ok(new) = Settings::load(&APP_INFO, "setings") {
             println!("reload settings.");
             *self = new;
+            self.fill_hotkeys();
             true
         } else {
             println!("failed to reload settings.");
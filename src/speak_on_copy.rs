@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+
+/// Reading whatever is copied, without pressing `Read`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct SpeakOnCopySettings {
+    pub enabled: bool,
+    /// Wait this long after a copy for the next one, and only read the last.
+    pub debounce_ms: u32,
+    /// Never read what these programs copy, by the file name of the program with or without `.exe`.
+    pub excluded_apps: Vec<String>,
+}
+
+impl Default for SpeakOnCopySettings {
+    fn default() -> SpeakOnCopySettings {
+        SpeakOnCopySettings {
+            enabled: false,
+            debounce_ms: 300,
+            excluded_apps: [
+                "1Password",
+                "Bitwarden",
+                "Dashlane",
+                "KeePass",
+                "KeePassXC",
+                "LastPass",
+                "RoboForm",
+            ]
+            .iter()
+            .map(|s| s.to_string())
+            .collect(),
+        }
+    }
+}
+
+impl SpeakOnCopySettings {
+    /// If the program at `exe_path` is one of `excluded_apps`.
+    pub fn is_excluded(&self, exe_path: &str) -> bool {
+        self.excluded_apps
+            .iter()
+            .any(|app| same_program(exe_path, app))
+    }
+}
+
+/// If the program at `exe_path` has the file name `name`, with or without `.exe`.
+pub fn same_program(exe_path: &str, name: &str) -> bool {
+    let file = exe_path.rsplit(['\\', '/']).next().unwrap_or(exe_path);
+    strip_exe(name.trim()).eq_ignore_ascii_case(strip_exe(file))
+}
+
+fn strip_exe(name: &str) -> &str {
+    match name.len().checked_sub(4) {
+        Some(i) if name.is_char_boundary(i) && name[i..].eq_ignore_ascii_case(".exe") => &name[..i],
+        _ => name,
+    }
+}
+
+/// What is known about a change to the clipboard.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClipboardChange {
+    pub seq: u32,
+    /// The last change the reader made itself.
+    pub own_seq: Option<u32>,
+    /// The program that copied asked for it not to be monitored, like a password manager.
+    pub private: bool,
+    /// The path of the program that copied, if it could be found.
+    pub owner: Option<String>,
+}
+
+/// Which changes to the clipboard are new copies by the user.
+#[derive(Debug, Default)]
+pub struct CopyFilter {
+    last_seq: Option<u32>,
+}
+
+impl CopyFilter {
+    /// The clipboard is at `seq`, and `own_seq` was the last change the reader made itself.
+    ///
+    /// Returns true only once for each new copy.
+    pub fn take(&mut self, seq: u32, own_seq: Option<u32>) -> bool {
+        if self.last_seq == Some(seq) {
+            return false;
+        }
+        self.last_seq = Some(seq);
+        own_seq != Some(seq)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn excluded_apps() {
+        let settings = SpeakOnCopySettings::default();
+        assert!(settings.is_excluded(r"C:\Program Files\KeePass Password Safe 2\KeePass.exe"));
+        assert!(settings.is_excluded(r"C:\Program Files\KeePassXC\keepassxc.EXE"));
+        assert!(settings.is_excluded("Bitwarden"));
+        assert!(!settings.is_excluded(r"C:\Windows\notepad.exe"));
+        assert!(!settings.is_excluded(""));
+    }
+
+    #[test]
+    fn only_new_copies() {
+        let mut filter = CopyFilter::default();
+        assert!(filter.take(5, Some(3)));
+        // the same copy, seen twice
+        assert!(!filter.take(5, Some(3)));
+        // the reader put the clipboard back after its own ctrl-c
+        assert!(!filter.take(8, Some(8)));
+        assert!(filter.take(9, Some(8)));
+    }
+}
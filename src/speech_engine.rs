@@ -0,0 +1,67 @@
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+
+use crate::wav::WordTiming;
+use rust_reader::wide_string::WideString;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RunningState {
+    /// Nothing has been spoken yet, or reading is paused.
+    Idle,
+    /// Finished reading the last text.
+    Done,
+    /// Reading out loud.
+    Speaking,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Status {
+    pub running_state: RunningState,
+    /// The word being read, in utf16 units of `last_read`.
+    pub word_range: Range<usize>,
+    /// The sentence being read, in utf16 units of `last_read`.
+    pub sent_range: Range<usize>,
+}
+
+/// Something that can read text out loud.
+///
+/// Every time the engine passes a word boundary it lets the owner know
+/// (SAPI posts a `WM_SAPI_EVENT`, espeak-ng sends on a channel),
+/// and the owner asks `get_status` for where it is.
+pub trait SpeechEngine {
+    /// Stop reading whatever is in progress and start reading `text`.
+    fn speak(&mut self, text: WideString);
+    /// Stop reading and start reading `last_read` again from the utf16 index `offset`.
+    ///
+    /// Positions in `get_status` stay relative to the start of `last_read`.
+    fn speak_from(&mut self, offset: usize);
+    /// Block until reading is done.
+    fn wait(&mut self);
+    fn speak_wait(&mut self, text: WideString) {
+        self.speak(text);
+        self.wait();
+    }
+    /// Read `text` into a wav file instead of out loud, returns when each word starts.
+    ///
+    /// Blocks until the whole file is written.
+    fn speak_to_wav(&mut self, text: WideString, path: &Path) -> io::Result<Vec<WordTiming>>;
+    fn pause(&mut self);
+    fn resume(&mut self);
+    /// Set the rate in the range (-10, 10), returns the rate now in use.
+    fn set_rate(&mut self, rate: i32) -> i32;
+    fn get_rate(&mut self) -> i32;
+    fn change_rate(&mut self, delta: i32) -> i32 {
+        let rate = self.get_rate() + delta;
+        self.set_rate(rate)
+    }
+    fn available_voice_names(&mut self) -> Vec<String>;
+    /// Returns name of voice now in use.
+    fn set_voice_by_name(&mut self, voice_name: String) -> String;
+    fn set_volume(&mut self, volume: u16);
+    #[allow(dead_code)]
+    fn get_volume(&mut self) -> u16;
+    fn get_status(&mut self) -> Status;
+    /// The text passed to the last call of `speak`.
+    fn last_read(&self) -> &WideString;
+}
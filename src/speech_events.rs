@@ -0,0 +1,315 @@
+use std::ops::Range;
+
+use crate::document::Document;
+use crate::speech_engine::*;
+
+/// Part of a document, in utf16 units of both the cleaned text that is spoken
+/// and the raw text it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    pub cleaned: Range<usize>,
+    pub original: Range<usize>,
+}
+
+impl Span {
+    fn new(document: &Document, cleaned: &Range<usize>) -> Span {
+        Span {
+            cleaned: cleaned.clone(),
+            original: document.original_range(cleaned),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpeechEvent {
+    /// The first word of a document is being read.
+    Start,
+    Word(Span),
+    Sentence(Span),
+    /// Reading passed a mark from `SpeechEvents::set_mark`.
+    Bookmark {
+        name: String,
+        at: Span,
+    },
+    /// Finished reading the document.
+    End,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    Start,
+    Word,
+    Sentence,
+    Bookmark,
+    End,
+}
+
+impl SpeechEvent {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            SpeechEvent::Start => EventKind::Start,
+            SpeechEvent::Word(_) => EventKind::Word,
+            SpeechEvent::Sentence(_) => EventKind::Sentence,
+            SpeechEvent::Bookmark { .. } => EventKind::Bookmark,
+            SpeechEvent::End => EventKind::End,
+        }
+    }
+}
+
+pub type SubscriptionId = usize;
+
+type Subscriber<C> = Box<dyn FnMut(&SpeechEvent, &mut C)>;
+
+/// Turns the status of the voice at each boundary into events, and passes them to whoever asked.
+///
+/// The engines only say where they are, this works out what changed since the last boundary.
+/// Subscribers are called with the event and a `C`, whatever holds this and what they follow
+/// along in. In the reader that is `State`, where the ticker and the progress bar subscribe.
+pub struct SpeechEvents<C = ()> {
+    subscribers: Vec<(SubscriptionId, Vec<EventKind>, Subscriber<C>)>,
+    /// Unsubscribed since `notify` started, so not to be called any more.
+    unsubscribed: Vec<SubscriptionId>,
+    next_id: SubscriptionId,
+    /// The cleaned utf16 offset and name of each mark, in the document being read.
+    marks: Vec<(usize, String)>,
+    last_word: Option<Range<usize>>,
+    last_sentence: Option<Range<usize>>,
+    started: bool,
+    ended: bool,
+}
+
+impl<C> Default for SpeechEvents<C> {
+    fn default() -> Self {
+        SpeechEvents {
+            subscribers: Vec::new(),
+            unsubscribed: Vec::new(),
+            next_id: 0,
+            marks: Vec::new(),
+            last_word: None,
+            last_sentence: None,
+            started: false,
+            ended: false,
+        }
+    }
+}
+
+impl<C> SpeechEvents<C> {
+    /// Call `f` with each event of one of `kinds`.
+    pub fn subscribe<F: FnMut(&SpeechEvent, &mut C) + 'static>(
+        &mut self,
+        kinds: &[EventKind],
+        f: F,
+    ) -> SubscriptionId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.subscribers.push((id, kinds.to_vec(), Box::new(f)));
+        id
+    }
+
+    #[allow(dead_code)]
+    pub fn unsubscribe(&mut self, id: SubscriptionId) {
+        self.subscribers.retain(|(i, _, _)| *i != id);
+        self.unsubscribed.push(id);
+    }
+
+    /// Call the subscribers to the kind of `event` with `context`, where `events` finds this.
+    ///
+    /// They can subscribe and unsubscribe from in there, it counts from the next event.
+    pub fn notify(context: &mut C, events: fn(&mut C) -> &mut Self, event: &SpeechEvent) {
+        let this = events(context);
+        this.unsubscribed.clear();
+        let mut subscribers = std::mem::take(&mut this.subscribers);
+        for (id, kinds, f) in &mut subscribers {
+            if kinds.contains(&event.kind()) && !events(context).unsubscribed.contains(id) {
+                f(event, context);
+            }
+        }
+        let this = events(context);
+        let added = std::mem::replace(&mut this.subscribers, subscribers);
+        this.subscribers.extend(added);
+        let unsubscribed = &this.unsubscribed;
+        this.subscribers
+            .retain(|(id, _, _)| !unsubscribed.contains(id));
+    }
+
+    /// Send a `Bookmark` event named `name` when reading gets to the cleaned utf16 offset `at`.
+    ///
+    /// Marks are for the document being read, `start` forgets them.
+    pub fn set_mark<T: Into<String>>(&mut self, name: T, at: usize) {
+        self.marks.push((at, name.into()));
+    }
+
+    /// A new document is being read.
+    pub fn start(&mut self) {
+        self.marks.clear();
+        self.last_word = None;
+        self.last_sentence = None;
+        self.started = false;
+        self.ended = false;
+    }
+
+    /// The voice passed a boundary in `document`, returns what happened since the last one.
+    pub fn observe(&mut self, status: &Status, document: &Document) -> Vec<SpeechEvent> {
+        let mut out = Vec::new();
+        if status.running_state == RunningState::Done {
+            if self.started && !self.ended {
+                self.ended = true;
+                out.push(SpeechEvent::End);
+            }
+        } else if status.word_range.end > 0 {
+            self.ended = false;
+            if !self.started {
+                self.started = true;
+                out.push(SpeechEvent::Start);
+            }
+            let sentence = &status.sent_range;
+            if !sentence.is_empty() && self.last_sentence.as_ref() != Some(sentence) {
+                self.last_sentence = Some(sentence.clone());
+                out.push(SpeechEvent::Sentence(Span::new(document, sentence)));
+            }
+            let word = &status.word_range;
+            if self.last_word.as_ref().map(|w| w.start) != Some(word.start) {
+                // marks between the last word and this one, or at this one after a jump back
+                let from = match &self.last_word {
+                    Some(last) if last.start < word.start => last.start + 1,
+                    _ => word.start,
+                };
+                self.last_word = Some(word.clone());
+                out.push(SpeechEvent::Word(Span::new(document, word)));
+                out.extend(
+                    self.marks
+                        .iter()
+                        .filter(|(at, _)| from <= *at && *at <= word.start)
+                        .map(|(at, name)| SpeechEvent::Bookmark {
+                            name: name.clone(),
+                            at: Span::new(document, &(*at..*at)),
+                        }),
+                );
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_engine::MemoryEngine;
+    use rust_reader::clean_text::RegexCleanerPair;
+
+    #[derive(Default)]
+    struct Heard {
+        events: SpeechEvents<Heard>,
+        words: Vec<SpeechEvent>,
+    }
+
+    #[test]
+    fn events_from_memory_engine() {
+        let cleaners = RegexCleanerPair::prep_list(&[("&", " and ")]).unwrap();
+        let document = Document::new("cats&dogs run".to_string(), &cleaners);
+        assert_eq!(document.text().as_string(), "cats and dogs run");
+        let mut engine = MemoryEngine::new(vec![]);
+        let mut events: SpeechEvents = SpeechEvents::default();
+        events.set_mark("run", 14);
+
+        engine.speak(document.text().clone());
+        assert_eq!(events.observe(&engine.get_status(), &document), vec![]);
+        engine.emit_word_boundary();
+        assert_eq!(
+            events.observe(&engine.get_status(), &document),
+            vec![
+                SpeechEvent::Start,
+                SpeechEvent::Sentence(Span {
+                    cleaned: 0..17,
+                    original: 0..13
+                }),
+                SpeechEvent::Word(Span {
+                    cleaned: 0..4,
+                    original: 0..4
+                }),
+            ]
+        );
+        // the same word again is nothing new
+        assert_eq!(events.observe(&engine.get_status(), &document), vec![]);
+        engine.emit_word_boundary();
+        // "and" was "&"
+        assert_eq!(
+            events.observe(&engine.get_status(), &document),
+            vec![SpeechEvent::Word(Span {
+                cleaned: 5..8,
+                original: 4..5
+            })]
+        );
+        engine.emit_word_boundary();
+        events.observe(&engine.get_status(), &document);
+        engine.emit_word_boundary();
+        assert_eq!(
+            events.observe(&engine.get_status(), &document),
+            vec![
+                SpeechEvent::Word(Span {
+                    cleaned: 14..17,
+                    original: 10..13
+                }),
+                SpeechEvent::Bookmark {
+                    name: "run".to_string(),
+                    at: Span {
+                        cleaned: 14..14,
+                        original: 10..10
+                    }
+                },
+            ]
+        );
+        engine.emit_word_boundary();
+        assert_eq!(
+            events.observe(&engine.get_status(), &document),
+            vec![SpeechEvent::End]
+        );
+        assert_eq!(events.observe(&engine.get_status(), &document), vec![]);
+    }
+
+    #[test]
+    fn subscribers_follow_along() {
+        let document = Document::new("one two three".to_string(), &[]);
+        let mut engine = MemoryEngine::new(vec![]);
+        let mut heard = Heard::default();
+        let id = heard
+            .events
+            .subscribe(&[EventKind::Word], |e, heard: &mut Heard| {
+                heard.words.push(e.clone())
+            });
+        // subscribing from a subscriber is heard from the next event on
+        heard
+            .events
+            .subscribe(&[EventKind::End], |_, heard: &mut Heard| {
+                heard
+                    .events
+                    .subscribe(&[EventKind::Word], |_, _| unreachable!());
+            });
+        engine.speak(document.text().clone());
+        let mut step = |heard: &mut Heard| {
+            engine.emit_word_boundary();
+            for event in heard.events.observe(&engine.get_status(), &document) {
+                SpeechEvents::notify(heard, |heard| &mut heard.events, &event);
+            }
+        };
+        step(&mut heard);
+        step(&mut heard);
+        heard.events.unsubscribe(id);
+        step(&mut heard);
+        step(&mut heard);
+        assert_eq!(
+            heard.words,
+            vec![
+                SpeechEvent::Word(Span {
+                    cleaned: 0..3,
+                    original: 0..3
+                }),
+                SpeechEvent::Word(Span {
+                    cleaned: 4..7,
+                    original: 4..7
+                }),
+            ]
+        );
+        assert_eq!(heard.events.subscribers.len(), 2);
+    }
+}
@@ -0,0 +1,220 @@
+use crate::actions::Action;
+use crate::copy_profile::CopyProfile;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Somewhere to get text to read from.
+pub trait TextSource {
+    /// The text, or why there is none in words to say.
+    fn get_text(&mut self) -> Result<String, String>;
+}
+
+/// Which `TextSource` an action reads from, as written in the settings.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceSetting {
+    /// Copy what is selected in the program in front, then put the clipboard back.
+    Selection,
+    /// What is already on the clipboard, without copying.
+    Clipboard,
+    File(PathBuf),
+    /// Only read from the command line, a hotkey would wait on the message loop for it.
+    Stdin,
+}
+
+impl SourceSetting {
+    /// `profiles` say how to copy from each program.
+    pub fn open(&self, profiles: &[CopyProfile]) -> Box<dyn TextSource> {
+        match self {
+            #[cfg(windows)]
+            SourceSetting::Selection => Box::new(CopySelection(profiles.to_vec())),
+            #[cfg(windows)]
+            SourceSetting::Clipboard => Box::new(CurrentClipboard),
+            #[cfg(not(windows))]
+            SourceSetting::Selection | SourceSetting::Clipboard => {
+                let _ = profiles;
+                Box::new(Unavailable(format!("{:?} is only on Windows.", self)))
+            }
+            SourceSetting::File(path) => Box::new(FileSource(path.clone())),
+            SourceSetting::Stdin => Box::new(Unavailable(
+                "stdin is only read from the command line.".to_string(),
+            )),
+        }
+    }
+}
+
+/// The source of each action that reads something.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct TextSourceSettings {
+    pub read: SourceSetting,
+    pub enqueue: SourceSetting,
+    pub export_wav: SourceSetting,
+    pub read_clipboard: SourceSetting,
+}
+
+impl Default for TextSourceSettings {
+    fn default() -> TextSourceSettings {
+        TextSourceSettings {
+            read: SourceSetting::Selection,
+            enqueue: SourceSetting::Selection,
+            export_wav: SourceSetting::Selection,
+            read_clipboard: SourceSetting::Clipboard,
+        }
+    }
+}
+
+impl TextSourceSettings {
+    /// A source for each action that reads something.
+    pub fn open(&self, profiles: &[CopyProfile]) -> BTreeMap<Action, Box<dyn TextSource>> {
+        [
+            (Action::Read, &self.read),
+            (Action::Enqueue, &self.enqueue),
+            (Action::ExportWav, &self.export_wav),
+            (Action::ReadClipboard, &self.read_clipboard),
+        ]
+        .into_iter()
+        .map(|(act, setting)| (act, setting.open(profiles)))
+        .collect()
+    }
+}
+
+/// Copies with the first of its profiles for the program in front.
+#[cfg(windows)]
+pub struct CopySelection(pub Vec<CopyProfile>);
+
+#[cfg(windows)]
+impl TextSource for CopySelection {
+    fn get_text(&mut self) -> Result<String, String> {
+        let (exe, class) = crate::clipboard::foreground_program();
+        let profile = CopyProfile::find(&self.0, exe.as_deref(), class.as_deref());
+        crate::clipboard::get_text(&profile).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(windows)]
+pub struct CurrentClipboard;
+
+#[cfg(windows)]
+impl TextSource for CurrentClipboard {
+    fn get_text(&mut self) -> Result<String, String> {
+        crate::clipboard::what_on_get_clipboard_text(6)
+            .map_err(|_| "could not read the clipboard.".to_string())
+    }
+}
+
+/// The text of a file, HTML files are read the way copied HTML is.
+pub struct FileSource(pub PathBuf);
+
+impl TextSource for FileSource {
+    fn get_text(&mut self) -> Result<String, String> {
+        read_file(&self.0)
+    }
+}
+
+pub fn read_file(path: &Path) -> Result<String, String> {
+    let is_html = path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("html") || e.eq_ignore_ascii_case("htm"));
+    match std::fs::read_to_string(path) {
+        Ok(s) if is_html => Ok(crate::html::html_to_speech(&s)),
+        Ok(s) => Ok(s),
+        Err(e) => Err(format!("could not read {}: {}.", path.display(), e)),
+    }
+}
+
+/// Everything on stdin, the first time.
+pub struct StdinSource;
+
+impl TextSource for StdinSource {
+    fn get_text(&mut self) -> Result<String, String> {
+        let mut text = String::new();
+        std::io::stdin()
+            .read_to_string(&mut text)
+            .map_err(|e| format!("could not read stdin: {}.", e))?;
+        Ok(text)
+    }
+}
+
+/// A source that can not be used here.
+pub struct Unavailable(pub String);
+
+impl TextSource for Unavailable {
+    fn get_text(&mut self) -> Result<String, String> {
+        Err(self.0.clone())
+    }
+}
+
+/// Gives out what it was made with, one each time, then errors.
+#[cfg(test)]
+pub struct ScriptedSource(pub std::collections::VecDeque<Result<String, String>>);
+
+#[cfg(test)]
+impl ScriptedSource {
+    pub fn new<I: IntoIterator<Item = Result<String, String>>>(script: I) -> ScriptedSource {
+        ScriptedSource(script.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+impl TextSource for ScriptedSource {
+    fn get_text(&mut self) -> Result<String, String> {
+        self.0
+            .pop_front()
+            .unwrap_or_else(|| Err("script ran out".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings() {
+        let settings: TextSourceSettings =
+            serde_json::from_str(r#"{"read_clipboard": {"file": "notes.txt"}}"#).unwrap();
+        assert_eq!(settings.read, SourceSetting::Selection);
+        assert_eq!(
+            settings.read_clipboard,
+            SourceSetting::File("notes.txt".into())
+        );
+        let sources = settings.open(&[]);
+        assert_eq!(sources.len(), 4);
+        assert!(sources.contains_key(&Action::ReadClipboard));
+    }
+
+    #[test]
+    fn no_stdin_for_hotkeys() {
+        let settings: TextSourceSettings = serde_json::from_str(r#"{"read": "stdin"}"#).unwrap();
+        assert_eq!(settings.read, SourceSetting::Stdin);
+        let mut sources = settings.open(&[]);
+        let read = sources.get_mut(&Action::Read).unwrap();
+        assert_eq!(
+            read.get_text(),
+            Err("stdin is only read from the command line.".to_string())
+        );
+    }
+
+    #[test]
+    fn files() {
+        let dir = std::env::temp_dir().join("rust_reader_text_source_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let html = dir.join("page.HTML");
+        std::fs::write(&html, "<h1>Title</h1>").unwrap();
+        assert_eq!(
+            FileSource(html).get_text(),
+            Ok("Heading 1: Title.".to_string())
+        );
+        assert!(FileSource(dir.join("missing.txt")).get_text().is_err());
+    }
+
+    #[test]
+    fn scripted() {
+        let mut source = ScriptedSource::new([Ok("one".to_string()), Err("two".to_string())]);
+        assert_eq!(source.get_text(), Ok("one".to_string()));
+        assert_eq!(source.get_text(), Err("two".to_string()));
+        assert!(source.get_text().is_err());
+    }
+}
@@ -0,0 +1,117 @@
+use std::ops::Range;
+
+use rust_reader::wide_string::*;
+use serde::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// How far to look for the context words, in utf16 units per word.
+const MAX_WORD_LEN: usize = 40;
+
+/// How the ticker tape looks and where it is.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct TickerSettings {
+    pub font: String,
+    /// The height of the letters in pixels.
+    pub size: i32,
+    pub left: i32,
+    pub top: i32,
+    pub width: i32,
+    pub height: i32,
+    /// How many words to show on each side of the one being spoken.
+    pub context_words: usize,
+    /// As red, green, blue.
+    pub text_color: [u8; 3],
+    pub background_color: [u8; 3],
+}
+
+impl Default for TickerSettings {
+    fn default() -> TickerSettings {
+        TickerSettings {
+            font: "Segoe UI".to_string(),
+            size: 32,
+            left: 0,
+            top: 0,
+            width: 800,
+            height: 48,
+            context_words: 6,
+            text_color: [255, 255, 255],
+            background_color: [0, 0, 0],
+        }
+    }
+}
+
+/// The words before `word`, the word itself, and the words after it.
+pub fn ticker_parts(
+    text: &WideString,
+    word: &Range<usize>,
+    context_words: usize,
+) -> (String, String, String) {
+    let len = text.len() - 1;
+    let word = word.start.min(len)..word.end.min(len);
+    let reach = (context_words + 1) * MAX_WORD_LEN;
+    let before = text.get_slice(word.start.saturating_sub(reach)..word.start);
+    let after = text.get_slice(word.end..(word.end + reach).min(len));
+
+    let starts: Vec<usize> = words(&before).map(|(i, _)| i).collect();
+    let before = match starts.len().checked_sub(context_words) {
+        _ if context_words == 0 => "",
+        Some(i) => &before[starts[i]..],
+        None => before.as_str(),
+    };
+    let end = words(&after)
+        .nth(context_words.saturating_sub(1))
+        .filter(|_| context_words > 0)
+        .map_or(0, |(i, w)| i + w.len());
+    let after = if context_words == 0 {
+        ""
+    } else if end == 0 {
+        after.as_str()
+    } else {
+        &after[..end]
+    };
+    (
+        before.trim_start().to_string(),
+        text.get_slice(word),
+        after.trim_end().to_string(),
+    )
+}
+
+fn words(s: &str) -> impl Iterator<Item = (usize, &str)> {
+    s.split_word_bound_indices()
+        .filter(|(_, w)| w.chars().any(char::is_alphanumeric))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn words_around() {
+        let text: WideString = "One two, three four five. Six".into();
+        assert_eq!(
+            ticker_parts(&text, &(9..14), 2),
+            (
+                "One two, ".to_string(),
+                "three".to_string(),
+                " four five".to_string()
+            )
+        );
+        assert_eq!(
+            ticker_parts(&text, &(9..14), 1),
+            (
+                "two, ".to_string(),
+                "three".to_string(),
+                " four".to_string()
+            )
+        );
+        assert_eq!(
+            ticker_parts(&text, &(26..29), 2),
+            ("four five. ".to_string(), "Six".to_string(), "".to_string())
+        );
+        assert_eq!(
+            ticker_parts(&text, &(0..3), 0),
+            ("".to_string(), "One".to_string(), "".to_string())
+        );
+    }
+}
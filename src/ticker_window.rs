@@ -0,0 +1,191 @@
+use std::ops::Range;
+
+use crate::ticker::*;
+use crate::window::*;
+use windows::core::PCWSTR;
+use windows::w;
+use windows::Win32::{
+    Foundation::{COLORREF, HINSTANCE, HWND, LPARAM, LRESULT, SIZE, WPARAM},
+    Graphics::Gdi,
+    UI::WindowsAndMessaging as wm,
+};
+
+/// A strip across the screen showing the word being read, with the words around it.
+pub struct TickerWindow {
+    window: HWND,
+    font: Gdi::HFONT,
+    settings: TickerSettings,
+    /// The words before, the word being read, and the words after, without nulls.
+    parts: [Vec<u16>; 3],
+}
+
+impl TickerWindow {
+    pub fn new(settings: &TickerSettings) -> Box<TickerWindow> {
+        let mut out = Box::new(TickerWindow {
+            window: HWND(0),
+            font: Gdi::HFONT(0),
+            settings: settings.clone(),
+            parts: Default::default(),
+        });
+
+        let window_class_name = w!("ticker_window_class_name");
+        unsafe {
+            wm::RegisterClassW(&wm::WNDCLASSW {
+                style: wm::CS_HREDRAW | wm::CS_VREDRAW,
+                lpfnWndProc: Some(window_proc_generic::<TickerWindow>),
+                cbClsExtra: 0,
+                cbWndExtra: 0,
+                hInstance: HINSTANCE(0),
+                hIcon: wm::HICON(0),
+                hCursor: wm::LoadCursorW(HINSTANCE(0), wm::IDC_ARROW)
+                    .expect("failed to load cursor"),
+                hbrBackground: Gdi::HBRUSH(0),
+                lpszMenuName: PCWSTR::null(),
+                lpszClassName: window_class_name,
+            });
+            out.window = wm::CreateWindowExW(
+                // never takes the focus from the text being read, and not in the taskbar
+                wm::WS_EX_NOACTIVATE | wm::WS_EX_TOPMOST | wm::WS_EX_TOOLWINDOW,
+                window_class_name,
+                w!("reader ticker"),
+                wm::WS_POPUP,
+                0,
+                0,
+                0,
+                0,
+                wm::GetDesktopWindow(),
+                wm::HMENU(0),
+                HINSTANCE(0),
+                Some(&mut *out as *mut _ as *mut _),
+            );
+        }
+        out.set_settings(settings);
+        out
+    }
+
+    pub fn set_settings(&mut self, settings: &TickerSettings) {
+        self.settings = settings.clone();
+        let face: WideString = settings.font.as_str().into();
+        unsafe {
+            if self.font.0 != 0 {
+                Gdi::DeleteObject(self.font);
+            }
+            self.font = Gdi::CreateFontW(
+                settings.size,
+                0,
+                0,
+                0,
+                Gdi::FW_NORMAL.0 as i32,
+                0,
+                0,
+                0,
+                Gdi::DEFAULT_CHARSET.0 as u32,
+                Gdi::OUT_DEFAULT_PRECIS.0 as u32,
+                Gdi::CLIP_DEFAULT_PRECIS.0 as u32,
+                Gdi::CLEARTYPE_QUALITY.0 as u32,
+                (Gdi::DEFAULT_PITCH.0 | Gdi::FF_SWISS.0) as u32,
+                PCWSTR::from_raw(face.as_ptr()),
+            );
+            // HWND_TOPMOST sets window to always be on top
+            wm::SetWindowPos(
+                self.window,
+                wm::HWND_TOPMOST,
+                settings.left,
+                settings.top,
+                settings.width,
+                settings.height,
+                wm::SWP_NOACTIVATE,
+            );
+        }
+        self.redraw();
+    }
+
+    /// Show `word` of the cleaned `text` being read.
+    pub fn show_word(&mut self, text: &WideString, word: &Range<usize>) {
+        let (before, word, after) = ticker_parts(text, word, self.settings.context_words);
+        self.parts = [before, word, after].map(|s| s.encode_utf16().collect());
+        if is_window_visible(self.window) {
+            self.redraw();
+        }
+    }
+
+    pub fn toggle_window_visible(&mut self) -> bool {
+        if is_window_visible(self.window) {
+            show_window(self.window, wm::SW_HIDE)
+        } else {
+            self.redraw();
+            show_window(self.window, wm::SW_SHOWNOACTIVATE)
+        }
+    }
+
+    fn redraw(&self) {
+        unsafe { Gdi::InvalidateRect(self.window, None, false) };
+    }
+
+    fn paint(&self) {
+        let mut ps = Gdi::PAINTSTRUCT::default();
+        let rect = get_client_rect(self.window);
+        let text = color(self.settings.text_color);
+        let background = color(self.settings.background_color);
+        unsafe {
+            let hdc = Gdi::BeginPaint(self.window, &mut ps);
+            let brush = Gdi::CreateSolidBrush(background);
+            Gdi::FillRect(hdc, &rect, brush);
+            Gdi::DeleteObject(brush);
+            let old_font = Gdi::SelectObject(hdc, self.font);
+            Gdi::SetBkMode(hdc, Gdi::OPAQUE);
+
+            let width = |s: &[u16]| {
+                let mut size = SIZE::default();
+                Gdi::GetTextExtentPoint32W(hdc, s, &mut size);
+                size
+            };
+            let [before, word, after] = &self.parts;
+            let word_size = width(word);
+            // the word being read stays in the middle, the rest flows past it
+            let x = (rect.right - word_size.cx) / 2;
+            let y = (rect.bottom - word_size.cy) / 2;
+            Gdi::SetTextColor(hdc, text);
+            Gdi::SetBkColor(hdc, background);
+            Gdi::TextOutW(hdc, x - width(before).cx, y, before);
+            Gdi::TextOutW(hdc, x + word_size.cx, y, after);
+            Gdi::SetTextColor(hdc, background);
+            Gdi::SetBkColor(hdc, text);
+            Gdi::TextOutW(hdc, x, y, word);
+
+            Gdi::SelectObject(hdc, old_font);
+            Gdi::EndPaint(self.window, &ps);
+        }
+    }
+}
+
+fn color([r, g, b]: [u8; 3]) -> COLORREF {
+    COLORREF(u32::from(r) | u32::from(g) << 8 | u32::from(b) << 16)
+}
+
+impl Drop for TickerWindow {
+    fn drop(&mut self) {
+        destroy_window(self.window);
+        unsafe { Gdi::DeleteObject(self.font) };
+    }
+}
+
+impl Windowed for TickerWindow {
+    fn window_proc(&mut self, msg: u32, _w_param: WPARAM, _l_param: LPARAM) -> Option<LRESULT> {
+        match msg {
+            wm::WM_PAINT => {
+                self.paint();
+                return Some(LRESULT(0));
+            }
+            wm::WM_ERASEBKGND => {
+                // all painted in WM_PAINT, so it does not flicker
+                return Some(LRESULT(1));
+            }
+            wm::WM_MOUSEACTIVATE => {
+                return Some(LRESULT(wm::MA_NOACTIVATE as isize));
+            }
+            _ => {}
+        }
+        None
+    }
+}
@@ -0,0 +1,410 @@
+use average::Variance;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Words further apart than this were not read one after the other, the reader jumped.
+const MAX_WORD_GAP: usize = 20;
+/// About 170 words per minute, for when there is nothing learned yet at a rate.
+const DEFAULT_US_PER_UTF16: f64 = 60_000.0;
+/// No voice is this slow, the reader was stopped in the debugger or the machine slept.
+const MAX_US_PER_UTF16: f64 = 1_000_000.0;
+/// How much each new reading counts once there are enough of them,
+/// so the last hundred or so readings matter the most.
+const DECAY: f64 = 0.02;
+/// Readings before there are this many are never outliers.
+const MIN_SAMPLES: u64 = 10;
+/// A reading this many standard deviations slower than the mean is a pause, not speech.
+const OUTLIER_SDS: f64 = 4.0;
+/// For a 90% confidence interval.
+const CONFIDENCE_Z: f64 = 1.645;
+/// Where the speeds from before they were kept by voice go.
+const ANY_VOICE: &str = "";
+
+/// Where the time comes from, so tests can make it up.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock that only moves when told to.
+#[cfg(test)]
+pub struct ManualClock(std::sync::Mutex<Instant>);
+
+#[cfg(test)]
+impl ManualClock {
+    pub fn new() -> Arc<ManualClock> {
+        Arc::new(ManualClock(std::sync::Mutex::new(Instant::now())))
+    }
+
+    pub fn advance(&self, d: Duration) {
+        *self.0.lock().unwrap() += d;
+    }
+}
+
+#[cfg(test)]
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        *self.0.lock().unwrap()
+    }
+}
+
+/// An exponentially weighted mean and variance of microseconds per utf16 unit.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct Speed {
+    pub mean: f64,
+    pub variance: f64,
+    /// How many readings were added, however long ago.
+    pub samples: u64,
+}
+
+impl Speed {
+    fn add(&mut self, x: f64) {
+        // a plain average until there are enough readings for the decay to take over
+        let alpha = (1.0 / (self.samples + 1) as f64).max(DECAY);
+        let diff = x - self.mean;
+        let incr = alpha * diff;
+        self.mean += incr;
+        self.variance = (1.0 - alpha) * (self.variance + diff * incr);
+        self.samples += 1;
+    }
+
+    fn is_outlier(&self, x: f64) -> bool {
+        // at least twice the mean, a steady voice has almost no variance
+        self.samples >= MIN_SAMPLES
+            && x > self.mean + (OUTLIER_SDS * self.variance.sqrt()).max(self.mean)
+    }
+}
+
+/// How long reading some text will take.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Estimate {
+    pub expected: chrono::Duration,
+    /// The 90% confidence interval.
+    pub low: chrono::Duration,
+    pub high: chrono::Duration,
+}
+
+/// Learns how fast each voice reads at each rate from the time between word boundaries.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(from = "SavedEstimator", into = "SavedEstimator")]
+pub struct TimeEstimator {
+    clock: Arc<dyn Clock>,
+    voices: BTreeMap<String, BTreeMap<i32, Speed>>,
+    last_boundary: Option<(Instant, Range<usize>)>,
+}
+
+impl TimeEstimator {
+    pub fn with_clock(clock: Arc<dyn Clock>) -> TimeEstimator {
+        TimeEstimator {
+            clock,
+            voices: BTreeMap::new(),
+            last_boundary: None,
+        }
+    }
+
+    /// What was learned in `other`, with the clock of `self`.
+    pub fn load(&mut self, other: &TimeEstimator) {
+        self.voices = other.voices.clone();
+        self.last_boundary = None;
+    }
+
+    /// Forget the last word boundary, so the time between now and the next one is not counted.
+    pub fn reset(&mut self) {
+        self.last_boundary = None;
+    }
+
+    /// The voice started reading `word`, returns the time since the last boundary.
+    ///
+    /// Only words read one after the other are learned from, not a jump.
+    pub fn boundary(&mut self, voice: &str, rate: i32, word: &Range<usize>) -> Duration {
+        let now = self.clock.now();
+        match self.last_boundary.replace((now, word.clone())) {
+            Some((then, last)) => {
+                let since = now.saturating_duration_since(then);
+                if last.start < word.start && word.start <= last.end + MAX_WORD_GAP {
+                    let utf16 = (word.start - last.start) as f64;
+                    self.learn(voice, rate, since.as_micros() as f64 / utf16);
+                }
+                since
+            }
+            None => Duration::ZERO,
+        }
+    }
+
+    /// Add a reading, returns false if it was thrown out as a pause.
+    pub fn learn(&mut self, voice: &str, rate: i32, us_per_utf16: f64) -> bool {
+        if !(us_per_utf16 > 0.0 && us_per_utf16 < MAX_US_PER_UTF16) {
+            return false;
+        }
+        let speed = self
+            .voices
+            .entry(voice.to_string())
+            .or_default()
+            .entry(rate)
+            .or_default();
+        if speed.is_outlier(us_per_utf16) {
+            return false;
+        }
+        speed.add(us_per_utf16);
+        true
+    }
+
+    /// What was learned for `voice` at `rate`, or before voices were told apart.
+    pub fn speed(&self, voice: &str, rate: i32) -> Option<&Speed> {
+        [voice, ANY_VOICE]
+            .iter()
+            .filter_map(|v| self.voices.get(*v)?.get(&rate))
+            .find(|s| s.samples > 0)
+    }
+
+    fn mean_variance(&self, voice: &str, rate: i32) -> (f64, f64) {
+        match self.speed(voice, rate) {
+            Some(s) => (s.mean, s.variance),
+            None => (DEFAULT_US_PER_UTF16 / 3f64.powf(rate as f64 / 10.0), 0.0),
+        }
+    }
+
+    /// How long it will take to read `len` utf16 units.
+    pub fn estimate(&self, voice: &str, rate: i32, len: usize) -> Estimate {
+        let (mean, variance) = self.mean_variance(voice, rate);
+        let len = len as f64;
+        let expected = len * mean;
+        let margin = CONFIDENCE_Z * (len * variance).sqrt();
+        let us = |us: f64| chrono::Duration::microseconds(us.max(0.0) as i64);
+        Estimate {
+            expected: us(expected),
+            low: us(expected - margin),
+            high: us(expected + margin),
+        }
+    }
+
+    /// About how many utf16 units will be read in `d`.
+    pub fn utf16_in(&self, voice: &str, rate: i32, d: chrono::Duration) -> usize {
+        let (mean, _) = self.mean_variance(voice, rate);
+        (d.num_microseconds().unwrap_or(i64::MAX) as f64 / mean) as usize
+    }
+}
+
+impl Default for TimeEstimator {
+    fn default() -> TimeEstimator {
+        TimeEstimator::with_clock(Arc::new(SystemClock))
+    }
+}
+
+impl fmt::Debug for TimeEstimator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TimeEstimator")
+            .field("voices", &self.voices)
+            .finish()
+    }
+}
+
+/// How a `TimeEstimator` is kept in the settings file.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum SavedEstimator {
+    Versioned(Versioned),
+    /// Before there was a version, one for each rate from -10 and not by voice.
+    Legacy(Box<[Variance; 21]>),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "version")]
+enum Versioned {
+    #[serde(rename = "1")]
+    V1 { speeds: Vec<SavedSpeed> },
+}
+
+/// A list and not a map, untagged enums can not read numbers as map keys.
+#[derive(Serialize, Deserialize)]
+struct SavedSpeed {
+    voice: String,
+    rate: i32,
+    speed: Speed,
+}
+
+impl From<SavedEstimator> for TimeEstimator {
+    fn from(saved: SavedEstimator) -> TimeEstimator {
+        let mut out = TimeEstimator::default();
+        match saved {
+            SavedEstimator::Versioned(Versioned::V1 { speeds }) => {
+                for s in speeds {
+                    out.voices
+                        .entry(s.voice)
+                        .or_default()
+                        .insert(s.rate, s.speed);
+                }
+            }
+            SavedEstimator::Legacy(rates) => {
+                let speeds = rates
+                    .iter()
+                    .zip(-10..)
+                    .filter(|(v, _)| !v.is_empty())
+                    .map(|(v, rate)| {
+                        let variance = if v.len() > 1 {
+                            v.sample_variance()
+                        } else {
+                            0.0
+                        };
+                        let speed = Speed {
+                            mean: v.mean(),
+                            variance,
+                            samples: v.len(),
+                        };
+                        (rate, speed)
+                    })
+                    .collect();
+                out.voices.insert(ANY_VOICE.to_string(), speeds);
+            }
+        }
+        out
+    }
+}
+
+impl From<TimeEstimator> for SavedEstimator {
+    fn from(t: TimeEstimator) -> SavedEstimator {
+        let speeds = t
+            .voices
+            .into_iter()
+            .flat_map(|(voice, rates)| {
+                rates.into_iter().map(move |(rate, speed)| SavedSpeed {
+                    voice: voice.clone(),
+                    rate,
+                    speed,
+                })
+            })
+            .collect();
+        SavedEstimator::Versioned(Versioned::V1 { speeds })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use average::Estimate as _;
+
+    const VOICE: &str = "voice";
+
+    fn read_words(t: &mut TimeEstimator, clock: &ManualClock, starts: &[usize], ms: u64) {
+        for &start in starts {
+            clock.advance(Duration::from_millis(ms));
+            t.boundary(VOICE, 0, &(start..start + 4));
+        }
+    }
+
+    #[test]
+    fn learns_from_boundaries() {
+        let clock = ManualClock::new();
+        let mut t = TimeEstimator::with_clock(clock.clone());
+        // the first boundary has nothing to measure from
+        read_words(&mut t, &clock, &[0, 5, 10, 15], 250);
+        let speed = t.speed(VOICE, 0).unwrap();
+        assert_eq!(speed.samples, 3);
+        assert_eq!(speed.mean, 50_000.0);
+        assert_eq!(speed.variance, 0.0);
+        // jumps are not learned
+        read_words(&mut t, &clock, &[200, 5], 250);
+        assert_eq!(t.speed(VOICE, 0).unwrap().samples, 3);
+        // neither is the time before a reset
+        t.reset();
+        read_words(&mut t, &clock, &[10], 250);
+        assert_eq!(t.speed(VOICE, 0).unwrap().samples, 3);
+    }
+
+    #[test]
+    fn by_voice_and_rate() {
+        let mut t = TimeEstimator::default();
+        t.learn(VOICE, 0, 50_000.0);
+        t.learn(VOICE, 5, 30_000.0);
+        t.learn("other", 0, 70_000.0);
+        assert_eq!(t.speed(VOICE, 0).unwrap().mean, 50_000.0);
+        assert_eq!(t.speed(VOICE, 5).unwrap().mean, 30_000.0);
+        assert_eq!(t.speed("other", 0).unwrap().mean, 70_000.0);
+        assert_eq!(t.speed("other", 5), None);
+        assert_eq!(
+            t.estimate(VOICE, 0, 20).expected,
+            chrono::Duration::seconds(1)
+        );
+        // nothing learned, so a guess
+        assert_eq!(
+            t.estimate("other", 10, 30).expected,
+            chrono::Duration::microseconds(600_000)
+        );
+    }
+
+    #[test]
+    fn recent_readings_count_more() {
+        let mut t = TimeEstimator::default();
+        for _ in 0..200 {
+            t.learn(VOICE, 0, 50_000.0);
+        }
+        for _ in 0..100 {
+            t.learn(VOICE, 0, 60_000.0);
+        }
+        // a plain average would be 53_333
+        let mean = t.speed(VOICE, 0).unwrap().mean;
+        assert!(mean > 58_000.0, "{}", mean);
+    }
+
+    #[test]
+    fn pauses_are_not_learned() {
+        let mut t = TimeEstimator::default();
+        for x in [48_000.0, 52_000.0].iter().cycle().take(20) {
+            assert!(t.learn(VOICE, 0, *x));
+        }
+        assert!(!t.learn(VOICE, 0, 400_000.0));
+        assert!(!t.learn(VOICE, 0, 5_000_000.0));
+        assert!(!t.learn(VOICE, 0, 0.0));
+        assert!(t.learn(VOICE, 0, 70_000.0));
+        assert_eq!(t.speed(VOICE, 0).unwrap().samples, 21);
+    }
+
+    #[test]
+    fn confidence_interval() {
+        let mut t = TimeEstimator::default();
+        for x in [40_000.0, 60_000.0].iter().cycle().take(50) {
+            t.learn(VOICE, 0, *x);
+        }
+        let e = t.estimate(VOICE, 0, 100);
+        assert!(e.low < e.expected && e.expected < e.high, "{:?}", e);
+        // the interval grows slower than the text
+        let e4 = t.estimate(VOICE, 0, 400);
+        assert!(e4.high - e4.expected < (e.high - e.expected) * 3);
+        assert_eq!(t.estimate(VOICE, 0, 0).high, chrono::Duration::zero());
+    }
+
+    #[test]
+    fn settings_file() {
+        let mut t = TimeEstimator::default();
+        t.learn(VOICE, -3, 50_000.0);
+        let json = serde_json::to_string(&t).unwrap();
+        assert!(json.contains("\"version\":\"1\""), "{}", json);
+        let back: TimeEstimator = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.voices, t.voices);
+    }
+
+    #[test]
+    fn migrate_from_one_per_rate() {
+        let mut old: [Variance; 21] = Default::default();
+        old[10].add(50_000.0);
+        old[15].add(30_000.0);
+        let json = serde_json::to_string(&old).unwrap();
+        let t: TimeEstimator = serde_json::from_str(&json).unwrap();
+        // any voice gets them, until it learns its own
+        assert_eq!(t.speed(VOICE, 0).unwrap().mean, 50_000.0);
+        assert_eq!(t.speed(VOICE, 5).unwrap().samples, 1);
+        assert_eq!(t.speed(VOICE, 1), None);
+    }
+}
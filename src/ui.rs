@@ -0,0 +1,78 @@
+use std::ops::Range;
+use std::time::Instant;
+
+use crate::history::History;
+use crate::session::Placement;
+use crate::settings::Settings;
+use crate::speak_on_copy::ClipboardChange;
+use crate::ticker::TickerSettings;
+use rust_reader::clean_text::Dictionary;
+use rust_reader::wide_string::WideString;
+
+/// How finely the progress bar is divided, for showing and for clicking on it.
+pub const PROGRESS_RANGE: i32 = 10_000;
+
+/// Where a document is shown, a `ReaderWindow` on Windows.
+pub trait DocumentView {
+    /// Tells the views apart in the messages they send about themselves.
+    fn id(&self) -> usize;
+    fn set_text(&mut self, text: &WideString);
+    fn set_rate(&mut self, rate: i32);
+    /// Show the progress as the title.
+    fn set_title(&mut self, title: &WideString);
+    /// Highlight the word being read.
+    fn select(&mut self, range: &Range<usize>);
+    /// Fill the progress bar to `percent`, with `text` next to it.
+    fn set_progress(&mut self, percent: f64, text: &str);
+    /// List what is queued to be read next.
+    fn set_queue(&mut self, items: &[String]);
+    fn placement(&self) -> Placement;
+    fn set_placement(&mut self, placement: &Placement);
+    /// When the user last switched to this view, `None` if they never have.
+    fn last_activated(&self) -> Option<Instant>;
+    fn is_visible(&self) -> bool;
+    fn set_visible(&mut self, visible: bool);
+}
+
+/// Everything else the reader shows or listens to, and the settings and history it edits.
+///
+/// `State` only talks to the user through this, so that it can be tested without windows.
+pub trait Ui {
+    type View: DocumentView;
+    /// A new view for another document.
+    fn new_view(&mut self) -> Box<Self::View>;
+
+    fn settings(&self) -> &Settings;
+    fn settings_mut(&mut self) -> &mut Settings;
+    /// Take in what was edited in the settings window, then save the settings.
+    fn save_settings(&mut self);
+    fn show_settings(&mut self);
+    /// The pronunciations as edited in the settings window.
+    fn edited_dictionary(&self) -> Dictionary;
+    /// Register the hotkeys in the settings, in place of the ones before.
+    fn register_hotkeys(&mut self);
+    /// How each registered hotkey is written, in the order of `ACTION_LIST`.
+    fn hotkey_names(&self) -> Vec<String>;
+
+    fn history(&self) -> &History;
+    /// Remember to call `refresh_history` if the list needs to change.
+    fn history_mut(&mut self) -> &mut History;
+    fn refresh_history(&mut self);
+    fn save_history(&mut self);
+    fn show_history(&mut self);
+
+    fn set_ticker_settings(&mut self, settings: &TickerSettings);
+    /// Show `word` of `text` on the ticker, if it is shown.
+    fn show_word(&mut self, text: &WideString, word: &Range<usize>);
+    fn toggle_ticker(&mut self);
+
+    /// Start or stop being told about copies.
+    fn listen_for_copies(&mut self, enabled: bool, debounce_ms: u32);
+    /// What is known about the last change to the clipboard, `None` if it can not be read.
+    fn clipboard_change(&mut self) -> Option<ClipboardChange>;
+    /// The text on the clipboard, `None` if there is none.
+    fn clipboard_text(&mut self) -> Option<String>;
+
+    /// Stop, once whatever is being done is finished.
+    fn quit(&mut self);
+}
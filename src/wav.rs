@@ -0,0 +1,144 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::speech_engine::*;
+use rust_reader::wide_string::WideString;
+
+/// When a word started in a rendered wav file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WordTiming {
+    /// Time from the start of the file.
+    pub ms: u64,
+    /// The word, in utf16 units of the text that was rendered.
+    pub word_range: Range<usize>,
+}
+
+/// Write 16 bit mono PCM as a wav file, for engines that do not write their own.
+/// On Windows SAPI writes its own, so only the test engine uses this.
+#[cfg(any(test, not(windows)))]
+pub fn write_wav<W: Write>(mut out: W, sample_rate: u32, samples: &[i16]) -> io::Result<()> {
+    let data_len = (samples.len() * 2) as u32;
+    out.write_all(b"RIFF")?;
+    out.write_all(&(36 + data_len).to_le_bytes())?;
+    out.write_all(b"WAVEfmt ")?;
+    out.write_all(&16u32.to_le_bytes())?;
+    out.write_all(&1u16.to_le_bytes())?; // PCM
+    out.write_all(&1u16.to_le_bytes())?; // mono
+    out.write_all(&sample_rate.to_le_bytes())?;
+    out.write_all(&(sample_rate * 2).to_le_bytes())?;
+    out.write_all(&2u16.to_le_bytes())?;
+    out.write_all(&16u16.to_le_bytes())?;
+    out.write_all(b"data")?;
+    out.write_all(&data_len.to_le_bytes())?;
+    for s in samples {
+        out.write_all(&s.to_le_bytes())?;
+    }
+    out.flush()
+}
+
+/// The timing sidecar goes next to the wav file, `book.wav` gets `book.tsv`.
+pub fn timings_path(wav: &Path) -> PathBuf {
+    wav.with_extension("tsv")
+}
+
+/// Write one line per word with the time it starts, its range in `text` and the word itself.
+pub fn write_timings<W: Write>(
+    mut out: W,
+    text: &WideString,
+    timings: &[WordTiming],
+) -> io::Result<()> {
+    writeln!(out, "ms\tstart\tend\tword")?;
+    for t in timings {
+        let word = text
+            .get_slice(t.word_range.clone())
+            .replace(char::is_whitespace, " ");
+        writeln!(
+            out,
+            "{}\t{}\t{}\t{}",
+            t.ms, t.word_range.start, t.word_range.end, word
+        )?;
+    }
+    out.flush()
+}
+
+/// Render `text` to `path` with its timing sidecar, returns the path of the sidecar.
+pub fn export<E: SpeechEngine + ?Sized>(
+    engine: &mut E,
+    text: WideString,
+    path: &Path,
+) -> io::Result<PathBuf> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let timings = engine.speak_to_wav(text, path)?;
+    let sidecar = timings_path(path);
+    write_timings(
+        BufWriter::new(File::create(&sidecar)?),
+        engine.last_read(),
+        &timings,
+    )?;
+    Ok(sidecar)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_engine::*;
+
+    #[test]
+    fn wav_header() {
+        let mut out = Vec::new();
+        write_wav(&mut out, 22050, &[0, -1]).unwrap();
+        assert_eq!(out.len(), 44 + 4);
+        assert_eq!(&out[0..4], b"RIFF");
+        assert_eq!(u32::from_le_bytes(out[4..8].try_into().unwrap()), 40);
+        assert_eq!(u32::from_le_bytes(out[24..28].try_into().unwrap()), 22050);
+        assert_eq!(u32::from_le_bytes(out[40..44].try_into().unwrap()), 4);
+        assert_eq!(&out[44..], &[0, 0, 0xff, 0xff]);
+    }
+
+    #[test]
+    fn timings_sidecar() {
+        let text: WideString = "Hello\tbig \u{1d565}world".into();
+        let timings = [
+            WordTiming {
+                ms: 0,
+                word_range: 0..9,
+            },
+            WordTiming {
+                ms: 450,
+                word_range: 10..17,
+            },
+        ];
+        let mut out = Vec::new();
+        write_timings(&mut out, &text, &timings).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "ms\tstart\tend\tword\n0\t0\t9\tHello big\n450\t10\t17\t\u{1d565}world\n"
+        );
+        assert_eq!(
+            timings_path(Path::new("dir/book.wav")),
+            PathBuf::from("dir/book.tsv")
+        );
+    }
+
+    #[test]
+    fn export_from_memory_engine() {
+        let dir = std::env::temp_dir().join("rust_reader_export_test");
+        let path = dir.join("hello.wav");
+        let mut engine = MemoryEngine::new(vec![]);
+        let sidecar = export(&mut *engine, "Hello world".into(), &path).unwrap();
+        assert_eq!(
+            engine.calls,
+            vec![Call::SpeakToWav("Hello world".to_string())]
+        );
+        assert!(path.exists());
+        assert_eq!(
+            std::fs::read_to_string(sidecar).unwrap(),
+            "ms\tstart\tend\tword\n0\t0\t5\tHello\n100\t6\t11\tworld\n"
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
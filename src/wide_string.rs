@@ -1,8 +1,6 @@
-use std::ffi::OsStr;
 use std::ops::Range;
-use std::os::windows::ffi::OsStrExt;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct WideString(Vec<u16>);
 
 impl WideString {
@@ -13,9 +11,13 @@ impl WideString {
         assert_eq!(r.last(), Some(&0));
         WideString(r)
     }
+    /// The length in u16 units, including the terminating null.
     pub fn len(&self) -> usize {
         self.0.len()
     }
+    pub fn is_empty(&self) -> bool {
+        self.0.len() <= 1
+    }
 
     /// Returns a raw pointer to the slice's buffer.
     ///
@@ -35,10 +37,15 @@ impl WideString {
     }
 }
 
+impl Default for WideString {
+    fn default() -> Self {
+        WideString::new()
+    }
+}
+
 impl<T: ::std::borrow::Borrow<str>> From<T> for WideString {
     fn from(instring: T) -> Self {
-        let osstr: &OsStr = instring.borrow().as_ref();
-        let mut out: Vec<u16> = osstr.encode_wide().collect();
+        let mut out: Vec<u16> = instring.borrow().encode_utf16().collect();
         out.push(0);
         WideString::from_raw(out)
     }
@@ -48,8 +55,7 @@ impl<T: ::std::borrow::Borrow<str>> ::std::iter::FromIterator<T> for WideString
     fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
         let mut out: Vec<u16> = Vec::new();
         for i in iter {
-            let instr: &OsStr = i.borrow().as_ref();
-            out.extend(instr.encode_wide());
+            out.extend(i.borrow().encode_utf16());
         }
         out.push(0);
         WideString::from_raw(out)
@@ -104,7 +110,7 @@ impl IndicesUtf for str {
     }
 }
 
-#[allow(dead_code)]
+/// The range of positions in the sorted index `v` that cover `r`.
 pub fn convert_range<T>(v: &[T], r: &Range<T>) -> Range<usize>
 where
     T: Ord,
@@ -114,7 +120,23 @@ where
     lo..(hi - 1)
 }
 
-#[allow(dead_code)]
+/// Like `convert_range`, but an end of `r` inside a segment takes in all of that segment.
+pub fn convert_range_covering<T>(v: &[T], r: &Range<T>) -> Range<usize>
+where
+    T: Ord,
+{
+    let mut lo = v.partition_point(|x| *x < r.start);
+    if lo > 0 && v.get(lo) != Some(&r.start) {
+        lo -= 1;
+    }
+    let mut hi = v.partition_point(|x| *x <= r.end);
+    if 0 < hi && hi < v.len() && v[hi - 1] != r.end {
+        hi += 1;
+    }
+    lo..(hi - 1)
+}
+
+/// The values of the index `v` at the ends of the range `r`.
 pub fn lookup_range<T>(v: &[T], r: &Range<usize>) -> Range<T>
 where
     T: Clone,
@@ -122,7 +144,10 @@ where
     v[r.start].clone()..v[r.end].clone()
 }
 
-#[allow(dead_code)]
+/// Map the range `r` from the index `o` to the matching range in the index `i`.
+///
+/// Like `invert_idx(&clean_text_u16idx_in(..), &clean_text_u16idx_out(..), &word)`
+/// to find what raw text a word in the cleaned text came from.
 pub fn invert_idx<I, O>(i: &[I], o: &[O], r: &Range<O>) -> Range<I>
 where
     O: Ord,
@@ -132,19 +157,28 @@ where
     lookup_range(i, &convert_range(o, r))
 }
 
-#[allow(dead_code)]
+/// Like `invert_idx`, but part of a segment of `o` maps to all of the segment in `i`.
+///
+/// For the indices of `clean_text_with_dictionary`, where a whole replacement is one segment.
+pub fn invert_idx_covering<I, O>(i: &[I], o: &[O], r: &Range<O>) -> Range<I>
+where
+    O: Ord,
+    I: Clone,
+{
+    assert_eq!(i.len(), o.len());
+    lookup_range(i, &convert_range_covering(o, r))
+}
+
 pub fn str_from_str_u16idx<'a>(s: &'a str, idx: &Range<usize>) -> &'a str {
     &s[u8idx_from_u16idx(s, idx)]
 }
 
-#[allow(dead_code)]
 pub fn u8idx_from_u16idx(s: &str, idx: &Range<usize>) -> Range<usize> {
     let mut u16idx = 0;
     let mut out = 0..0;
     for c in s.chars() {
         if u16idx <= idx.start {
             out.start = out.end;
-            println!("{:?}", out.start);
         }
         out.end += c.len_utf8();
         u16idx += c.len_utf16();
@@ -155,7 +189,6 @@ pub fn u8idx_from_u16idx(s: &str, idx: &Range<usize>) -> Range<usize> {
     out
 }
 
-#[allow(dead_code)]
 pub fn u16idx_from_u8idx(s: &str, idx: Range<usize>) -> Range<usize> {
     let start = s[..idx.start].len_utf16();
     start..start + s[idx].len_utf16()
@@ -225,5 +258,9 @@ mod tests {
         assert_eq!(4..9, invert_idx(&id8, &id16, &(2..5)));
         assert_eq!(4..14, invert_idx(&id8, &id16, &(2..8)));
         assert_eq!(4..19, invert_idx(&id8, &id16, &(2..11)));
+
+        // 1..3 is inside the first character
+        assert_eq!(0..2, invert_idx_covering(&id16, &id8, &(1..3)));
+        assert_eq!(0..5, invert_idx_covering(&id16, &id8, &(1..8)));
     }
 }
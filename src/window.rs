@@ -1,11 +1,15 @@
-use windows::w;
 use windows::core::PCWSTR;
+use windows::w;
 use windows::Win32::{
     Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, RECT, WPARAM},
     System::Console::SetConsoleTitleW,
     System::SystemServices::SS_NOPREFIX,
     UI::{
-        Controls::{EM_SCROLLCARET, EM_SETSEL},
+        Controls::{
+            InitCommonControlsEx, BST_CHECKED, BST_UNCHECKED, EM_SCROLLCARET, EM_SETSEL,
+            ICC_PROGRESS_CLASS, INITCOMMONCONTROLSEX, PBM_SETPOS, PBM_SETRANGE32, PBS_SMOOTH,
+            PROGRESS_CLASSW,
+        },
         Input::KeyboardAndMouse::EnableWindow,
         WindowsAndMessaging as wm,
     },
@@ -14,7 +18,7 @@ use windows::Win32::{
 use std::mem;
 use std::ops::Range;
 
-pub use crate::wide_string::*;
+pub use rust_reader::wide_string::*;
 
 pub fn create_static_window(window_wnd: HWND, name: Option<&WideString>) -> HWND {
     unsafe {
@@ -35,6 +39,56 @@ pub fn create_static_window(window_wnd: HWND, name: Option<&WideString>) -> HWND
     }
 }
 
+/// A progress bar that goes from 0 to `range`.
+pub fn create_progress_window(window_wnd: HWND, range: i32) -> HWND {
+    unsafe {
+        InitCommonControlsEx(&INITCOMMONCONTROLSEX {
+            dwSize: mem::size_of::<INITCOMMONCONTROLSEX>() as u32,
+            dwICC: ICC_PROGRESS_CLASS,
+        });
+        let out = wm::CreateWindowExW(
+            wm::WINDOW_EX_STYLE(0),
+            PROGRESS_CLASSW,
+            PCWSTR(&mut 0u16),
+            wm::WS_CHILD | wm::WS_VISIBLE | wm::WINDOW_STYLE(PBS_SMOOTH),
+            0,
+            0,
+            0,
+            0,
+            window_wnd,
+            wm::HMENU(0),
+            HINSTANCE(0),
+            None,
+        );
+        wm::SendMessageW(out, PBM_SETRANGE32, WPARAM(0), LPARAM(range as isize));
+        out
+    }
+}
+
+pub fn set_progress_position(h_wnd: HWND, pos: i32) -> LRESULT {
+    unsafe { wm::SendMessageW(h_wnd, PBM_SETPOS, WPARAM(pos as usize), LPARAM(0)) }
+}
+
+/// A window that is never shown, only to receive messages.
+pub fn create_message_window() -> HWND {
+    unsafe {
+        wm::CreateWindowExW(
+            wm::WINDOW_EX_STYLE(0),
+            w!("STATIC"),
+            PCWSTR(&mut 0u16),
+            wm::WINDOW_STYLE(0),
+            0,
+            0,
+            0,
+            0,
+            wm::HWND_MESSAGE,
+            wm::HMENU(0),
+            HINSTANCE(0),
+            None,
+        )
+    }
+}
+
 pub fn create_button_window(window_wnd: HWND, name: PCWSTR) -> HWND {
     unsafe {
         wm::CreateWindowExW(
@@ -57,6 +111,38 @@ pub fn create_button_window(window_wnd: HWND, name: PCWSTR) -> HWND {
     }
 }
 
+pub fn create_check_box_window(window_wnd: HWND, name: PCWSTR) -> HWND {
+    unsafe {
+        wm::CreateWindowExW(
+            wm::WINDOW_EX_STYLE(0),
+            w!("BUTTON"),
+            name,
+            wm::WS_TABSTOP
+                | wm::WS_VISIBLE
+                | wm::WS_CHILD
+                | wm::WINDOW_STYLE(wm::BS_AUTOCHECKBOX as u32),
+            0,
+            0,
+            0,
+            0,
+            window_wnd,
+            wm::HMENU(0),
+            HINSTANCE(0),
+            None,
+        )
+    }
+}
+
+pub fn is_checked(h_wnd: HWND) -> bool {
+    let state = unsafe { wm::SendMessageW(h_wnd, wm::BM_GETCHECK, WPARAM(0), LPARAM(0)) };
+    state.0 as u32 == BST_CHECKED.0
+}
+
+pub fn set_checked(h_wnd: HWND, checked: bool) {
+    let state = if checked { BST_CHECKED } else { BST_UNCHECKED };
+    unsafe { wm::SendMessageW(h_wnd, wm::BM_SETCHECK, WPARAM(state.0 as usize), LPARAM(0)) };
+}
+
 pub fn create_edit_window(window_wnd: HWND, style: wm::WINDOW_STYLE) -> HWND {
     // https://msdn.microsoft.com/en-us/library/windows/desktop/hh298433.aspx
     unsafe {
@@ -82,6 +168,45 @@ pub fn create_edit_window(window_wnd: HWND, style: wm::WINDOW_STYLE) -> HWND {
     }
 }
 
+pub fn create_list_window(window_wnd: HWND, style: wm::WINDOW_STYLE) -> HWND {
+    unsafe {
+        wm::CreateWindowExW(
+            wm::WS_EX_CLIENTEDGE,
+            w!("LISTBOX"),
+            PCWSTR(&mut 0u16),
+            wm::WS_CHILD
+                | wm::WS_VSCROLL
+                | wm::WS_BORDER
+                | style
+                | wm::WINDOW_STYLE(wm::LBS_NOINTEGRALHEIGHT as u32),
+            0,
+            0,
+            0,
+            0,
+            window_wnd,
+            wm::HMENU(0),
+            HINSTANCE(0),
+            None,
+        )
+    }
+}
+
+/// Replace all the items of a list box.
+pub fn set_list_items(h_wnd: HWND, items: &[String]) {
+    unsafe {
+        wm::SendMessageW(h_wnd, wm::LB_RESETCONTENT, WPARAM(0), LPARAM(0));
+        for item in items {
+            let item: WideString = item.as_str().into();
+            wm::SendMessageW(
+                h_wnd,
+                wm::LB_ADDSTRING,
+                WPARAM(0),
+                LPARAM(item.as_ptr() as isize),
+            );
+        }
+    }
+}
+
 pub fn get_message() -> Option<wm::MSG> {
     let mut msg: wm::MSG = unsafe { mem::zeroed() };
     if unsafe { wm::GetMessageW(&mut msg, HWND(0), 0, 0) } != true {
@@ -102,6 +227,25 @@ pub fn set_window_text(h_wnd: HWND, wide: &WideString) -> bool {
     unsafe { wm::SetWindowTextW(h_wnd, PCWSTR::from_raw(wide.as_ptr())).into() }
 }
 
+/// Show `text` and wait for the user to close it, for when there is no other window.
+pub fn message_box(title: &str, text: &str, error: bool) {
+    let title: WideString = title.into();
+    let text: WideString = text.into();
+    let icon = if error {
+        wm::MB_ICONERROR
+    } else {
+        wm::MB_ICONINFORMATION
+    };
+    unsafe {
+        wm::MessageBoxW(
+            HWND(0),
+            PCWSTR::from_raw(text.as_ptr()),
+            PCWSTR::from_raw(title.as_ptr()),
+            wm::MB_OK | icon,
+        )
+    };
+}
+
 pub fn get_window_text_length(h_wnd: HWND) -> i32 {
     unsafe { wm::GetWindowTextLengthW(h_wnd) }
 }
@@ -148,6 +292,24 @@ pub fn move_window(h_wnd: HWND, rect: &RECT) -> bool {
     unsafe { wm::MoveWindow(h_wnd, rect.left, rect.top, rect.right, rect.bottom, true).into() }
 }
 
+/// Where the window is when not minimized or maximized.
+/// Unlike `move_window` the `right` and `bottom` are positions, not the width and height.
+pub fn get_normal_position(h_wnd: HWND) -> RECT {
+    let mut placement: wm::WINDOWPLACEMENT = unsafe { mem::zeroed() };
+    placement.length = mem::size_of::<wm::WINDOWPLACEMENT>() as u32;
+    unsafe { wm::GetWindowPlacement(h_wnd, &mut placement) };
+    placement.rcNormalPosition
+}
+
+/// Put the window back where `get_normal_position` found it.
+pub fn set_normal_position(h_wnd: HWND, rect: &RECT, n_cmd_show: wm::SHOW_WINDOW_CMD) -> bool {
+    let mut placement: wm::WINDOWPLACEMENT = unsafe { mem::zeroed() };
+    placement.length = mem::size_of::<wm::WINDOWPLACEMENT>() as u32;
+    placement.showCmd = n_cmd_show;
+    placement.rcNormalPosition = *rect;
+    unsafe { wm::SetWindowPlacement(h_wnd, &placement).into() }
+}
+
 pub fn is_window_visible(h_wnd: HWND) -> bool {
     unsafe { wm::IsWindowVisible(h_wnd).into() }
 }
@@ -0,0 +1,157 @@
+use std::ops::Range;
+
+use crate::actions::ACTION_LIST;
+use crate::clipboard::*;
+use crate::clipboard_listener::ClipboardListener;
+use crate::history::History;
+use crate::history_window::HistoryWindow;
+use crate::hot_key::HotKey;
+use crate::notification_icon::NotificationIcon;
+use crate::reader_window::ReaderWindow;
+use crate::settings::Settings;
+use crate::settings_window::SettingsWindow;
+use crate::speak_on_copy::ClipboardChange;
+use crate::ticker::TickerSettings;
+use crate::ticker_window::TickerWindow;
+use crate::ui::Ui;
+use crate::window::close;
+use rust_reader::clean_text::Dictionary;
+use rust_reader::wide_string::WideString;
+
+/// The windows of the reader, other than the `ReaderWindow` of each document.
+pub struct WindowsUi {
+    settings: Box<SettingsWindow>,
+    history: Box<HistoryWindow>,
+    ticker: Box<TickerWindow>,
+    /// Tells us about copies, when `speak_on_copy` is enabled.
+    clipboard_listener: Box<ClipboardListener>,
+    /// Here and not in a `ReaderWindow`, as any of those can be closed.
+    _icon: Box<NotificationIcon>,
+    /// The hotkey of each action in `ACTION_LIST`, `None` if it could not be registered.
+    hk: Vec<Option<HotKey>>,
+}
+
+impl WindowsUi {
+    pub fn new(settings: Settings, voices: Vec<String>, history: History) -> WindowsUi {
+        let mut out = WindowsUi {
+            ticker: TickerWindow::new(&settings.ticker),
+            clipboard_listener: ClipboardListener::new(),
+            settings: SettingsWindow::new(settings, voices),
+            history: HistoryWindow::new(history),
+            _icon: NotificationIcon::new(),
+            hk: Vec::new(),
+        };
+        out.register_hotkeys();
+        out
+    }
+}
+
+impl Ui for WindowsUi {
+    type View = ReaderWindow;
+
+    fn new_view(&mut self) -> Box<ReaderWindow> {
+        ReaderWindow::new()
+    }
+
+    fn settings(&self) -> &Settings {
+        self.settings.get_inner_settings()
+    }
+
+    fn settings_mut(&mut self) -> &mut Settings {
+        self.settings.get_mut_inner_settings()
+    }
+
+    fn save_settings(&mut self) {
+        self.settings.inner_to_file();
+    }
+
+    fn show_settings(&mut self) {
+        self.settings.show_window();
+    }
+
+    fn edited_dictionary(&self) -> Dictionary {
+        self.settings.get_edited_dictionary()
+    }
+
+    fn register_hotkeys(&mut self) {
+        let hotkeys = &self.settings.get_inner_settings().hotkeys;
+        assert_eq!(ACTION_LIST.len(), hotkeys.len());
+        // the old ones have to go first, or the same keys can not be registered again
+        self.hk.clear();
+        self.hk = ACTION_LIST
+            .iter()
+            .zip(hotkeys.iter())
+            .map(|(&act, &(modifiers, vk))| {
+                let hk = HotKey::new(modifiers, vk, act as i32);
+                if hk.is_none() {
+                    // most likely another program has it, the other hotkeys still work
+                    eprintln!("could not register the hotkey for {}", act);
+                }
+                hk
+            })
+            .collect();
+    }
+
+    fn hotkey_names(&self) -> Vec<String> {
+        self.hk
+            .iter()
+            .map(|h| match h {
+                Some(h) => h.to_string(),
+                None => "taken by another program".to_string(),
+            })
+            .collect()
+    }
+
+    fn history(&self) -> &History {
+        self.history.get_inner_history()
+    }
+
+    fn history_mut(&mut self) -> &mut History {
+        self.history.get_mut_inner_history()
+    }
+
+    fn refresh_history(&mut self) {
+        self.history.refresh();
+    }
+
+    fn save_history(&mut self) {
+        self.history.get_inner_history().to_file();
+    }
+
+    fn show_history(&mut self) {
+        self.history.show_window();
+    }
+
+    fn set_ticker_settings(&mut self, settings: &TickerSettings) {
+        self.ticker.set_settings(settings);
+    }
+
+    fn show_word(&mut self, text: &WideString, word: &Range<usize>) {
+        self.ticker.show_word(text, word);
+    }
+
+    fn toggle_ticker(&mut self) {
+        self.ticker.toggle_window_visible();
+    }
+
+    fn listen_for_copies(&mut self, enabled: bool, debounce_ms: u32) {
+        self.clipboard_listener.listen(enabled, debounce_ms);
+    }
+
+    fn clipboard_change(&mut self) -> Option<ClipboardChange> {
+        Some(ClipboardChange {
+            seq: get_clipboard_seq_num()?.get(),
+            own_seq: own_seq_num(),
+            private: clipboard_is_private(),
+            owner: clipboard_owner_exe(),
+        })
+    }
+
+    fn clipboard_text(&mut self) -> Option<String> {
+        what_on_get_clipboard_text(6).ok()
+    }
+
+    fn quit(&mut self) {
+        close();
+    }
+}